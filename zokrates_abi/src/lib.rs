@@ -34,7 +34,7 @@ impl fmt::Display for Error {
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Value<T> {
     U8(u8),
     U16(u16),
@@ -198,10 +198,10 @@ impl<'ast, T: Field> Flattener<T> {
     ) -> Vec<zir::Parameter<'ast>> {
         let span = p.get_span();
 
-        let private = p.private;
+        let visibility = p.visibility;
         self.fold_variable(zokrates_ast::typed::variable::try_from_g_variable(p.id).unwrap())
             .into_iter()
-            .map(|v| zir::Parameter::new(v, private).span(span))
+            .map(|v| zir::Parameter::new(v, visibility).span(span))
             .collect()
     }
 
@@ -39,7 +39,7 @@ use crate::constant_resolver::ConstantResolver;
 use crate::dead_code::DeadCodeEliminator;
 use crate::expression_validator::ExpressionValidator;
 use crate::panic_extractor::PanicExtractor;
-pub use crate::zir_propagation::ZirPropagator;
+pub use crate::zir_propagation::{Error as ZirPropagationError, ZirPropagator};
 use std::fmt;
 use zokrates_ast::typed::{abi::Abi, TypedProgram};
 use zokrates_ast::zir::ZirProgram;
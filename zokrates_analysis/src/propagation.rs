@@ -14,6 +14,7 @@ use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::ops::*;
 use std::ops::{BitAnd, BitOr, BitXor, Shl, Shr, Sub};
+use zokrates_ast::common::embed::{check_limb_decomposition, check_poseidon_arity};
 use zokrates_ast::common::expressions::{
     BinaryExpression, BinaryOrExpression, EqExpression, ValueExpression,
 };
@@ -348,6 +349,19 @@ impl<'ast, T: Field> ResultFolder<'ast, T> for Propagator<'ast, T> {
                 let assignee = self.fold_assignee(s.assignee)?;
                 let embed_call = self.fold_embed_call(e)?;
 
+                match embed_call.embed {
+                    FlatEmbed::Poseidon => {
+                        check_poseidon_arity::<T>(embed_call.generics[0] as usize)
+                            .map_err(Error::InvalidValue)?
+                    }
+                    FlatEmbed::LimbDecompose => check_limb_decomposition::<T>(
+                        embed_call.generics[0] as usize,
+                        embed_call.generics[1] as usize,
+                    )
+                    .map_err(Error::InvalidValue)?,
+                    _ => {}
+                }
+
                 fn process_u_from_bits<'ast, T: Field>(
                     arguments: &[TypedExpression<'ast, T>],
                     bitwidth: UBitwidth,
@@ -537,6 +551,12 @@ impl<'ast, T: Field> ResultFolder<'ast, T> for Propagator<'ast, T> {
                                     _ => unreachable!("should be a field value"),
                                 }
                             }
+                            FlatEmbed::Keccak256 => Ok(None),
+                            FlatEmbed::Poseidon => Ok(None),
+                            FlatEmbed::LimbDecompose => Ok(None),
+                            FlatEmbed::ConditionalInverse => Ok(None),
+                            FlatEmbed::ScalarMul => Ok(None),
+                            FlatEmbed::EuclideanDiv => Ok(None),
                             #[cfg(feature = "bellman")]
                             FlatEmbed::Sha256Round => Ok(None),
                             #[cfg(feature = "ark")]
@@ -25,6 +25,7 @@ pub enum Error {
     OutOfBounds(usize, usize),
     DivisionByZero,
     AssertionFailed(RuntimeError),
+    StepLimitExceeded(u64),
 }
 
 impl fmt::Display for Error {
@@ -39,6 +40,9 @@ impl fmt::Display for Error {
                 write!(f, "Division by zero detected in zir during static analysis",)
             }
             Error::AssertionFailed(err) => write!(f, "Assertion failed ({})", err),
+            Error::StepLimitExceeded(max_steps) => {
+                write!(f, "Step limit of {} exceeded in zir", max_steps)
+            }
         }
     }
 }
@@ -46,12 +50,40 @@ impl fmt::Display for Error {
 #[derive(Default)]
 pub struct ZirPropagator<'ast, T> {
     constants: Constants<'ast, T>,
+    /// the number of statements and expressions which can be folded before failing, unlimited if `None`
+    max_steps: Option<u64>,
+    steps: u64,
 }
 
 impl<'ast, T: Field> ZirPropagator<'ast, T> {
     pub fn with_constants(constants: Constants<'ast, T>) -> Self {
-        Self { constants }
+        Self {
+            constants,
+            ..Self::default()
+        }
+    }
+
+    /// fail with `Error::StepLimitExceeded` after folding `max_steps` statements and expressions
+    pub fn with_step_limit(self, max_steps: u64) -> Self {
+        Self {
+            max_steps: Some(max_steps),
+            ..self
+        }
+    }
+
+    /// the number of statements and expressions folded so far
+    pub fn steps(&self) -> u64 {
+        self.steps
     }
+
+    fn step(&mut self) -> Result<(), Error> {
+        self.steps += 1;
+        match self.max_steps {
+            Some(max_steps) if self.steps > max_steps => Err(Error::StepLimitExceeded(max_steps)),
+            _ => Ok(()),
+        }
+    }
+
     pub fn propagate(p: ZirProgram<T>) -> Result<ZirProgram<T>, Error> {
         ZirPropagator::default().fold_program(p)
     }
@@ -84,6 +116,14 @@ impl<'ast, T: Field> ResultFolder<'ast, T> for ZirPropagator<'ast, T> {
         })
     }
 
+    fn fold_statement_cases(
+        &mut self,
+        s: ZirStatement<'ast, T>,
+    ) -> Result<Vec<ZirStatement<'ast, T>>, Self::Error> {
+        self.step()?;
+        fold_statement_cases(self, s)
+    }
+
     fn fold_assembly_block(
         &mut self,
         s: zokrates_ast::zir::AssemblyBlockStatement<'ast, T>,
@@ -271,6 +311,7 @@ impl<'ast, T: Field> ResultFolder<'ast, T> for ZirPropagator<'ast, T> {
         &mut self,
         e: FieldElementExpression<'ast, T>,
     ) -> Result<FieldElementExpression<'ast, T>, Self::Error> {
+        self.step()?;
         match e {
             FieldElementExpression::Value(n) => Ok(FieldElementExpression::Value(n)),
             FieldElementExpression::Add(e) => {
@@ -492,6 +533,7 @@ impl<'ast, T: Field> ResultFolder<'ast, T> for ZirPropagator<'ast, T> {
         &mut self,
         e: BooleanExpression<'ast, T>,
     ) -> Result<BooleanExpression<'ast, T>, Error> {
+        self.step()?;
         match e {
             BooleanExpression::Value(v) => Ok(BooleanExpression::Value(v)),
             BooleanExpression::FieldLt(e) => {
@@ -673,6 +715,7 @@ impl<'ast, T: Field> ResultFolder<'ast, T> for ZirPropagator<'ast, T> {
         bitwidth: UBitwidth,
         e: UExpressionInner<'ast, T>,
     ) -> Result<UExpressionInner<'ast, T>, Self::Error> {
+        self.step()?;
         match e {
             UExpressionInner::Value(v) => Ok(UExpressionInner::Value(v)),
             UExpressionInner::Add(e) => {
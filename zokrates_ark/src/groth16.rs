@@ -126,6 +126,8 @@ mod tests {
             module_map: Default::default(),
             arguments: vec![Parameter::public(Variable::new(0))],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements: vec![Statement::constraint(
                 Variable::new(0),
                 Variable::public(0),
@@ -165,6 +167,8 @@ mod tests {
             module_map: Default::default(),
             arguments: vec![Parameter::public(Variable::new(0))],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements: vec![Statement::constraint(
                 Variable::new(0),
                 Variable::public(0),
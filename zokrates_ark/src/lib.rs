@@ -92,7 +92,7 @@ impl<'a, T: Field + ArkFieldExtensions, I: IntoIterator<Item = Statement<'a, T>>
             ConstraintSystemRef::CS(rc) => {
                 let mut cs = rc.borrow_mut();
                 symbols.extend(self.program.arguments.iter().enumerate().map(|(_, p)| {
-                    let wire = match p.private {
+                    let wire = match p.visibility.is_private() {
                         true => cs.new_witness_variable(|| {
                             Ok(witness
                                 .0
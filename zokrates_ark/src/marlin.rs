@@ -401,6 +401,8 @@ mod tests {
             module_map: Default::default(),
             arguments: vec![Parameter::private(Variable::new(0))],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements: vec![
                 Statement::constraint(
                     QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
@@ -444,6 +446,8 @@ mod tests {
             module_map: Default::default(),
             arguments: vec![Parameter::private(Variable::new(0))],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements: vec![
                 Statement::constraint(
                     QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
@@ -0,0 +1,77 @@
+//! Compares the zstd levels for the constraints section on a large circuit
+//!
+//! Run with `cargo bench -p zokrates_ast --features compression --bench compression`.
+//! The ratio of each level is printed before its write speed is measured.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::io::Cursor;
+use zokrates_ast::ir::{
+    read_section_bytes, LinComb, Parameter, Prog, ProgHeader, QuadComb, Statement, Variable,
+};
+use zokrates_field::Bn128Field;
+
+/// the number of constraints of the benchmarked circuit
+const CONSTRAINTS: usize = 200_000;
+
+/// a circuit of chained products of sums, with coefficients and variables varying across constraints like in flattened hashes
+fn circuit() -> Prog<'static, Bn128Field> {
+    Prog {
+        arguments: vec![
+            Parameter::private(Variable::new(0)),
+            Parameter::private(Variable::new(1)),
+        ],
+        return_count: 1,
+        statements: (0..CONSTRAINTS)
+            .map(|i| {
+                let output = match i + 1 == CONSTRAINTS {
+                    true => Variable::public(0),
+                    false => Variable::new(i + 2),
+                };
+
+                Statement::constraint(
+                    QuadComb::new(
+                        LinComb::from(Variable::new(i))
+                            + LinComb::summand(i % 251 + 1, Variable::new(i / 2)),
+                        LinComb::from(Variable::new(i + 1)) + LinComb::summand(i, Variable::one()),
+                    ),
+                    output,
+                    None,
+                )
+            })
+            .collect(),
+        ..Prog::default()
+    }
+}
+
+fn compression(c: &mut Criterion) {
+    let mut buffer = Cursor::new(vec![]);
+    circuit().serialize(&mut buffer).unwrap();
+
+    buffer.set_position(0);
+    let header = ProgHeader::read(&mut buffer).unwrap();
+    let constraints = read_section_bytes(&mut buffer, &header.sections[1]).unwrap();
+
+    let mut group = c.benchmark_group("compression");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(constraints.len() as u64));
+
+    for level in [1, 3, 6, 9, 19] {
+        let compressed = zstd::encode_all(&constraints[..], level).unwrap();
+        println!(
+            "level {}: {} bytes compressed to {} ({:.1}x)",
+            level,
+            constraints.len(),
+            compressed.len(),
+            constraints.len() as f64 / compressed.len() as f64
+        );
+
+        group.bench_with_input(BenchmarkId::from_parameter(level), &level, |b, level| {
+            b.iter(|| zstd::encode_all(&constraints[..], *level).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, compression);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+//! Compares reading a large circuit with `Prog::read_parallel` to reading it sequentially
+//!
+//! Run with `cargo bench -p zokrates_ast --features parallel --bench parallel`.
+//! Both readers verify the checksums of the sections before deserializing the statements.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::io::Cursor;
+use zokrates_ast::ir::{LinComb, Parameter, Prog, ProgEnum, QuadComb, Statement, Variable};
+use zokrates_field::Bn128Field;
+
+/// the number of constraints of the benchmarked circuit
+const CONSTRAINTS: usize = 200_000;
+
+/// a circuit of chained products of sums, with coefficients and variables varying across constraints like in flattened hashes
+fn circuit() -> Prog<'static, Bn128Field> {
+    Prog {
+        arguments: vec![
+            Parameter::private(Variable::new(0)),
+            Parameter::private(Variable::new(1)),
+        ],
+        return_count: 1,
+        statements: (0..CONSTRAINTS)
+            .map(|i| {
+                let output = match i + 1 == CONSTRAINTS {
+                    true => Variable::public(0),
+                    false => Variable::new(i + 2),
+                };
+
+                Statement::constraint(
+                    QuadComb::new(
+                        LinComb::from(Variable::new(i))
+                            + LinComb::summand(i % 251 + 1, Variable::new(i / 2)),
+                        LinComb::from(Variable::new(i + 1)) + LinComb::summand(i, Variable::one()),
+                    ),
+                    output,
+                    None,
+                )
+            })
+            .collect(),
+        ..Prog::default()
+    }
+}
+
+fn parallel(c: &mut Criterion) {
+    let mut buffer = Cursor::new(vec![]);
+    circuit().serialize(&mut buffer).unwrap();
+    let bytes = buffer.into_inner();
+
+    let mut group = c.benchmark_group("parallel");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+    group.bench_function("read_parallel", |b| {
+        b.iter(|| {
+            Prog::<Bn128Field>::read_parallel(&bytes)
+                .unwrap()
+                .statements
+                .len()
+        })
+    });
+
+    group.bench_function("sequential", |b| {
+        b.iter(
+            || match ProgEnum::deserialize(Cursor::new(&bytes)).unwrap() {
+                ProgEnum::Bn128Program(p) => p.collect().statements.len(),
+                _ => unreachable!(),
+            },
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parallel);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+//! Compares reading a program in place with `ProgIterator::from_slice` to reading it through a `Cursor`
+//!
+//! Run with `cargo bench -p zokrates_ast --bench slice`.
+//! The program logs long format strings, whose bytes are copied through the reader by `ProgEnum::deserialize` only.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::io::Cursor;
+use zokrates_ast::common::FormatString;
+use zokrates_ast::ir::{
+    LinComb, Parameter, Prog, ProgEnum, ProgIterator, QuadComb, Statement, Variable,
+};
+use zokrates_ast::typed::ConcreteType;
+use zokrates_field::Bn128Field;
+
+/// the number of constraints of the benchmarked program
+const CONSTRAINTS: usize = 50_000;
+
+/// the number of constraints between two logs
+const LOG_INTERVAL: usize = 50;
+
+/// the length in bytes of each logged format string
+const FORMAT_STRING_LENGTH: usize = 4096;
+
+/// a chain of squarings, logging its intermediate values with long format strings
+fn program() -> Prog<'static, Bn128Field> {
+    let format_string = format!("{}{{}}", "-".repeat(FORMAT_STRING_LENGTH));
+
+    Prog {
+        arguments: vec![Parameter::private(Variable::new(0))],
+        return_count: 1,
+        statements: (0..CONSTRAINTS)
+            .flat_map(|i| {
+                let output = match i + 1 == CONSTRAINTS {
+                    true => Variable::public(0),
+                    false => Variable::new(i + 1),
+                };
+
+                let constraint = Statement::constraint(
+                    QuadComb::new(Variable::new(i).into(), Variable::new(i).into()),
+                    output,
+                    None,
+                );
+
+                let log = (i % LOG_INTERVAL == 0).then(|| {
+                    Statement::log(
+                        FormatString::from(format_string.as_str()),
+                        vec![(ConcreteType::FieldElement, vec![Variable::new(i).into()])],
+                    )
+                });
+
+                log.into_iter().chain(std::iter::once(constraint))
+            })
+            .collect(),
+        ..Prog::default()
+    }
+}
+
+fn slice(c: &mut Criterion) {
+    let mut buffer = Cursor::new(vec![]);
+    program().serialize(&mut buffer).unwrap();
+    let bytes = buffer.into_inner();
+
+    let mut group = c.benchmark_group("slice");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+    group.bench_function("from_slice", |b| {
+        b.iter(|| {
+            ProgIterator::<Bn128Field, _>::from_slice(&bytes)
+                .unwrap()
+                .statements
+                .count()
+        })
+    });
+
+    group.bench_function("cursor", |b| {
+        b.iter(
+            || match ProgEnum::deserialize(Cursor::new(&bytes)).unwrap() {
+                ProgEnum::Bn128Program(p) => p.statements.count(),
+                _ => unreachable!(),
+            },
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, slice);
+criterion_main!(benches);
@@ -5,18 +5,19 @@ use crate::common::{
 use crate::flat::flat_expression_from_bits;
 use crate::flat::{FlatDirective, FlatExpression, FlatFunctionIterator, FlatStatement};
 use crate::typed::types::{
-    ConcreteGenericsAssignment, DeclarationConstant, DeclarationSignature, DeclarationType,
-    GenericIdentifier,
+    ConcreteGenericsAssignment, DeclarationConstant, DeclarationSignature, DeclarationTupleType,
+    DeclarationType, GenericIdentifier,
 };
 use crate::untyped::{
     types::{UnresolvedSignature, UnresolvedType},
     ConstantGenericNode, Expression,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::*;
 use zokrates_field::Field;
 
+use super::poseidon_constants;
 use super::ModuleMap;
 
 cfg_if::cfg_if! {
@@ -49,6 +50,12 @@ pub enum FlatEmbed {
     U16FromBits,
     U32FromBits,
     U64FromBits,
+    Keccak256,
+    Poseidon,
+    LimbDecompose,
+    ConditionalInverse,
+    ScalarMul,
+    EuclideanDiv,
     #[cfg(feature = "bellman")]
     Sha256Round,
     #[cfg(feature = "ark")]
@@ -150,6 +157,84 @@ impl FlatEmbed {
                     Expression::U32Constant(64).into(),
                 )
                 .into()]),
+            FlatEmbed::Keccak256 => UnresolvedSignature::new()
+                .generics(vec!["N".into()])
+                .inputs(vec![UnresolvedType::array(
+                    UnresolvedType::array(
+                        UnresolvedType::Boolean.into(),
+                        Expression::U32Constant(8).into(),
+                    )
+                    .into(),
+                    Expression::Identifier("N").into(),
+                )
+                .into()])
+                .output(
+                    UnresolvedType::array(
+                        UnresolvedType::Boolean.into(),
+                        Expression::U32Constant(256).into(),
+                    )
+                    .into(),
+                ),
+            FlatEmbed::Poseidon => UnresolvedSignature::new()
+                .generics(vec!["N".into()])
+                .inputs(vec![UnresolvedType::array(
+                    UnresolvedType::FieldElement.into(),
+                    Expression::Identifier("N").into(),
+                )
+                .into()])
+                .output(UnresolvedType::FieldElement.into()),
+            FlatEmbed::LimbDecompose => UnresolvedSignature::new()
+                .generics(vec!["R".into(), "L".into()])
+                .inputs(vec![UnresolvedType::FieldElement.into()])
+                .output(
+                    UnresolvedType::array(
+                        UnresolvedType::FieldElement.into(),
+                        Expression::Identifier("L").into(),
+                    )
+                    .into(),
+                ),
+            FlatEmbed::ConditionalInverse => UnresolvedSignature::new()
+                .inputs(vec![UnresolvedType::FieldElement.into()])
+                .output(
+                    UnresolvedType::Tuple(vec![
+                        UnresolvedType::FieldElement.into(),
+                        UnresolvedType::Boolean.into(),
+                    ])
+                    .into(),
+                ),
+            FlatEmbed::ScalarMul => UnresolvedSignature::new()
+                .generics(vec!["N".into()])
+                .inputs(vec![
+                    UnresolvedType::array(
+                        UnresolvedType::Boolean.into(),
+                        Expression::Identifier("N").into(),
+                    )
+                    .into(),
+                    UnresolvedType::array(
+                        UnresolvedType::FieldElement.into(),
+                        Expression::U32Constant(2).into(),
+                    )
+                    .into(),
+                ])
+                .output(
+                    UnresolvedType::array(
+                        UnresolvedType::FieldElement.into(),
+                        Expression::U32Constant(2).into(),
+                    )
+                    .into(),
+                ),
+            FlatEmbed::EuclideanDiv => UnresolvedSignature::new()
+                .inputs(vec![
+                    UnresolvedType::FieldElement.into(),
+                    UnresolvedType::FieldElement.into(),
+                ])
+                .output(
+                    UnresolvedType::Tuple(vec![
+                        UnresolvedType::FieldElement.into(),
+                        UnresolvedType::FieldElement.into(),
+                    ])
+                    .into(),
+                ),
             #[cfg(feature = "bellman")]
             FlatEmbed::Sha256Round => UnresolvedSignature::new()
                 .inputs(vec![
@@ -260,6 +345,68 @@ impl FlatEmbed {
                     DeclarationType::Boolean,
                     64u32,
                 ))]),
+            FlatEmbed::Keccak256 => DeclarationSignature::new()
+                .generics(vec![Some(DeclarationConstant::Generic(
+                    GenericIdentifier::with_name("N").with_index(0),
+                ))])
+                .inputs(vec![DeclarationType::array((
+                    DeclarationType::array((DeclarationType::Boolean, 8u32)),
+                    GenericIdentifier::with_name("N").with_index(0),
+                ))])
+                .output(DeclarationType::array((DeclarationType::Boolean, 256u32))),
+            FlatEmbed::Poseidon => DeclarationSignature::new()
+                .generics(vec![Some(DeclarationConstant::Generic(
+                    GenericIdentifier::with_name("N").with_index(0),
+                ))])
+                .inputs(vec![DeclarationType::array((
+                    DeclarationType::FieldElement,
+                    GenericIdentifier::with_name("N").with_index(0),
+                ))])
+                .output(DeclarationType::FieldElement),
+            FlatEmbed::LimbDecompose => DeclarationSignature::new()
+                .generics(vec![
+                    Some(DeclarationConstant::Generic(
+                        GenericIdentifier::with_name("R").with_index(0),
+                    )),
+                    Some(DeclarationConstant::Generic(
+                        GenericIdentifier::with_name("L").with_index(1),
+                    )),
+                ])
+                .inputs(vec![DeclarationType::FieldElement])
+                .output(DeclarationType::array((
+                    DeclarationType::FieldElement,
+                    GenericIdentifier::with_name("L").with_index(1),
+                ))),
+            FlatEmbed::ConditionalInverse => DeclarationSignature::new()
+                .inputs(vec![DeclarationType::FieldElement])
+                .output(DeclarationType::tuple(DeclarationTupleType::new(vec![
+                    DeclarationType::FieldElement,
+                    DeclarationType::Boolean,
+                ]))),
+            FlatEmbed::ScalarMul => DeclarationSignature::new()
+                .generics(vec![Some(DeclarationConstant::Generic(
+                    GenericIdentifier::with_name("N").with_index(0),
+                ))])
+                .inputs(vec![
+                    DeclarationType::array((
+                        DeclarationType::Boolean,
+                        GenericIdentifier::with_name("N").with_index(0),
+                    )),
+                    DeclarationType::array((DeclarationType::FieldElement, 2u32)),
+                ])
+                .output(DeclarationType::array((
+                    DeclarationType::FieldElement,
+                    2u32,
+                ))),
+            FlatEmbed::EuclideanDiv => DeclarationSignature::new()
+                .inputs(vec![
+                    DeclarationType::FieldElement,
+                    DeclarationType::FieldElement,
+                ])
+                .output(DeclarationType::tuple(DeclarationTupleType::new(vec![
+                    DeclarationType::FieldElement,
+                    DeclarationType::FieldElement,
+                ]))),
             #[cfg(feature = "bellman")]
             FlatEmbed::Sha256Round => DeclarationSignature::new()
                 .inputs(vec![
@@ -317,6 +464,12 @@ impl FlatEmbed {
             FlatEmbed::U16FromBits => "_U16_FROM_BITS",
             FlatEmbed::U32FromBits => "_U32_FROM_BITS",
             FlatEmbed::U64FromBits => "_U64_FROM_BITS",
+            FlatEmbed::Keccak256 => "_KECCAK256",
+            FlatEmbed::Poseidon => "_POSEIDON",
+            FlatEmbed::LimbDecompose => "_LIMB_DECOMPOSE",
+            FlatEmbed::ConditionalInverse => "_CONDITIONAL_INVERSE",
+            FlatEmbed::ScalarMul => "_SCALAR_MUL",
+            FlatEmbed::EuclideanDiv => "_EUCLIDEAN_DIV",
             #[cfg(feature = "bellman")]
             FlatEmbed::Sha256Round => "_SHA256_ROUND",
             #[cfg(feature = "ark")]
@@ -639,6 +792,796 @@ pub fn unpack_to_bitwidth<'ast, T: Field>(
     }
 }
 
+/// Checks that a field element can be decomposed into `limbs` limbs of `radix_bits` bits over `T`
+///
+/// # Remarks
+///
+/// * the limbs must be able to represent all field elements, so `radix_bits * limbs` must be at least `T::get_required_bits()`
+/// * the most significant limb only gets the remaining bits, so it must not be empty
+pub fn check_limb_decomposition<T: Field>(radix_bits: usize, limbs: usize) -> Result<(), String> {
+    let bitwidth = T::get_required_bits();
+
+    if radix_bits == 0 || limbs == 0 {
+        return Err(format!(
+            "Cannot decompose to {} limbs of {} bits: both must be positive",
+            limbs, radix_bits
+        ));
+    }
+
+    if radix_bits * limbs < bitwidth {
+        return Err(format!(
+            "Cannot decompose to {} limbs of {} bits: elements of `{}` require {} bits",
+            limbs,
+            radix_bits,
+            T::name(),
+            bitwidth
+        ));
+    }
+
+    if radix_bits * (limbs - 1) >= bitwidth {
+        return Err(format!(
+            "Cannot decompose to {} limbs of {} bits: elements of `{}` fit in {} limbs",
+            limbs,
+            radix_bits,
+            T::name(),
+            (bitwidth + radix_bits - 1) / radix_bits
+        ));
+    }
+
+    Ok(())
+}
+
+/// The number of bytes absorbed by each keccak-256 permutation
+const KECCAK256_RATE: usize = 136;
+
+/// The round constants of keccak-f[1600]
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// The rotation offsets of keccak-f[1600], indexed by `[x][y]`
+const KECCAK_ROTATIONS: [[usize; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+/// A bit of the keccak state, either known at compile time or given by a linear expression
+#[derive(Clone)]
+enum KeccakBit<T> {
+    Constant(bool),
+    Expression(FlatExpression<T>),
+}
+
+/// Keeps track of the statements and variables introduced while flattening keccak
+struct KeccakFlattener<'ast, T> {
+    counter: usize,
+    statements: Vec<FlatStatement<'ast, T>>,
+}
+
+impl<'ast, T: Field> KeccakFlattener<'ast, T> {
+    fn define(&mut self, e: FlatExpression<T>) -> FlatExpression<T> {
+        let v = Variable::new(self.counter);
+        self.counter += 1;
+        self.statements.push(FlatStatement::definition(v, e));
+        FlatExpression::identifier(v)
+    }
+
+    /// define a variable for `bit` if it is a compound expression, to keep expressions small
+    fn normalize(&mut self, bit: KeccakBit<T>) -> KeccakBit<T> {
+        match bit {
+            KeccakBit::Expression(e @ FlatExpression::Identifier(_)) => KeccakBit::Expression(e),
+            KeccakBit::Expression(e) => KeccakBit::Expression(self.define(e)),
+            bit => bit,
+        }
+    }
+
+    fn xor(&mut self, a: &KeccakBit<T>, b: &KeccakBit<T>) -> KeccakBit<T> {
+        match (a, b) {
+            (KeccakBit::Constant(a), KeccakBit::Constant(b)) => KeccakBit::Constant(a ^ b),
+            (KeccakBit::Constant(false), e) | (e, KeccakBit::Constant(false)) => e.clone(),
+            (KeccakBit::Constant(true), KeccakBit::Expression(e))
+            | (KeccakBit::Expression(e), KeccakBit::Constant(true)) => {
+                KeccakBit::Expression(FlatExpression::value(T::one()) - e.clone())
+            }
+            // a ^ b == a + b - 2ab
+            (KeccakBit::Expression(a), KeccakBit::Expression(b)) => {
+                let ab = self.define(a.clone() * b.clone());
+                KeccakBit::Expression(
+                    a.clone() + b.clone() - FlatExpression::value(T::from(2)) * ab,
+                )
+            }
+        }
+    }
+
+    /// compute `!a & b`
+    fn and_not(&mut self, a: &KeccakBit<T>, b: &KeccakBit<T>) -> KeccakBit<T> {
+        match (a, b) {
+            (KeccakBit::Constant(true), _) | (_, KeccakBit::Constant(false)) => {
+                KeccakBit::Constant(false)
+            }
+            (KeccakBit::Constant(false), b) => b.clone(),
+            (KeccakBit::Expression(a), KeccakBit::Constant(true)) => {
+                KeccakBit::Expression(FlatExpression::value(T::one()) - a.clone())
+            }
+            (KeccakBit::Expression(a), KeccakBit::Expression(b)) => KeccakBit::Expression(
+                self.define((FlatExpression::value(T::one()) - a.clone()) * b.clone()),
+            ),
+        }
+    }
+
+    /// apply keccak-f[1600] to `state`, made of 25 lanes of 64 bits, the lane `(x, y)` being at index `x + 5 * y`
+    fn permute(&mut self, mut state: Vec<Vec<KeccakBit<T>>>) -> Vec<Vec<KeccakBit<T>>> {
+        let lane = |x: usize, y: usize| x % 5 + 5 * (y % 5);
+
+        for round_constant in KECCAK_ROUND_CONSTANTS {
+            // theta
+            let c: Vec<Vec<_>> = (0..5)
+                .map(|x| {
+                    (0..64)
+                        .map(|z| {
+                            let bit = (1..5).fold(state[lane(x, 0)][z].clone(), |acc, y| {
+                                self.xor(&acc, &state[lane(x, y)][z])
+                            });
+                            self.normalize(bit)
+                        })
+                        .collect()
+                })
+                .collect();
+            let d: Vec<Vec<_>> = (0..5)
+                .map(|x| {
+                    (0..64)
+                        .map(|z| {
+                            let bit = self.xor(&c[(x + 4) % 5][z], &c[(x + 1) % 5][(z + 63) % 64]);
+                            self.normalize(bit)
+                        })
+                        .collect()
+                })
+                .collect();
+            for x in 0..5 {
+                for y in 0..5 {
+                    for z in 0..64 {
+                        let bit = self.xor(&state[lane(x, y)][z], &d[x][z]);
+                        state[lane(x, y)][z] = self.normalize(bit);
+                    }
+                }
+            }
+
+            // rho and pi
+            let mut b = vec![vec![]; 25];
+            for x in 0..5 {
+                for y in 0..5 {
+                    let rotation = KECCAK_ROTATIONS[x][y];
+                    b[lane(y, 2 * x + 3 * y)] = (0..64)
+                        .map(|z| state[lane(x, y)][(z + 64 - rotation) % 64].clone())
+                        .collect();
+                }
+            }
+
+            // chi
+            for x in 0..5 {
+                for y in 0..5 {
+                    for z in 0..64 {
+                        let t = self.and_not(&b[lane(x + 1, y)][z], &b[lane(x + 2, y)][z]);
+                        let bit = self.xor(&b[lane(x, y)][z], &t);
+                        state[lane(x, y)][z] = self.normalize(bit);
+                    }
+                }
+            }
+
+            // iota
+            for z in 0..64 {
+                let constant = KeccakBit::Constant((round_constant >> z) & 1 == 1);
+                state[0][z] = self.xor(&state[0][z], &constant);
+            }
+        }
+
+        state
+    }
+}
+
+/// Returns a flat function which computes the keccak-256 digest of `n` bytes, as used by Ethereum
+///
+/// # Remarks
+///
+/// * bytes are passed and returned as arrays of 8 bits, most significant bit first
+/// * the padding is the original keccak padding, not the one of the SHA-3 standard
+/// * the witness is computed by definitions, so no solver is required
+pub fn keccak256<'ast, T: Field>(
+    n: usize,
+) -> FlatFunctionIterator<'ast, T, impl IntoIterator<Item = FlatStatement<'ast, T>>> {
+    let arguments: Vec<_> = (0..n * 8)
+        .map(|i| Parameter::private(Variable::new(i)))
+        .collect();
+
+    let mut flattener = KeccakFlattener {
+        counter: n * 8,
+        statements: vec![],
+    };
+
+    // the bits of the message, least significant bit of each byte first as keccak expects
+    let mut message: Vec<KeccakBit<T>> = (0..n)
+        .flat_map(|byte| {
+            (0..8)
+                .rev()
+                .map(move |bit| KeccakBit::Expression(Variable::new(byte * 8 + bit).into()))
+        })
+        .collect();
+
+    // pad with `0x01 0x00 ... 0x00 0x80` to a multiple of the rate
+    let padded_len = (n / KECCAK256_RATE + 1) * KECCAK256_RATE;
+    message.extend(
+        (0..(padded_len - n) * 8)
+            .map(|i| KeccakBit::Constant(i == 0 || i == (padded_len - n) * 8 - 1)),
+    );
+
+    let mut state = vec![vec![KeccakBit::Constant(false); 64]; 25];
+
+    for block in message.chunks(KECCAK256_RATE * 8) {
+        for (i, bit) in block.iter().enumerate() {
+            state[i / 64][i % 64] = flattener.xor(&state[i / 64][i % 64], bit);
+        }
+        state = flattener.permute(state);
+    }
+
+    // the digest is made of the first 32 bytes of the state, returned most significant bit first
+    let outputs: Vec<_> = (0..32)
+        .flat_map(|byte| (0..8).rev().map(move |bit| byte * 8 + bit))
+        .map(|i| match &state[i / 64][i % 64] {
+            KeccakBit::Constant(b) => FlatExpression::value(T::from(*b)),
+            KeccakBit::Expression(e) => e.clone(),
+        })
+        .collect();
+
+    let return_count = outputs.len();
+
+    let statements = flattener.statements.into_iter().chain(
+        outputs
+            .into_iter()
+            .enumerate()
+            .map(|(index, e)| FlatStatement::definition(Variable::public(index), e)),
+    );
+
+    FlatFunctionIterator {
+        arguments,
+        statements,
+        return_count,
+        module_map: ModuleMap::default(),
+    }
+}
+
+/// The number of full rounds of poseidon, half of which happen before the partial rounds
+const POSEIDON_FULL_ROUNDS: usize = 8;
+
+/// The largest number of inputs poseidon is defined for
+const POSEIDON_MAX_ARITY: usize = 6;
+
+/// The parameters of poseidon for a given width of the state
+struct PoseidonParameters<T> {
+    partial_rounds: usize,
+    round_constants: Vec<T>,
+    mds: Vec<Vec<T>>,
+}
+
+/// Checks that poseidon is defined over `T`
+///
+/// # Remarks
+///
+/// The round constants and MDS matrices depend on the field, so only the curves we have parameters for are supported
+pub fn check_poseidon_field<T: Field>() -> Result<(), String> {
+    use zokrates_field::Bn128Field;
+
+    if T::id() != Bn128Field::id() {
+        return Err(format!(
+            "`poseidon` is only supported over `{}`, found `{}`",
+            Bn128Field::name(),
+            T::name()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that poseidon is defined for `arity` inputs over `T`
+pub fn check_poseidon_arity<T: Field>(arity: usize) -> Result<(), String> {
+    check_poseidon_field::<T>()?;
+
+    if arity == 0 || arity > POSEIDON_MAX_ARITY {
+        return Err(format!(
+            "`poseidon` supports 1 to {} inputs over `{}`, found {}",
+            POSEIDON_MAX_ARITY,
+            T::name(),
+            arity
+        ));
+    }
+
+    Ok(())
+}
+
+fn poseidon_parameters<T: Field>(arity: usize) -> PoseidonParameters<T> {
+    let index = arity - 1;
+
+    let parse = |c: &&str| T::try_from_dec_str(c).unwrap();
+
+    PoseidonParameters {
+        partial_rounds: poseidon_constants::PARTIAL_ROUNDS[index],
+        round_constants: poseidon_constants::ROUND_CONSTANTS[index]
+            .iter()
+            .map(parse)
+            .collect(),
+        mds: poseidon_constants::MDS[index]
+            .iter()
+            .map(|row| row.iter().map(parse).collect())
+            .collect(),
+    }
+}
+
+/// An element of the poseidon state, kept as a linear combination so that adding constants and mixing do not introduce variables
+#[derive(Clone)]
+struct PoseidonElement<T> {
+    constant: T,
+    terms: BTreeMap<Variable, T>,
+}
+
+impl<T: Field> PoseidonElement<T> {
+    fn constant(constant: T) -> Self {
+        PoseidonElement {
+            constant,
+            terms: BTreeMap::new(),
+        }
+    }
+
+    fn variable(v: Variable) -> Self {
+        PoseidonElement {
+            constant: T::zero(),
+            terms: std::iter::once((v, T::one())).collect(),
+        }
+    }
+
+    fn scale(&self, factor: &T) -> Self {
+        PoseidonElement {
+            constant: self.constant.clone() * factor,
+            terms: self
+                .terms
+                .iter()
+                .map(|(v, c)| (*v, c.clone() * factor))
+                .collect(),
+        }
+    }
+
+    fn add(mut self, other: Self) -> Self {
+        self.constant = self.constant + other.constant;
+        for (v, c) in other.terms {
+            let coefficient = self.terms.entry(v).or_insert_with(T::zero);
+            *coefficient = coefficient.clone() + c;
+        }
+        self
+    }
+
+    fn into_expression(self) -> FlatExpression<T> {
+        self.terms
+            .into_iter()
+            .fold(FlatExpression::value(self.constant), |acc, (v, c)| {
+                acc + FlatExpression::value(c) * FlatExpression::identifier(v)
+            })
+    }
+}
+
+/// Returns a flat function which computes the poseidon hash of `arity` field elements, as in circomlib
+///
+/// # Remarks
+///
+/// * the state is initialized to `[0, inputs...]` and the first element of the final state is returned
+/// * the witness is computed by definitions, so no solver is required
+/// * fails if poseidon is not defined for `arity` inputs over `T`, see `check_poseidon_arity`
+pub fn poseidon<'ast, T: Field>(
+    arity: usize,
+) -> Result<FlatFunctionIterator<'ast, T, impl IntoIterator<Item = FlatStatement<'ast, T>>>, String>
+{
+    check_poseidon_arity::<T>(arity)?;
+
+    let parameters = poseidon_parameters::<T>(arity);
+    let width = arity + 1;
+
+    let arguments: Vec<_> = (0..arity)
+        .map(|i| Parameter::private(Variable::new(i)))
+        .collect();
+
+    let mut counter = arity;
+    let mut statements = vec![];
+
+    let mut define = |e: FlatExpression<T>| {
+        let v = Variable::new(counter);
+        counter += 1;
+        statements.push(FlatStatement::definition(v, e));
+        v
+    };
+
+    let mut state: Vec<PoseidonElement<T>> = std::iter::once(PoseidonElement::constant(T::zero()))
+        .chain((0..arity).map(|i| PoseidonElement::variable(Variable::new(i))))
+        .collect();
+
+    for round in 0..POSEIDON_FULL_ROUNDS + parameters.partial_rounds {
+        let full = round < POSEIDON_FULL_ROUNDS / 2
+            || round >= POSEIDON_FULL_ROUNDS / 2 + parameters.partial_rounds;
+
+        for (i, e) in state.iter_mut().enumerate() {
+            e.constant = e.constant.clone() + &parameters.round_constants[round * width + i];
+        }
+
+        // the sbox is applied to the whole state in full rounds, and to its first element in partial rounds
+        for e in state.iter_mut().take(if full { width } else { 1 }) {
+            *e = match e.terms.is_empty() {
+                true => PoseidonElement::constant(e.constant.clone().pow(5)),
+                false => {
+                    let x = e.clone().into_expression();
+                    let x2 = FlatExpression::identifier(define(x.clone() * x.clone()));
+                    let x4 = FlatExpression::identifier(define(x2.clone() * x2));
+                    PoseidonElement::variable(define(x4 * x))
+                }
+            };
+        }
+
+        state = parameters
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&state)
+                    .fold(PoseidonElement::constant(T::zero()), |acc, (m, e)| {
+                        acc.add(e.scale(m))
+                    })
+            })
+            .collect();
+    }
+
+    let output = state.swap_remove(0).into_expression();
+
+    statements.push(FlatStatement::definition(Variable::public(0), output));
+
+    Ok(FlatFunctionIterator {
+        arguments,
+        statements,
+        return_count: 1,
+        module_map: ModuleMap::default(),
+    })
+}
+
+/// Returns a flat function which computes the inverse of a field element, along with whether that element is zero
+///
+/// # Remarks
+///
+/// * the inverse of zero is defined as zero, and the second output is true exactly when the input is zero
+/// * the outputs are enforced by `x * inv == 1 - is_zero`, `x * is_zero == 0` and `inv * is_zero == 0`, the last one making the inverse of zero deterministic
+pub fn conditional_inverse<'ast, T: Field>(
+) -> FlatFunctionIterator<'ast, T, impl IntoIterator<Item = FlatStatement<'ast, T>>> {
+    let x = Variable::new(0);
+    let inv = Variable::new(1);
+    let is_zero = Variable::new(2);
+
+    let statements = vec![
+        FlatStatement::Directive(FlatDirective::new(
+            vec![inv, is_zero],
+            Solver::ConditionalInverse,
+            vec![x.into()],
+        )),
+        FlatStatement::condition(
+            FlatExpression::value(T::one()) - FlatExpression::identifier(is_zero),
+            FlatExpression::identifier(x) * FlatExpression::identifier(inv),
+            RuntimeError::Inverse,
+        ),
+        FlatStatement::condition(
+            FlatExpression::value(T::zero()),
+            FlatExpression::identifier(x) * FlatExpression::identifier(is_zero),
+            RuntimeError::Inverse,
+        ),
+        FlatStatement::condition(
+            FlatExpression::value(T::zero()),
+            FlatExpression::identifier(inv) * FlatExpression::identifier(is_zero),
+            RuntimeError::Inverse,
+        ),
+        FlatStatement::definition(Variable::public(0), FlatExpression::identifier(inv)),
+        FlatStatement::definition(Variable::public(1), FlatExpression::identifier(is_zero)),
+    ];
+
+    FlatFunctionIterator {
+        arguments: vec![Parameter::private(x)],
+        statements,
+        return_count: 2,
+        module_map: ModuleMap::default(),
+    }
+}
+
+/// The number of bits of the operands of `euclidean_div` over `T`, small enough for `q * b + r` not to wrap around the modulus
+pub fn euclidean_div_bitwidth<T: Field>() -> usize {
+    (T::get_required_bits() - 1) / 2
+}
+
+/// Returns a flat function which computes the quotient and remainder of the euclidean division of `a` by `b`
+///
+/// # Remarks
+///
+/// * `a` and `b` are integers below `2^w`, where `w` is `euclidean_div_bitwidth::<T>()`, for example 126 over bn128
+/// * the outputs are enforced by `q * b == a - r`, with `q`, `r`, `b` and `b - 1 - r` decomposed into `w` bits, so that `0 <= r < b` and `q * b + r` does not wrap around the modulus
+/// * division by zero fails: the solver returns `q = 0` and `r = a`, and no remainder satisfies `r < 0`
+pub fn euclidean_div<'ast, T: Field>(
+) -> FlatFunctionIterator<'ast, T, impl IntoIterator<Item = FlatStatement<'ast, T>>> {
+    let bitwidth = euclidean_div_bitwidth::<T>();
+
+    let a = Variable::new(0);
+    let b = Variable::new(1);
+    let q = Variable::new(2);
+    let r = Variable::new(3);
+    let mut counter = 4;
+
+    let mut statements = vec![FlatStatement::directive(
+        vec![q, r],
+        Solver::EuclideanDiv,
+        vec![a.into(), b.into()],
+    )];
+
+    // `q`, `r` and `b` fit in `bitwidth` bits, and `r < b` as `b - 1 - r` does too
+    let checked = [
+        FlatExpression::identifier(q),
+        FlatExpression::identifier(r),
+        FlatExpression::identifier(b),
+        FlatExpression::identifier(b)
+            - FlatExpression::value(T::one())
+            - FlatExpression::identifier(r),
+    ];
+
+    for e in checked {
+        let bits: Vec<_> = (counter..counter + bitwidth).map(Variable::new).collect();
+        counter += bitwidth;
+
+        statements.push(FlatStatement::directive(
+            bits.clone(),
+            Solver::bits(bitwidth),
+            vec![e.clone()],
+        ));
+        statements.extend(bits.iter().map(|bit| {
+            FlatStatement::condition(
+                FlatExpression::identifier(*bit),
+                FlatExpression::identifier(*bit) * FlatExpression::identifier(*bit),
+                RuntimeError::Bitness,
+            )
+        }));
+        statements.push(FlatStatement::condition(
+            flat_expression_from_bits(bits.into_iter().map(FlatExpression::identifier).collect()),
+            e,
+            RuntimeError::Euclidean,
+        ));
+    }
+
+    // q * b == a - r
+    statements.push(FlatStatement::condition(
+        FlatExpression::identifier(a) - FlatExpression::identifier(r),
+        FlatExpression::identifier(q) * FlatExpression::identifier(b),
+        RuntimeError::Euclidean,
+    ));
+    statements.push(FlatStatement::definition(Variable::public(0), q.into()));
+    statements.push(FlatStatement::definition(Variable::public(1), r.into()));
+
+    FlatFunctionIterator {
+        arguments: vec![Parameter::private(a), Parameter::private(b)],
+        statements,
+        return_count: 2,
+        module_map: ModuleMap::default(),
+    }
+}
+
+/// The coefficient `a` of Baby Jubjub, the twisted Edwards curve `a * x^2 + y^2 = 1 + d * x^2 * y^2` defined over the scalar field of bn128
+const BABY_JUBJUB_A: u32 = 168700;
+
+/// The coefficient `d` of Baby Jubjub
+const BABY_JUBJUB_D: u32 = 168696;
+
+/// The number of bits of the scalar handled by each addition in `scalar_mul`
+const SCALAR_MUL_WINDOW: usize = 2;
+
+/// Adds two points of Baby Jubjub
+///
+/// The addition law is complete, so the denominators are never zero for points on the curve.
+/// Should they be, the coordinates are set to zero and the constraints of `scalar_mul` fail.
+pub fn baby_jubjub_add<T: Field>((x1, y1): (T, T), (x2, y2): (T, T)) -> (T, T) {
+    let a = T::from(BABY_JUBJUB_A);
+    let tau = T::from(BABY_JUBJUB_D) * x1 * x2 * y1 * y2;
+
+    (
+        (x1 * y2 + y1 * x2)
+            .checked_div(&(T::one() + tau))
+            .unwrap_or_else(T::zero),
+        (y1 * y2 - a * x1 * x2)
+            .checked_div(&(T::one() - tau))
+            .unwrap_or_else(T::zero),
+    )
+}
+
+/// A point of Baby Jubjub, given by the linear expressions of its coordinates
+type EdwardsPoint<T> = (FlatExpression<T>, FlatExpression<T>);
+
+/// Keeps track of the statements and variables introduced while flattening a scalar multiplication
+struct ScalarMulFlattener<'ast, T> {
+    counter: usize,
+    statements: Vec<FlatStatement<'ast, T>>,
+}
+
+impl<'ast, T: Field> ScalarMulFlattener<'ast, T> {
+    fn variable(&mut self) -> Variable {
+        let v = Variable::new(self.counter);
+        self.counter += 1;
+        v
+    }
+
+    fn define(&mut self, e: FlatExpression<T>) -> FlatExpression<T> {
+        let v = self.variable();
+        self.statements.push(FlatStatement::definition(v, e));
+        FlatExpression::identifier(v)
+    }
+
+    /// define a variable for `e` if it is a compound expression, so that it can be multiplied by a constant
+    fn normalize(&mut self, e: FlatExpression<T>) -> FlatExpression<T> {
+        match e {
+            e @ (FlatExpression::Identifier(_) | FlatExpression::Value(_)) => e,
+            e => self.define(e),
+        }
+    }
+
+    /// add two points whose coordinates are variables or constants, as in circomlib's `BabyAdd`
+    fn add(&mut self, p: &EdwardsPoint<T>, q: &EdwardsPoint<T>) -> EdwardsPoint<T> {
+        let (x1, y1) = p.clone();
+        let (x2, y2) = q.clone();
+
+        let a = FlatExpression::value(T::from(BABY_JUBJUB_A));
+        let d = FlatExpression::value(T::from(BABY_JUBJUB_D));
+
+        let x3 = self.variable();
+        let y3 = self.variable();
+        self.statements.push(FlatStatement::directive(
+            vec![x3, y3],
+            Solver::BabyJubjubAdd,
+            vec![x1.clone(), y1.clone(), x2.clone(), y2.clone()],
+        ));
+
+        let beta = self.define(x1.clone() * y2.clone());
+        let gamma = self.define(y1.clone() * x2.clone());
+        let delta = self.define((y1 - a.clone() * x1) * (x2 + y2));
+        let tau = self.define(beta.clone() * gamma.clone());
+
+        // x3 * (1 + d * tau) == x1 * y2 + y1 * x2
+        self.statements.push(FlatStatement::condition(
+            beta.clone() + gamma.clone(),
+            FlatExpression::identifier(x3)
+                * (FlatExpression::value(T::one()) + d.clone() * tau.clone()),
+            RuntimeError::Division,
+        ));
+        // y3 * (1 - d * tau) == y1 * y2 - a * x1 * x2
+        self.statements.push(FlatStatement::condition(
+            delta + a * beta - gamma,
+            FlatExpression::identifier(y3) * (FlatExpression::value(T::one()) - d * tau),
+            RuntimeError::Division,
+        ));
+
+        (x3.into(), y3.into())
+    }
+
+    /// select `q` if `bit` is set, `p` otherwise
+    fn select(
+        &mut self,
+        bit: &FlatExpression<T>,
+        p: &EdwardsPoint<T>,
+        q: &EdwardsPoint<T>,
+    ) -> EdwardsPoint<T> {
+        let x = self.define(bit.clone() * (q.0.clone() - p.0.clone()));
+        let y = self.define(bit.clone() * (q.1.clone() - p.1.clone()));
+        (p.0.clone() + x, p.1.clone() + y)
+    }
+
+    /// select the entry of `table` at the index given by `bits`, most significant bit first
+    fn lookup(&mut self, bits: &[FlatExpression<T>], table: &[EdwardsPoint<T>]) -> EdwardsPoint<T> {
+        let mut entries = table[..1 << bits.len()].to_vec();
+
+        for bit in bits.iter().rev() {
+            entries = entries
+                .chunks(2)
+                .map(|pair| self.select(bit, &pair[0], &pair[1]))
+                .collect();
+        }
+
+        let (x, y) = entries.swap_remove(0);
+        (self.normalize(x), self.normalize(y))
+    }
+}
+
+/// Returns a flat function which multiplies a point of Baby Jubjub by a scalar of `n` bits
+///
+/// # Remarks
+///
+/// * the arguments are the bits of the scalar, most significant first, followed by the coordinates of the base point
+/// * the multiples `0, P, ..., (2^w - 1) * P` of the base point `P` are computed once, then the scalar is processed `w` bits at a time from the most significant end, doubling `w` times and adding the multiple selected by the window
+/// * each addition is computed by the `BabyJubjubAdd` solver and checked by 6 constraints
+/// * the base point is expected to be on the curve, and the bits of the scalar to be boolean
+/// * Baby Jubjub is defined over the scalar field of bn128, so this is only meaningful over that field
+pub fn scalar_mul<'ast, T: Field>(
+    n: usize,
+) -> FlatFunctionIterator<'ast, T, impl IntoIterator<Item = FlatStatement<'ast, T>>> {
+    let arguments: Vec<_> = (0..n + 2)
+        .map(|i| Parameter::private(Variable::new(i)))
+        .collect();
+
+    let mut flattener = ScalarMulFlattener {
+        counter: n + 2,
+        statements: vec![],
+    };
+
+    let bits: Vec<FlatExpression<T>> = (0..n).map(|i| Variable::new(i).into()).collect();
+    let base: EdwardsPoint<T> = (Variable::new(n).into(), Variable::new(n + 1).into());
+
+    let identity: EdwardsPoint<T> = (
+        FlatExpression::value(T::zero()),
+        FlatExpression::value(T::one()),
+    );
+
+    let mut table = vec![identity, base.clone()];
+    while table.len() < 1 << SCALAR_MUL_WINDOW.min(n) {
+        let multiple = flattener.add(table.last().unwrap(), &base);
+        table.push(multiple);
+    }
+
+    // the most significant window is shorter if the window does not divide `n`
+    let first = match n % SCALAR_MUL_WINDOW {
+        0 => SCALAR_MUL_WINDOW.min(n),
+        r => r,
+    };
+
+    let mut result = flattener.lookup(&bits[..first], &table);
+
+    for window in bits[first..].chunks(SCALAR_MUL_WINDOW) {
+        for _ in 0..window.len() {
+            result = flattener.add(&result, &result);
+        }
+        let multiple = flattener.lookup(window, &table);
+        result = flattener.add(&result, &multiple);
+    }
+
+    let statements = flattener.statements.into_iter().chain([
+        FlatStatement::definition(Variable::public(0), result.0),
+        FlatStatement::definition(Variable::public(1), result.1),
+    ]);
+
+    FlatFunctionIterator {
+        arguments,
+        statements,
+        return_count: 2,
+        module_map: ModuleMap::default(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -671,6 +1614,284 @@ mod tests {
         }
     }
 
+    /// evaluate a flat function made of definitions, conditions and baby jubjub additions on `inputs`, checking the conditions
+    fn evaluate<'ast>(
+        f: FlatFunctionIterator<
+            'ast,
+            Bn128Field,
+            impl IntoIterator<Item = FlatStatement<'ast, Bn128Field>>,
+        >,
+        inputs: Vec<Bn128Field>,
+    ) -> Vec<Bn128Field> {
+        fn eval(e: &FlatExpression<Bn128Field>, w: &HashMap<Variable, Bn128Field>) -> Bn128Field {
+            match e {
+                FlatExpression::Value(v) => v.value,
+                FlatExpression::Identifier(id) => w[&id.id],
+                FlatExpression::Add(e) => eval(&e.left, w) + eval(&e.right, w),
+                FlatExpression::Sub(e) => eval(&e.left, w) - eval(&e.right, w),
+                FlatExpression::Mult(e) => eval(&e.left, w) * eval(&e.right, w),
+            }
+        }
+
+        let mut witness: HashMap<_, _> = f
+            .arguments
+            .iter()
+            .zip(inputs)
+            .map(|(p, i)| (p.id, i))
+            .collect();
+
+        for s in f.statements {
+            match s {
+                FlatStatement::Definition(d) => {
+                    let value = eval(&d.rhs, &witness);
+                    witness.insert(d.assignee, value);
+                }
+                FlatStatement::Condition(c) => {
+                    assert_eq!(eval(&c.quad, &witness), eval(&c.lin, &witness));
+                }
+                FlatStatement::Directive(d) => {
+                    assert_eq!(d.solver, Solver::BabyJubjubAdd);
+                    let i: Vec<_> = d.inputs.iter().map(|i| eval(i, &witness)).collect();
+                    let (x, y) = baby_jubjub_add((i[0], i[1]), (i[2], i[3]));
+                    witness.insert(d.outputs[0], x);
+                    witness.insert(d.outputs[1], y);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        (0..f.return_count)
+            .map(|i| witness[&Variable::public(i)])
+            .collect()
+    }
+
+    #[test]
+    fn limb_decomposition() {
+        // bn128 elements require 254 bits
+        assert!(check_limb_decomposition::<Bn128Field>(64, 4).is_ok());
+        assert!(check_limb_decomposition::<Bn128Field>(254, 1).is_ok());
+        assert!(check_limb_decomposition::<Bn128Field>(1, 254).is_ok());
+        assert!(check_limb_decomposition::<Bn128Field>(64, 3).is_err());
+        assert!(check_limb_decomposition::<Bn128Field>(64, 5).is_err());
+        assert!(check_limb_decomposition::<Bn128Field>(0, 4).is_err());
+    }
+
+    mod keccak {
+        use super::*;
+
+        fn to_bits(bytes: &[u8]) -> Vec<bool> {
+            bytes
+                .iter()
+                .flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1 == 1))
+                .collect()
+        }
+
+        fn check(message: &[u8], digest: &str) {
+            let f = keccak256::<Bn128Field>(message.len());
+            assert_eq!(f.arguments.len(), message.len() * 8);
+            assert_eq!(f.return_count, 256);
+
+            let digest: Vec<u8> = (0..digest.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&digest[i..i + 2], 16).unwrap())
+                .collect();
+
+            let inputs = to_bits(message).into_iter().map(Bn128Field::from).collect();
+
+            assert_eq!(
+                evaluate(f, inputs),
+                to_bits(&digest)
+                    .into_iter()
+                    .map(Bn128Field::from)
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        #[test]
+        fn empty() {
+            check(
+                b"",
+                "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+            );
+        }
+
+        #[test]
+        fn abc() {
+            check(
+                b"abc",
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            );
+        }
+
+        #[test]
+        fn two_blocks() {
+            // a full block of input requires a second block for the padding
+            check(
+                &(0..136).collect::<Vec<u8>>(),
+                "7ce759f1ab7f9ce437719970c26b0a66ff11fe3e38e17df89cf5d29c7d7f807e",
+            );
+        }
+    }
+
+    mod poseidon {
+        use super::*;
+
+        fn check(inputs: &[u32], output: &str) {
+            let f = poseidon::<Bn128Field>(inputs.len()).unwrap();
+            assert_eq!(f.arguments.len(), inputs.len());
+            assert_eq!(f.return_count, 1);
+
+            let inputs = inputs.iter().map(|i| Bn128Field::from(*i)).collect();
+
+            assert_eq!(
+                evaluate(f, inputs),
+                vec![Bn128Field::try_from_dec_str(output).unwrap()]
+            );
+        }
+
+        // test vectors from circomlib
+        #[test]
+        fn one() {
+            check(
+                &[1],
+                "18586133768512220936620570745912940619677854269274689475585506675881198879027",
+            );
+        }
+
+        #[test]
+        fn two() {
+            check(
+                &[1, 2],
+                "7853200120776062878684798364095072458815029376092732009249414926327459813530",
+            );
+        }
+
+        #[test]
+        fn four() {
+            check(
+                &[1, 2, 3, 4],
+                "18821383157269793795438455681495246036402687001665670618754263018637548127333",
+            );
+        }
+
+        #[test]
+        fn six() {
+            check(
+                &[1, 2, 3, 4, 5, 6],
+                "20400040500897583745843009878988256314335038853985262692600694741116813247201",
+            );
+        }
+
+        #[test]
+        fn constraint_count() {
+            // 3 constraints per sbox, on the whole state in full rounds and on one element in partial rounds
+            // the first full round has a constant first element, and one constraint binds the output
+            let f = poseidon::<Bn128Field>(2).unwrap().collect();
+            assert_eq!(f.statements.len(), 3 * (8 * 3 + 57) - 3 + 1);
+        }
+
+        #[test]
+        fn unsupported() {
+            assert!(check_poseidon_arity::<Bn128Field>(2).is_ok());
+            assert!(check_poseidon_arity::<Bn128Field>(0).is_err());
+            assert!(check_poseidon_arity::<Bn128Field>(7).is_err());
+            assert!(check_poseidon_arity::<zokrates_field::Bls12_381Field>(2).is_err());
+
+            assert!(poseidon::<Bn128Field>(7).is_err());
+            assert!(poseidon::<zokrates_field::Bls12_381Field>(2).is_err());
+        }
+    }
+
+    mod scalar_mul {
+        use super::*;
+
+        const G: [&str; 2] = [
+            "16540640123574156134436876038791482806971768689494387082833631921987005038935",
+            "20819045374670962167435360035096875258406992893633759881276124905556507972311",
+        ];
+
+        fn check(scalar: u32, n: usize, expected: [&str; 2]) {
+            let f = scalar_mul::<Bn128Field>(n);
+            assert_eq!(f.arguments.len(), n + 2);
+            assert_eq!(f.return_count, 2);
+
+            let inputs = (0..n)
+                .rev()
+                .map(|i| Bn128Field::from((scalar >> i) & 1 == 1))
+                .chain(G.iter().map(|c| Bn128Field::try_from_dec_str(c).unwrap()))
+                .collect();
+
+            assert_eq!(
+                evaluate(f, inputs),
+                expected
+                    .iter()
+                    .map(|c| Bn128Field::try_from_dec_str(c).unwrap())
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        #[test]
+        fn zero() {
+            check(0, 4, ["0", "1"]);
+        }
+
+        #[test]
+        fn one() {
+            check(1, 1, G);
+        }
+
+        #[test]
+        fn two() {
+            check(
+                2,
+                2,
+                [
+                    "17324563846726889236817837922625232543153115346355010501047597319863650987830",
+                    "20022170825455209233733649024450576091402881793145646502279487074566492066831",
+                ],
+            );
+        }
+
+        #[test]
+        fn odd_length() {
+            // 1234 on 11 bits, so that the most significant window is a single bit
+            check(
+                1234,
+                11,
+                [
+                    "878241207667111462574392327092174564415519427834800162597138774443407892386",
+                    "8115807049620949693065844408212138852475257294866292431651021475478905019419",
+                ],
+            );
+        }
+
+        #[test]
+        fn constraint_count() {
+            // 2 additions for the table, then 2 doublings and 1 addition per window after the first one
+            // each addition is 1 directive and 6 constraints
+            // each lookup is 3 selections of 2 constraints, and 2 constraints defining the selected coordinates
+            let f = scalar_mul::<Bn128Field>(8).collect();
+            assert_eq!(f.statements.len(), (2 + 3 * 3) * 7 + 4 * (3 * 2 + 2) + 2);
+        }
+    }
+
+    mod euclidean_div {
+        use super::*;
+
+        #[test]
+        fn constraint_count() {
+            // bn128 elements require 254 bits, so operands have 126 bits
+            assert_eq!(euclidean_div_bitwidth::<Bn128Field>(), 126);
+
+            // 1 directive, then 4 decompositions of 1 directive, 126 bitness checks and 1 sum check
+            // 1 product check and 2 definitions of the outputs
+            let f = euclidean_div::<Bn128Field>().collect();
+            assert_eq!(f.arguments.len(), 2);
+            assert_eq!(f.return_count, 2);
+            assert_eq!(f.statements.len(), 1 + 4 * (1 + 126 + 1) + 1 + 2);
+        }
+    }
+
     #[cfg(feature = "bellman")]
     #[cfg(test)]
     mod sha256 {
@@ -1,4 +1,5 @@
-use crate::common::SourceMetadata;
+use crate::common::flat::Variable;
+use crate::common::{FormatString, SourceMetadata};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Write;
@@ -28,11 +29,67 @@ pub enum RuntimeError {
     ShaXor,
     Division,
     SourceAssertion(SourceMetadata),
+    SourceAssertionMessage(SourceMetadata, AssertionMessage),
     SourceAssemblyConstraint(SourceMetadata),
     ArgumentBitness,
     SelectRangeCheck,
 }
 
+/// A user-provided assertion message, formatted with the values of its arguments at the failure point
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub struct AssertionMessage {
+    pub format_string: FormatString,
+    /// the variables whose values are substituted in the format string
+    pub arguments: Vec<Variable>,
+    /// the values of `arguments`, resolved by the interpreter when the assertion fails
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+impl AssertionMessage {
+    pub fn new(format_string: FormatString, arguments: Vec<Variable>) -> Self {
+        Self {
+            format_string,
+            arguments,
+            values: vec![],
+        }
+    }
+
+    /// resolve the values of the arguments, leaving them unresolved if any of them is unknown
+    pub fn resolve<F: Fn(&Variable) -> Option<String>>(self, f: F) -> Self {
+        let values = self
+            .arguments
+            .iter()
+            .map(f)
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        Self { values, ..self }
+    }
+
+    /// the formatted message, if the arguments were resolved and match the format string
+    pub fn format(&self) -> Option<String> {
+        if self.values.len() != self.arguments.len() {
+            return None;
+        }
+
+        self.format_string
+            .validate_arity(self.values.len())
+            .ok()
+            .map(|_| {
+                let mut res = self.format_string.parts[0].clone();
+                for (index, part) in self
+                    .format_string
+                    .argument_indices()
+                    .zip(&self.format_string.parts[1..])
+                {
+                    res.push_str(&self.values[index]);
+                    res.push_str(part);
+                }
+                res
+            })
+    }
+}
+
 impl From<crate::zir::RuntimeError> for RuntimeError {
     fn from(error: crate::zir::RuntimeError) -> Self {
         match error {
@@ -49,6 +106,16 @@ impl From<crate::zir::RuntimeError> for RuntimeError {
 }
 
 impl RuntimeError {
+    /// resolve the arguments of a user-provided assertion message, if any
+    pub fn resolve<F: Fn(&Variable) -> Option<String>>(self, f: F) -> Self {
+        match self {
+            RuntimeError::SourceAssertionMessage(metadata, message) => {
+                RuntimeError::SourceAssertionMessage(metadata, message.resolve(f))
+            }
+            e => e,
+        }
+    }
+
     pub fn is_malicious(&self) -> bool {
         use RuntimeError::*;
 
@@ -56,6 +123,7 @@ impl RuntimeError {
             self,
             SourceAssemblyConstraint(_)
                 | SourceAssertion(_)
+                | SourceAssertionMessage(..)
                 | Inverse
                 | SelectRangeCheck
                 | ArgumentBitness
@@ -98,6 +166,19 @@ impl fmt::Display for RuntimeError {
                 write!(&mut buf, "Assertion failed at {}", m).unwrap();
                 buf.as_str()
             }
+            SourceAssertionMessage(m, message) => {
+                // fall back to the location of the assertion if the message cannot be formatted
+                match message.format() {
+                    Some(message) => write!(
+                        &mut buf,
+                        "Assertion failed at {}:{}: \"{}\"",
+                        m.file, m.position, message
+                    )
+                    .unwrap(),
+                    None => write!(&mut buf, "Assertion failed at {}", m).unwrap(),
+                };
+                buf.as_str()
+            }
             SourceAssemblyConstraint(m) => {
                 write!(&mut buf, "Unsatisfied constraint at {}", m).unwrap();
                 buf.as_str()
@@ -109,3 +190,39 @@ impl fmt::Display for RuntimeError {
         write!(f, "{}", msg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assertion(format_string: &str, arguments: Vec<Variable>) -> RuntimeError {
+        RuntimeError::SourceAssertionMessage(
+            SourceMetadata::new(String::from("main.zok"), Default::default()),
+            AssertionMessage::new(FormatString::from(format_string), arguments),
+        )
+    }
+
+    #[test]
+    fn assertion_message() {
+        let e = assertion(
+            "expected {} to be {1}",
+            vec![Variable::new(0), Variable::new(1)],
+        );
+        let e = e.resolve(|v| Some(v.to_string()));
+        assert_eq!(
+            e.to_string(),
+            "Assertion failed at main.zok:0:0: \"expected _0 to be _1\""
+        );
+    }
+
+    #[test]
+    fn assertion_message_fallback() {
+        // unresolved arguments
+        let e = assertion("{}", vec![Variable::new(0)]).resolve(|_| None);
+        assert_eq!(e.to_string(), "Assertion failed at main.zok:0:0");
+
+        // arity mismatch
+        let e = assertion("{} {}", vec![Variable::new(0)]).resolve(|_| Some(String::from("42")));
+        assert_eq!(e.to_string(), "Assertion failed at main.zok:0:0");
+    }
+}
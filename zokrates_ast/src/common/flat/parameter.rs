@@ -1,19 +1,29 @@
-use crate::common::{Span, WithSpan};
+use crate::common::{Span, Visibility, WithSpan};
 
 use super::variable::Variable;
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Derivative)]
 #[derivative(PartialOrd, PartialEq, Eq, Hash, Ord)]
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Parameter {
     #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
     pub span: Option<Span>,
     pub id: Variable,
-    pub private: bool,
+    pub visibility: Visibility,
+    /// the name of the source-level argument this parameter was flattened from, if any
+    #[derivative(
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore",
+        Hash = "ignore"
+    )]
+    #[serde(default)]
+    pub name: Option<String>,
 }
 
 impl WithSpan for Parameter {
@@ -28,27 +38,57 @@ impl WithSpan for Parameter {
 }
 
 impl Parameter {
-    pub fn new(id: Variable, private: bool) -> Self {
+    pub fn new(id: Variable, visibility: Visibility) -> Self {
         Parameter {
             id,
-            private,
+            visibility,
             span: None,
+            name: None,
         }
     }
 
+    pub fn with_name(mut self, name: Option<String>) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// the source-level name of this parameter, falling back to its variable id
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.id.to_string())
+    }
+
     pub fn public(v: Variable) -> Self {
-        Self::new(v, false)
+        Self::new(v, Visibility::Public)
     }
 
     pub fn private(v: Variable) -> Self {
-        Self::new(v, true)
+        Self::new(v, Visibility::Private)
+    }
+
+    pub fn committed(v: Variable) -> Self {
+        Self::new(v, Visibility::Committed)
+    }
+}
+
+impl Parameter {
+    /// compare two parameters by their position in the layout of the inputs of a proof
+    ///
+    /// Public parameters come first, then private ones, then committed ones. Parameters with the same visibility are equal,
+    /// so a stable sort with this comparator keeps them in declaration order, see `sort_by_layout`.
+    /// The public parameters, in that order, followed by the outputs of the program, form the public input vector of verifiers.
+    pub fn layout_cmp(&self, other: &Parameter) -> Ordering {
+        self.visibility.cmp(&other.visibility)
+    }
+
+    /// sort `parameters` in layout order, keeping parameters with the same visibility in declaration order, see `layout_cmp`
+    pub fn sort_by_layout(parameters: &mut [Parameter]) {
+        parameters.sort_by(Parameter::layout_cmp);
     }
 }
 
 impl fmt::Display for Parameter {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let visibility = if self.private { "private " } else { "" };
-        write!(f, "{}{}", visibility, self.id)
+        write!(f, "{}{}", self.visibility, self.id)
     }
 }
 
@@ -56,8 +96,37 @@ impl Parameter {
     pub fn apply_substitution(self, substitution: &HashMap<Variable, Variable>) -> Parameter {
         Parameter {
             id: *substitution.get(&self.id).unwrap(),
-            private: self.private,
             ..self
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout() {
+        let mut parameters = vec![
+            Parameter::private(Variable::new(0)),
+            Parameter::committed(Variable::new(1)),
+            Parameter::public(Variable::new(4)),
+            Parameter::private(Variable::new(2)),
+            Parameter::public(Variable::new(3)),
+        ];
+
+        Parameter::sort_by_layout(&mut parameters);
+
+        // parameters with the same visibility stay in declaration order, regardless of their ids
+        assert_eq!(
+            parameters,
+            vec![
+                Parameter::public(Variable::new(4)),
+                Parameter::public(Variable::new(3)),
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(2)),
+                Parameter::committed(Variable::new(1)),
+            ]
+        );
+    }
+}
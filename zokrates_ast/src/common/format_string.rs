@@ -2,32 +2,300 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+/// A format string, where each placeholder is substituted by an argument.
+/// Placeholders are either all implicit (`{}`), taking the arguments in order, or all positional (`{0}`).
+/// `parts` holds the literal text around the placeholders, with escaped braces already resolved.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct FormatString {
     pub parts: Vec<String>,
+    /// the index of the argument substituted for each placeholder, empty in programs compiled before positional placeholders
+    #[serde(default)]
+    pub indices: Vec<usize>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum FormatError {
+    /// a `{` at the end of the string
+    UnmatchedBrace(usize),
+    /// a `}` which is neither closing a placeholder nor escaped
+    UnmatchedClosingBrace(usize),
+    /// a `{` which is neither opening a placeholder nor escaped
+    InvalidPlaceholder(usize),
+    /// a placeholder of one style in a string using the other one
+    MixedPlaceholders(usize),
+    /// a positional placeholder referring to a missing argument
+    IndexOutOfRange {
+        index: usize,
+        found: usize,
+    },
+    /// an argument which no placeholder refers to
+    UnusedArgument(usize),
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::UnmatchedBrace(i) => write!(
+                f,
+                "Unmatched `{{` at the end of the format string (position {}), use `{{{{` to escape it",
+                i
+            ),
+            FormatError::UnmatchedClosingBrace(i) => write!(
+                f,
+                "Unmatched `}}` in format string at position {}, use `}}}}` to escape it",
+                i
+            ),
+            FormatError::InvalidPlaceholder(i) => write!(
+                f,
+                "Invalid placeholder in format string at position {}, expected `{{}}` or `{{<index>}}`",
+                i
+            ),
+            FormatError::MixedPlaceholders(i) => write!(
+                f,
+                "Cannot mix `{{}}` and `{{<index>}}` placeholders in format string (position {})",
+                i
+            ),
+            FormatError::IndexOutOfRange { index, found } => write!(
+                f,
+                "Invalid placeholder `{{{}}}` in format string: only {} argument(s) were given",
+                index, found
+            ),
+            FormatError::UnusedArgument(i) => write!(
+                f,
+                "Argument {} is never used in format string",
+                i
+            ),
+            FormatError::ArityMismatch { expected, found } => write!(
+                f,
+                "Wrong argument count in format string: expected {}, got {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+fn escape(part: &str) -> String {
+    part.replace('{', "{{").replace('}', "}}")
 }
 
 impl fmt::Display for FormatString {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.parts.join("{}"))
+        let positional = !self.is_sequential();
+
+        write!(f, "{}", escape(&self.parts[0]))?;
+        for (index, part) in self.argument_indices().zip(&self.parts[1..]) {
+            match positional {
+                true => write!(f, "{{{}}}", index)?,
+                false => write!(f, "{{}}")?,
+            };
+            write!(f, "{}", escape(part))?;
+        }
+        Ok(())
     }
 }
 
 impl FormatString {
+    /// parse a format string, where `{}` and `{<index>}` are placeholders and `{{` and `}}` are escaped braces
+    pub fn parse(s: &str) -> Result<Self, FormatError> {
+        let mut parts = vec![];
+        let mut indices = vec![];
+        let mut current = String::new();
+        let mut implicit = false;
+        let mut positional = false;
+        let mut chars = s.char_indices().peekable();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '{' => match chars.next() {
+                    Some((_, '{')) => current.push('{'),
+                    Some((_, '}')) => {
+                        if positional {
+                            return Err(FormatError::MixedPlaceholders(i));
+                        }
+                        implicit = true;
+                        indices.push(indices.len());
+                        parts.push(std::mem::take(&mut current));
+                    }
+                    Some((_, d)) if d.is_ascii_digit() => {
+                        if implicit {
+                            return Err(FormatError::MixedPlaceholders(i));
+                        }
+                        positional = true;
+                        let mut digits = d.to_string();
+                        while let Some((_, d)) = chars.next_if(|(_, d)| d.is_ascii_digit()) {
+                            digits.push(d);
+                        }
+                        match chars.next() {
+                            Some((_, '}')) => {}
+                            Some(_) => return Err(FormatError::InvalidPlaceholder(i)),
+                            None => return Err(FormatError::UnmatchedBrace(i)),
+                        }
+                        indices.push(
+                            digits
+                                .parse()
+                                .map_err(|_| FormatError::InvalidPlaceholder(i))?,
+                        );
+                        parts.push(std::mem::take(&mut current));
+                    }
+                    Some(_) => return Err(FormatError::InvalidPlaceholder(i)),
+                    None => return Err(FormatError::UnmatchedBrace(i)),
+                },
+                '}' => match chars.next() {
+                    Some((_, '}')) => current.push('}'),
+                    _ => return Err(FormatError::UnmatchedClosingBrace(i)),
+                },
+                c => current.push(c),
+            }
+        }
+
+        parts.push(current);
+
+        Ok(FormatString { parts, indices })
+    }
+
     pub fn len(&self) -> usize {
-        self.parts.len() - 1
+        self.placeholder_count()
     }
 
     pub fn is_empty(&self) -> bool {
         self.parts.len() == 1
     }
+
+    pub fn placeholder_count(&self) -> usize {
+        self.parts.len() - 1
+    }
+
+    /// the index of the argument substituted for each placeholder, in order
+    pub fn argument_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.placeholder_count()).map(|i| self.indices.get(i).copied().unwrap_or(i))
+    }
+
+    /// whether each placeholder refers to the next argument
+    fn is_sequential(&self) -> bool {
+        self.argument_indices()
+            .enumerate()
+            .all(|(i, index)| i == index)
+    }
+
+    /// check that this format string can be used with `n` arguments
+    pub fn validate_arity(&self, n: usize) -> Result<(), FormatError> {
+        if self.is_sequential() {
+            return match self.placeholder_count() {
+                expected if expected == n => Ok(()),
+                expected => Err(FormatError::ArityMismatch { expected, found: n }),
+            };
+        }
+
+        if let Some(index) = self.argument_indices().find(|index| *index >= n) {
+            return Err(FormatError::IndexOutOfRange { index, found: n });
+        }
+
+        match (0..n).find(|i| !self.argument_indices().any(|index| index == *i)) {
+            Some(i) => Err(FormatError::UnusedArgument(i)),
+            None => Ok(()),
+        }
+    }
 }
 
 impl From<&str> for FormatString {
+    /// parse a format string, falling back to splitting on `{}` if it is malformed
     fn from(s: &str) -> Self {
-        let parts = s.split("{}").map(|p| p.to_string());
-        FormatString {
-            parts: parts.collect(),
-        }
+        Self::parse(s).unwrap_or_else(|_| {
+            let parts: Vec<_> = s.split("{}").map(|p| p.to_string()).collect();
+            FormatString {
+                indices: (0..parts.len() - 1).collect(),
+                parts,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders() {
+        let f = FormatString::parse("a {} b {}").unwrap();
+        assert_eq!(f.placeholder_count(), 2);
+        assert_eq!(f.parts, vec!["a ", " b ", ""]);
+        assert!(f.validate_arity(2).is_ok());
+        assert_eq!(
+            f.validate_arity(1),
+            Err(FormatError::ArityMismatch {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn escaped_braces() {
+        let f = FormatString::parse("{{}} {}").unwrap();
+        assert_eq!(f.placeholder_count(), 1);
+        assert_eq!(f.parts, vec!["{} ", ""]);
+        assert_eq!(f.to_string(), "{{}} {}");
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            FormatString::parse("a {"),
+            Err(FormatError::UnmatchedBrace(2))
+        );
+        assert_eq!(
+            FormatString::parse("a } b"),
+            Err(FormatError::UnmatchedClosingBrace(2))
+        );
+        assert_eq!(
+            FormatString::parse("{a}"),
+            Err(FormatError::InvalidPlaceholder(0))
+        );
+    }
+
+    #[test]
+    fn positional_placeholders() {
+        let f = FormatString::parse("{1} {0} {1}").unwrap();
+        assert_eq!(f.placeholder_count(), 3);
+        assert_eq!(f.argument_indices().collect::<Vec<_>>(), vec![1, 0, 1]);
+        assert_eq!(f.to_string(), "{1} {0} {1}");
+        assert!(f.validate_arity(2).is_ok());
+    }
+
+    #[test]
+    fn positional_out_of_range() {
+        let f = FormatString::parse("{0} {2}").unwrap();
+        assert_eq!(
+            f.validate_arity(2),
+            Err(FormatError::IndexOutOfRange { index: 2, found: 2 })
+        );
+        assert_eq!(f.validate_arity(3), Err(FormatError::UnusedArgument(1)));
+    }
+
+    #[test]
+    fn repeated_index() {
+        let f = FormatString::parse("{0}{0}").unwrap();
+        assert!(f.validate_arity(1).is_ok());
+        assert_eq!(f.validate_arity(2), Err(FormatError::UnusedArgument(1)));
+    }
+
+    #[test]
+    fn mixed_placeholders() {
+        assert_eq!(
+            FormatString::parse("{} {0}"),
+            Err(FormatError::MixedPlaceholders(3))
+        );
+        assert_eq!(
+            FormatString::parse("{0} {}"),
+            Err(FormatError::MixedPlaceholders(4))
+        );
+        assert_eq!(
+            FormatString::parse("{0"),
+            Err(FormatError::UnmatchedBrace(0))
+        );
     }
 }
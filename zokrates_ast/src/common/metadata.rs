@@ -1,3 +1,4 @@
+use crate::common::FlatEmbed;
 use crate::untyped::Position;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -32,3 +33,24 @@ impl fmt::Display for SourceMetadata {
         }
     }
 }
+
+/// Where a constraint comes from, to tell the constraints written by the programmer apart from those added by the compiler
+#[derive(Clone, Copy, Debug, PartialEq, Hash, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Origin {
+    /// the constraint was written in the source program
+    Source,
+    /// the constraint was generated when flattening a call to this embed
+    Embed(FlatEmbed),
+    /// the constraint was introduced or rewritten by an optimization
+    Optimizer,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::Source => write!(f, "source"),
+            Origin::Embed(embed) => write!(f, "embed {}", embed.id()),
+            Origin::Optimizer => write!(f, "optimizer"),
+        }
+    }
+}
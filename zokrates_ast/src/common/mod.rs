@@ -7,6 +7,7 @@ mod format_string;
 mod metadata;
 pub mod operators;
 mod parameter;
+mod poseidon_constants;
 mod position;
 mod solvers;
 pub mod statements;
@@ -14,15 +15,15 @@ mod value;
 mod variable;
 
 pub use self::embed::FlatEmbed;
-pub use self::error::RuntimeError;
+pub use self::error::{AssertionMessage, RuntimeError};
 pub use self::fold::{Fold, ResultFold};
-pub use self::metadata::SourceMetadata;
-pub use self::parameter::Parameter;
+pub use self::metadata::{Origin, SourceMetadata};
+pub use self::parameter::{Parameter, Visibility};
 pub use self::position::{
     LocalSourceSpan, ModuleId, ModuleIdHash, ModuleMap, OwnedModuleId, Position, SourceSpan, Span,
-    WithSpan,
+    SpanDisplay, SpanMap, WithSpan,
 };
-pub use self::solvers::{RefCall, Solver};
+pub use self::solvers::{Polynomial, RefCall, Solver, SolverError, MAX_POLYNOMIAL_DEGREE};
 pub use self::value::Value;
 pub use self::variable::Variable;
-pub use format_string::FormatString;
+pub use format_string::{FormatError, FormatString};
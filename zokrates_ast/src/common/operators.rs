@@ -1,3 +1,7 @@
+use crate::zir::{BooleanExpression, FieldElementExpression, UExpression, UExpressionInner};
+use num_bigint::BigUint;
+use zokrates_field::Field;
+
 pub trait OperatorStr {
     const STR: &'static str;
 }
@@ -141,3 +145,236 @@ pub struct OpPos;
 impl OperatorStr for OpPos {
     const STR: &'static str = "+";
 }
+
+/// Static properties of an operator, for analyses reasoning about the range of values
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OperatorMetadata {
+    /// whether the operator is implemented with field arithmetic, rather than on the bit decomposition of its operands
+    pub is_field_native: bool,
+    /// whether the result can wrap around the field modulus, or the bitwidth of its type
+    pub can_overflow: bool,
+    pub arity: u8,
+}
+
+impl OperatorMetadata {
+    const fn binary(is_field_native: bool, can_overflow: bool) -> Self {
+        Self {
+            is_field_native,
+            can_overflow,
+            arity: 2,
+        }
+    }
+
+    const fn unary(is_field_native: bool, can_overflow: bool) -> Self {
+        Self {
+            is_field_native,
+            can_overflow,
+            arity: 1,
+        }
+    }
+}
+
+pub trait OperatorInfo {
+    const METADATA: OperatorMetadata;
+
+    fn metadata() -> OperatorMetadata {
+        Self::METADATA
+    }
+}
+
+impl OperatorInfo for OpAdd {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(true, true);
+}
+
+impl OperatorInfo for OpSub {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(true, true);
+}
+
+impl OperatorInfo for OpFloorSub {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpMul {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(true, true);
+}
+
+impl OperatorInfo for OpDiv {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(true, true);
+}
+
+impl OperatorInfo for OpRem {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpPow {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(true, true);
+}
+
+impl OperatorInfo for OpEq {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(true, false);
+}
+
+impl OperatorInfo for OpLt {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpLe {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpGt {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpGe {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpXor {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpOr {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpAnd {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpLsh {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, true);
+}
+
+impl OperatorInfo for OpRsh {
+    const METADATA: OperatorMetadata = OperatorMetadata::binary(false, false);
+}
+
+impl OperatorInfo for OpNot {
+    const METADATA: OperatorMetadata = OperatorMetadata::unary(false, false);
+}
+
+impl OperatorInfo for OpNeg {
+    const METADATA: OperatorMetadata = OperatorMetadata::unary(true, true);
+}
+
+impl OperatorInfo for OpPos {
+    const METADATA: OperatorMetadata = OperatorMetadata::unary(true, false);
+}
+
+/// evaluate `e` to a field element if all its leaves are values, applying the semantics of each operator
+/// Returns `None` if `e` depends on a variable or divides by zero.
+/// Exponents, shift amounts and conditions are only evaluated if they are values themselves.
+pub fn eval_const<T: Field>(e: &FieldElementExpression<T>) -> Option<T> {
+    match e {
+        FieldElementExpression::Value(v) => Some(v.value),
+        FieldElementExpression::Identifier(_) | FieldElementExpression::Select(_) => None,
+        FieldElementExpression::Add(e) => Some(eval_const(&e.left)? + eval_const(&e.right)?),
+        FieldElementExpression::Sub(e) => Some(eval_const(&e.left)? - eval_const(&e.right)?),
+        FieldElementExpression::Mult(e) => Some(eval_const(&e.left)? * eval_const(&e.right)?),
+        FieldElementExpression::Div(e) => {
+            let left = eval_const(&e.left)?;
+            match eval_const(&e.right)? {
+                right if right == T::from(0) => None,
+                right => Some(left / right),
+            }
+        }
+        FieldElementExpression::Pow(e) => {
+            Some(eval_const(&e.left)?.pow(eval_const_uint(&e.right)? as usize))
+        }
+        FieldElementExpression::And(e) => eval_const_bitwise(&e.left, &e.right, |l, r| l & r),
+        FieldElementExpression::Or(e) => eval_const_bitwise(&e.left, &e.right, |l, r| l | r),
+        FieldElementExpression::Xor(e) => eval_const_bitwise(&e.left, &e.right, |l, r| l ^ r),
+        FieldElementExpression::LeftShift(e) => {
+            let by = eval_const_uint(&e.right)? as usize;
+            let mask = (BigUint::from(1usize) << T::get_required_bits()) - BigUint::from(1usize);
+            T::try_from((eval_const(&e.left)?.to_biguint() << by) & mask).ok()
+        }
+        FieldElementExpression::RightShift(e) => {
+            let by = eval_const_uint(&e.right)? as usize;
+            T::try_from(eval_const(&e.left)?.to_biguint() >> by).ok()
+        }
+        FieldElementExpression::Conditional(e) => match e.condition.as_ref() {
+            BooleanExpression::Value(c) if c.value => eval_const(&e.consequence),
+            BooleanExpression::Value(_) => eval_const(&e.alternative),
+            _ => None,
+        },
+    }
+}
+
+fn eval_const_bitwise<T: Field>(
+    left: &FieldElementExpression<T>,
+    right: &FieldElementExpression<T>,
+    op: impl FnOnce(BigUint, BigUint) -> BigUint,
+) -> Option<T> {
+    T::try_from(op(
+        eval_const(left)?.to_biguint(),
+        eval_const(right)?.to_biguint(),
+    ))
+    .ok()
+}
+
+fn eval_const_uint<T>(e: &UExpression<T>) -> Option<u128> {
+    match &e.inner {
+        UExpressionInner::Value(v) => Some(v.value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zir::{Expr, Id, UBitwidth};
+    use zokrates_field::Bn128Field;
+
+    fn n(v: i32) -> FieldElementExpression<'static, Bn128Field> {
+        FieldElementExpression::number(Bn128Field::from(v))
+    }
+
+    #[test]
+    fn metadata() {
+        assert_eq!(
+            OpAdd::metadata(),
+            OperatorMetadata {
+                is_field_native: true,
+                can_overflow: true,
+                arity: 2
+            }
+        );
+        assert!(!OpLsh::metadata().is_field_native);
+        assert!(!OpLt::metadata().can_overflow);
+        assert_eq!(OpNeg::metadata().arity, 1);
+    }
+
+    #[test]
+    fn nested_arithmetic() {
+        // (2 + 3) * (7 - 1) / 3 == 10
+        let e = (n(2) + n(3)) * (n(7) - n(1)) / n(3);
+        assert_eq!(eval_const(&e), Some(Bn128Field::from(10)));
+
+        // 2 ** 3 + 1 == 9
+        let e = n(2).pow(UExpression::value(3).annotate(UBitwidth::B32)) + n(1);
+        assert_eq!(eval_const(&e), Some(Bn128Field::from(9)));
+    }
+
+    #[test]
+    fn wraparound() {
+        let e = n(0) - n(1);
+        assert_eq!(eval_const(&e), Some(Bn128Field::max_value()));
+
+        let e = FieldElementExpression::number(Bn128Field::max_value()) + n(2);
+        assert_eq!(eval_const(&e), Some(Bn128Field::from(1)));
+    }
+
+    #[test]
+    fn division_by_zero() {
+        let e = n(1) / (n(2) - n(2));
+        assert_eq!(eval_const(&e), None);
+    }
+
+    #[test]
+    fn variable() {
+        let e = n(1) + FieldElementExpression::identifier("a".into());
+        assert_eq!(eval_const(&e), None);
+    }
+}
@@ -5,6 +5,37 @@ use serde::{Deserialize, Serialize};
 
 use super::{Span, WithSpan};
 
+/// How the value of a parameter is exposed to the verifier
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Visibility {
+    Public,
+    Private,
+    /// hidden from the verifier, but bound to a commitment in commit-and-prove schemes
+    Committed,
+}
+
+impl Visibility {
+    pub fn is_public(&self) -> bool {
+        *self == Visibility::Public
+    }
+
+    /// whether the value is hidden from the verifier, which committed values are
+    /// Backends which do not support commitments treat committed values as private ones
+    pub fn is_private(&self) -> bool {
+        !self.is_public()
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Visibility::Public => write!(f, ""),
+            Visibility::Private => write!(f, "private "),
+            Visibility::Committed => write!(f, "committed "),
+        }
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(PartialOrd, PartialEq, Hash, Eq)]
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -12,7 +43,7 @@ pub struct Parameter<V> {
     #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
     pub span: Option<Span>,
     pub id: V,
-    pub private: bool,
+    pub visibility: Visibility,
 }
 
 impl<V> From<V> for Parameter<V> {
@@ -22,20 +53,24 @@ impl<V> From<V> for Parameter<V> {
 }
 
 impl<V> Parameter<V> {
-    pub fn new(v: V, private: bool) -> Self {
+    pub fn new(v: V, visibility: Visibility) -> Self {
         Parameter {
             span: None,
             id: v,
-            private,
+            visibility,
         }
     }
 
     pub fn public(v: V) -> Self {
-        Self::new(v, false)
+        Self::new(v, Visibility::Public)
     }
 
     pub fn private(v: V) -> Self {
-        Self::new(v, true)
+        Self::new(v, Visibility::Private)
+    }
+
+    pub fn committed(v: V) -> Self {
+        Self::new(v, Visibility::Committed)
     }
 }
 
@@ -52,7 +87,6 @@ impl<V> WithSpan for Parameter<V> {
 
 impl<V: fmt::Display> fmt::Display for Parameter<V> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let visibility = if self.private { "private " } else { "" };
-        write!(f, "{}{}", visibility, self.id)
+        write!(f, "{}{}", self.visibility, self.id)
     }
 }
@@ -1,10 +1,11 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
     fmt,
     path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use super::FlatEmbed;
 
@@ -26,9 +27,32 @@ pub struct ModuleMap {
 }
 
 impl ModuleMap {
+    /// Panics in debug builds if two distinct modules have the same hash, see `insert`
     pub fn new<I: IntoIterator<Item = OwnedModuleId>>(i: I) -> Self {
-        Self {
-            modules: i.into_iter().map(|id| (hash(&id), id)).collect(),
+        let mut map = Self::default();
+        for id in i {
+            let res = map.insert(id);
+            debug_assert!(res.is_ok(), "{}", res.unwrap_err());
+        }
+        map
+    }
+
+    /// add `id` to this map, returning its hash
+    /// Fails if another module has the same hash, as spans in that module would otherwise point to the wrong one
+    pub fn insert(&mut self, id: OwnedModuleId) -> Result<ModuleIdHash, String> {
+        let h = hash(&id);
+
+        match self.modules.get(&h) {
+            Some(existing) if *existing != id => Err(format!(
+                "Modules `{}` and `{}` have the same hash `{}`",
+                existing.display(),
+                id.display(),
+                h
+            )),
+            _ => {
+                self.modules.insert(h, id);
+                Ok(h)
+            }
         }
     }
 
@@ -48,8 +72,71 @@ impl ModuleMap {
                 .collect(),
         }
     }
+
+    /// add the modules of `other` to this map, returning how to remap the module hashes of `other`
+    /// Modules present in both maps are shared, and a module whose hash is already used by another path gets a fresh one
+    pub fn merge(&mut self, other: &ModuleMap) -> HashMap<ModuleIdHash, ModuleIdHash> {
+        other
+            .modules
+            .iter()
+            .map(|(id, path)| {
+                let existing = self
+                    .modules
+                    .iter()
+                    .find(|(_, p)| *p == path)
+                    .map(|(id, _)| *id);
+
+                let new_id = match existing {
+                    Some(existing) => existing,
+                    None => {
+                        let mut new_id = *id;
+                        while self.modules.contains_key(&new_id) {
+                            new_id = new_id.wrapping_add(1);
+                        }
+                        self.modules.insert(new_id, path.clone());
+                        new_id
+                    }
+                };
+                (*id, new_id)
+            })
+            .collect()
+    }
+
+    /// a SHA-256 hash of the modules of this map, reading the contents of each module with `read`
+    ///
+    /// The hash covers the path and the contents of each module, in the order of their paths.
+    /// Paths are hashed with `/` separators, so that the hash does not depend on the platform.
+    pub fn source_hash<F: FnMut(&ModuleId) -> std::io::Result<Vec<u8>>>(
+        &self,
+        mut read: F,
+    ) -> std::io::Result<[u8; 32]> {
+        let mut hasher = Sha256::new();
+        hasher.update(SOURCE_HASH_DOMAIN);
+
+        let mut paths: Vec<_> = self.modules.values().collect();
+        paths.sort();
+
+        for path in paths {
+            let name = path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let contents = read(path)?;
+
+            hasher.update((name.len() as u64).to_le_bytes());
+            hasher.update(name.as_bytes());
+            hasher.update((contents.len() as u64).to_le_bytes());
+            hasher.update(&contents);
+        }
+
+        Ok(hasher.finalize().into())
+    }
 }
 
+/// Separates source hashes from other uses of SHA-256, and from future versions of this hash
+const SOURCE_HASH_DOMAIN: &[u8] = b"zokrates-source-hash-v1";
+
 #[derive(Clone, PartialEq, Eq, Copy, Hash, Default, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct Position {
     pub line: usize,
@@ -97,6 +184,55 @@ impl Span {
             Span::Embed(s) => ResolvedSpan::Embed(s),
         }
     }
+
+    /// the smallest span enclosing both `self` and `other`, if they are in the same module
+    pub fn try_merge(self, other: Span) -> Option<Span> {
+        match (self, other) {
+            (Span::Source(s), Span::Source(o)) => s.try_merge(o).map(Span::Source),
+            (Span::Embed(s), Span::Embed(o)) if s == o => Some(Span::Embed(s)),
+            _ => None,
+        }
+    }
+
+    /// the smallest span enclosing both `self` and `other`
+    /// Panics if they are not in the same module, see `try_merge` for a fallible version
+    pub fn merge(self, other: Span) -> Span {
+        self.try_merge(other)
+            .expect("cannot merge spans from different modules")
+    }
+
+    /// whether `pos` lies within this span, bounds included
+    pub fn contains(&self, pos: Position) -> bool {
+        match self {
+            Span::Source(s) => s.contains(pos),
+            Span::Embed(_) => false,
+        }
+    }
+}
+
+/// A span which can be displayed as `module:line:col-line:col`, see `Span::display`
+pub struct SpanDisplay<'a> {
+    span: Span,
+    map: &'a ModuleMap,
+}
+
+impl Span {
+    /// display this span as a range in its module, resolving the module path through `map`
+    pub fn display<'a>(&self, map: &'a ModuleMap) -> SpanDisplay<'a> {
+        SpanDisplay { span: *self, map }
+    }
+}
+
+impl<'a> fmt::Display for SpanDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.span {
+            Span::Source(s) => match self.map.modules.get(&s.module) {
+                Some(module) => write!(f, "{}:{}", module.display(), s.local()),
+                None => write!(f, "<unknown module>:{}", s.local()),
+            },
+            Span::Embed(e) => write!(f, "{:?}", e),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Copy, Hash, Default, PartialOrd, Ord, Deserialize, Serialize)]
@@ -126,6 +262,29 @@ impl From<FlatEmbed> for Span {
 }
 
 impl SourceSpan {
+    /// the smallest span enclosing both `self` and `other`, if they are in the same module
+    pub fn try_merge(self, other: SourceSpan) -> Option<SourceSpan> {
+        (self.module == other.module).then(|| {
+            let span = self.local().merge(other.local());
+            SourceSpan {
+                module: self.module,
+                from: span.from,
+                to: span.to,
+            }
+        })
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        self.local().contains(pos)
+    }
+
+    fn local(&self) -> LocalSourceSpan {
+        LocalSourceSpan {
+            from: self.from,
+            to: self.to,
+        }
+    }
+
     pub fn mock() -> Self {
         Self {
             module: hash(&OwnedModuleId::default()),
@@ -145,13 +304,17 @@ pub trait WithSpan: Sized {
     fn get_span(&self) -> Option<Span>;
 }
 
-fn hash(id: &ModuleId) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let mut hasher = DefaultHasher::new();
-    id.hash(&mut hasher);
-    hasher.finish()
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// hash a module id with 64-bit FNV-1a over the bytes of its path, lossily converted to UTF-8
+/// Unlike `DefaultHasher`, the algorithm is fixed, so hashes stored in serialized programs stay valid across builds
+fn hash(id: &ModuleId) -> ModuleIdHash {
+    id.to_string_lossy()
+        .bytes()
+        .fold(FNV_OFFSET_BASIS, |acc, b| {
+            (acc ^ b as u64).wrapping_mul(FNV_PRIME)
+        })
 }
 
 impl LocalSourceSpan {
@@ -163,6 +326,18 @@ impl LocalSourceSpan {
         }
     }
 
+    /// the smallest span enclosing both `self` and `other`
+    pub fn merge(self, other: LocalSourceSpan) -> LocalSourceSpan {
+        LocalSourceSpan {
+            from: self.from.min(other.from),
+            to: self.to.max(other.to),
+        }
+    }
+
+    pub fn contains(&self, pos: Position) -> bool {
+        self.from <= pos && pos <= self.to
+    }
+
     pub fn mock() -> Self {
         Self {
             from: Position::mock(),
@@ -171,6 +346,15 @@ impl LocalSourceSpan {
     }
 }
 
+impl fmt::Display for LocalSourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.from.line == self.to.line {
+            true => write!(f, "{}-{}", self.from, self.to.col),
+            false => write!(f, "{}-{}", self.from, self.to),
+        }
+    }
+}
+
 impl Position {
     pub fn col(&self, delta: isize) -> Position {
         assert!(self.col <= isize::max_value() as usize);
@@ -219,6 +403,44 @@ impl fmt::Display for ResolvedSourceSpan {
     }
 }
 
+/// Maps spans of generated source, such as the output of a preprocessor, back to the spans it was generated from
+/// Each mapping covers an interval of a generated module. A span enclosed by several intervals resolves through the narrowest one, so that nested expansions resolve to their most precise origin.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SpanMap {
+    intervals: BTreeMap<ModuleIdHash, Vec<(LocalSourceSpan, Span)>>,
+}
+
+impl SpanMap {
+    /// map the interval `generated` to `original`, which takes precedence over an identical interval inserted before
+    pub fn insert(&mut self, generated: SourceSpan, original: Span) {
+        self.intervals
+            .entry(generated.module)
+            .or_default()
+            .push((generated.local(), original));
+    }
+
+    /// the original span of `span`, which is the span the narrowest interval enclosing it is mapped to
+    /// Spans which no interval encloses, including spans of embeds, are returned unchanged
+    pub fn resolve(&self, span: Span) -> Span {
+        let s = match span {
+            Span::Source(s) => s,
+            span => return span,
+        };
+
+        self.intervals
+            .get(&s.module)
+            .and_then(|intervals| {
+                intervals
+                    .iter()
+                    .filter(|(generated, _)| generated.contains(s.from) && generated.contains(s.to))
+                    // the narrowest of nested intervals starts last and ends first
+                    .max_by_key(|(generated, _)| (generated.from, std::cmp::Reverse(generated.to)))
+            })
+            .map(|(_, original)| *original)
+            .unwrap_or(span)
+    }
+}
+
 #[test]
 fn position_col() {
     let pos = Position {
@@ -240,3 +462,189 @@ fn position_col() {
         }
     );
 }
+
+#[test]
+fn span_merge() {
+    let span = |module, from: (usize, usize), to: (usize, usize)| {
+        Span::Source(SourceSpan {
+            module,
+            from: Position {
+                line: from.0,
+                col: from.1,
+            },
+            to: Position {
+                line: to.0,
+                col: to.1,
+            },
+        })
+    };
+
+    assert_eq!(
+        span(0, (1, 5), (1, 10)).merge(span(0, (2, 1), (2, 3))),
+        span(0, (1, 5), (2, 3))
+    );
+    assert_eq!(
+        span(0, (2, 1), (3, 1)).merge(span(0, (1, 5), (2, 3))),
+        span(0, (1, 5), (3, 1))
+    );
+    assert_eq!(
+        span(0, (1, 5), (1, 10)).try_merge(span(1, (2, 1), (2, 3))),
+        None
+    );
+}
+
+#[test]
+fn span_contains() {
+    let span = Span::Source(SourceSpan {
+        module: 0,
+        from: Position { line: 1, col: 5 },
+        to: Position { line: 3, col: 2 },
+    });
+
+    assert!(span.contains(Position { line: 1, col: 5 }));
+    assert!(span.contains(Position { line: 2, col: 100 }));
+    assert!(span.contains(Position { line: 3, col: 2 }));
+    assert!(!span.contains(Position { line: 1, col: 4 }));
+    assert!(!span.contains(Position { line: 3, col: 3 }));
+}
+
+#[test]
+fn span_display() {
+    let map = ModuleMap::new(vec!["foo/bar.zok".into()]);
+
+    let span = |from: (usize, usize), to: (usize, usize)| {
+        Span::Source(SourceSpan {
+            module: hash(Path::new("foo/bar.zok")),
+            from: Position {
+                line: from.0,
+                col: from.1,
+            },
+            to: Position {
+                line: to.0,
+                col: to.1,
+            },
+        })
+    };
+
+    assert_eq!(
+        span((1, 5), (1, 10)).display(&map).to_string(),
+        "foo/bar.zok:1:5-10"
+    );
+    assert_eq!(
+        span((1, 5), (3, 2)).display(&map).to_string(),
+        "foo/bar.zok:1:5-3:2"
+    );
+    assert_eq!(
+        span((1, 5), (1, 10))
+            .display(&ModuleMap::default())
+            .to_string(),
+        "<unknown module>:1:5-10"
+    );
+}
+
+#[test]
+fn module_map_merge() {
+    let mut map = ModuleMap::new(vec!["main.zok".into(), "common.zok".into()]);
+    let other = ModuleMap::new(vec!["common.zok".into(), "other.zok".into()]);
+
+    let remapping = map.merge(&other);
+
+    // overlapping and disjoint modules keep their hash, as it is derived from the path
+    assert_eq!(remapping.len(), 2);
+    assert!(remapping.iter().all(|(from, to)| from == to));
+    assert_eq!(
+        map,
+        ModuleMap::new(vec![
+            "main.zok".into(),
+            "common.zok".into(),
+            "other.zok".into()
+        ])
+    );
+
+    // a hash already used by another path is remapped
+    let mut map = ModuleMap {
+        modules: vec![(0, "main.zok".into())].into_iter().collect(),
+    };
+    let other = ModuleMap {
+        modules: vec![(0, "other.zok".into()), (1, "main.zok".into())]
+            .into_iter()
+            .collect(),
+    };
+
+    let remapping = map.merge(&other);
+
+    assert_eq!(remapping[&1], 0);
+    let new_id = remapping[&0];
+    assert_ne!(new_id, 0);
+    assert_eq!(map.modules[&new_id], PathBuf::from("other.zok"));
+}
+
+#[test]
+fn module_id_hash() {
+    // 64-bit FNV-1a test vectors
+    assert_eq!(hash(Path::new("")), 0xcbf29ce484222325);
+    assert_eq!(hash(Path::new("a")), 0xaf63dc4c8601ec8c);
+    assert_eq!(hash(Path::new("foobar")), 0x85944171f73967e8);
+}
+
+#[test]
+fn module_map_insert() {
+    let mut map = ModuleMap::default();
+
+    let h = map.insert("main.zok".into()).unwrap();
+    assert_eq!(h, hash(Path::new("main.zok")));
+    assert_eq!(map.insert("main.zok".into()), Ok(h));
+
+    // simulate a collision with another path
+    map.modules
+        .insert(hash(Path::new("other.zok")), "main.zok".into());
+    assert!(map.insert("other.zok".into()).is_err());
+}
+
+#[test]
+fn span_map() {
+    let generated_id = hash(Path::new("generated.zok"));
+
+    let span = |module: ModuleIdHash, from: (usize, usize), to: (usize, usize)| SourceSpan {
+        module,
+        from: Position {
+            line: from.0,
+            col: from.1,
+        },
+        to: Position {
+            line: to.0,
+            col: to.1,
+        },
+    };
+
+    let original = Span::Source(span(hash(Path::new("main.zok")), (3, 1), (3, 20)));
+    let included = Span::Source(span(hash(Path::new("include.zok")), (1, 1), (8, 2)));
+
+    let mut map = SpanMap::default();
+    map.insert(span(generated_id, (1, 1), (40, 1)), included);
+    // an expansion nested in the included lines
+    map.insert(span(generated_id, (10, 1), (12, 5)), original);
+
+    // the narrowest enclosing interval is used
+    assert_eq!(
+        map.resolve(Span::Source(span(generated_id, (11, 3), (11, 9)))),
+        original
+    );
+    assert_eq!(
+        map.resolve(Span::Source(span(generated_id, (20, 1), (20, 4)))),
+        included
+    );
+    // a span which overflows the nested interval resolves through the outer one
+    assert_eq!(
+        map.resolve(Span::Source(span(generated_id, (11, 3), (13, 1)))),
+        included
+    );
+
+    // unmapped spans pass through unchanged
+    let unmapped = Span::Source(span(generated_id, (41, 1), (41, 2)));
+    assert_eq!(map.resolve(unmapped), unmapped);
+    let other_module = Span::Source(span(hash(Path::new("lib.zok")), (11, 3), (11, 9)));
+    assert_eq!(map.resolve(other_module), other_module);
+    let embed = Span::Embed(FlatEmbed::Unpack);
+    assert_eq!(map.resolve(embed), embed);
+}
@@ -1,13 +1,77 @@
-use crate::zir::ZirFunction;
+use crate::common::{Span, WithSpan};
+use crate::zir::{Typed, ZirFunction, ZirStatement};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::fmt;
+use zokrates_field::Field;
 
-#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Hash, Eq)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Hash, Eq, PartialOrd, Ord)]
 pub struct RefCall {
     pub index: usize,
     pub signature: (usize, usize),
 }
 
+/// The largest total degree of a term of a `Polynomial`
+pub const MAX_POLYNOMIAL_DEGREE: u32 = 256;
+
+/// A sparse multivariate polynomial `p(i0, i1, ...)`, given as a sum of terms
+/// Each term is a coefficient and the exponent of each variable, so that `(3, [2, 1])` is `3 * i0^2 * i1`.
+/// A zero exponent makes a variable absent from a term, even if it is zero, so a term whose exponents are all zero is a constant.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Hash, Eq, PartialOrd, Ord)]
+pub struct Polynomial<T> {
+    /// the number of variables of the polynomial
+    pub variables: usize,
+    pub terms: Vec<(T, Vec<u32>)>,
+}
+
+impl<T> Polynomial<T> {
+    pub fn new(variables: usize, terms: Vec<(T, Vec<u32>)>) -> Self {
+        Polynomial { variables, terms }
+    }
+}
+
+impl<T: Field> Polynomial<T> {
+    /// evaluate the polynomial on `inputs`, one per variable
+    pub fn evaluate(&self, inputs: &[T]) -> T {
+        self.terms
+            .iter()
+            .fold(T::zero(), |acc, (coefficient, exponents)| {
+                acc + inputs
+                    .iter()
+                    .zip(exponents)
+                    .fold(*coefficient, |acc, (input, exponent)| {
+                        acc * input.pow(*exponent as usize)
+                    })
+            })
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Polynomial<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.terms.is_empty() {
+            return write!(f, "0");
+        }
+
+        let terms: Vec<String> =
+            self.terms
+                .iter()
+                .map(|(coefficient, exponents)| {
+                    std::iter::once(coefficient.to_string())
+                        .chain(exponents.iter().enumerate().filter(|(_, e)| **e != 0).map(
+                            |(i, e)| match e {
+                                1 => format!("i{}", i),
+                                e => format!("i{}^{}", i, e),
+                            },
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(" * ")
+                })
+                .collect();
+
+        write!(f, "{}", terms.join(" + "))
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Hash, Eq)]
 pub enum Solver<'ast, T> {
     ConditionEq,
@@ -21,10 +85,13 @@ pub enum Solver<'ast, T> {
     #[serde(borrow)]
     Zir(ZirFunction<'ast, T>),
     Ref(RefCall),
+    ConditionalInverse,
+    BabyJubjubAdd,
     #[cfg(feature = "bellman")]
     Sha256Round,
     #[cfg(feature = "ark")]
     SnarkVerifyBls12377(usize),
+    Polynomial(Polynomial<T>),
 }
 
 impl<'ast, T> fmt::Display for Solver<'ast, T> {
@@ -40,10 +107,13 @@ impl<'ast, T> fmt::Display for Solver<'ast, T> {
             Solver::EuclideanDiv => write!(f, "EuclideanDiv"),
             Solver::Zir(_) => write!(f, "Zir(..)"),
             Solver::Ref(call) => write!(f, "Ref@{}", call.index),
+            Solver::ConditionalInverse => write!(f, "ConditionalInverse"),
+            Solver::BabyJubjubAdd => write!(f, "BabyJubjubAdd"),
             #[cfg(feature = "bellman")]
             Solver::Sha256Round => write!(f, "Sha256Round"),
             #[cfg(feature = "ark")]
             Solver::SnarkVerifyBls12377(n) => write!(f, "SnarkVerifyBls12377({})", n),
+            Solver::Polynomial(p) => write!(f, "Polynomial({})", p.variables),
         }
     }
 }
@@ -61,11 +131,189 @@ impl<'ast, T> Solver<'ast, T> {
             Solver::EuclideanDiv => (2, 2),
             Solver::Zir(f) => (f.signature.inputs.len(), f.signature.outputs.len()),
             Solver::Ref(c) => c.signature,
+            Solver::ConditionalInverse => (1, 2),
+            Solver::BabyJubjubAdd => (4, 2),
             #[cfg(feature = "bellman")]
             Solver::Sha256Round => (768, 26935),
             #[cfg(feature = "ark")]
             Solver::SnarkVerifyBls12377(n) => (26 + 3 * n, 41991 + 4972 * n),
+            Solver::Polynomial(p) => (p.variables, 1),
+        }
+    }
+}
+
+impl<'ast, T> Solver<'ast, T> {
+    fn variant_index(&self) -> usize {
+        match self {
+            Solver::ConditionEq => 0,
+            Solver::Bits(_) => 1,
+            Solver::Div => 2,
+            Solver::Xor => 3,
+            Solver::Or => 4,
+            Solver::ShaAndXorAndXorAnd => 5,
+            Solver::ShaCh => 6,
+            Solver::EuclideanDiv => 7,
+            Solver::Zir(_) => 8,
+            Solver::Ref(_) => 9,
+            Solver::ConditionalInverse => 10,
+            Solver::BabyJubjubAdd => 11,
+            #[cfg(feature = "bellman")]
+            Solver::Sha256Round => 12,
+            #[cfg(feature = "ark")]
+            Solver::SnarkVerifyBls12377(_) => 13,
+            Solver::Polynomial(_) => 14,
+        }
+    }
+}
+
+impl<'ast, T: Field> PartialOrd for Solver<'ast, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Zir functions are not ordered, so distinct ones are compared by their representation, which ignores spans
+impl<'ast, T: Field> Ord for Solver<'ast, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Solver::Bits(a), Solver::Bits(b)) => a.cmp(b),
+            (Solver::Zir(a), Solver::Zir(b)) if a == b => Ordering::Equal,
+            (Solver::Zir(a), Solver::Zir(b)) => a
+                .to_string()
+                .cmp(&b.to_string())
+                .then_with(|| format!("{:?}", a).cmp(&format!("{:?}", b))),
+            (Solver::Ref(a), Solver::Ref(b)) => a.cmp(b),
+            #[cfg(feature = "ark")]
+            (Solver::SnarkVerifyBls12377(a), Solver::SnarkVerifyBls12377(b)) => a.cmp(b),
+            (Solver::Polynomial(a), Solver::Polynomial(b)) => a.cmp(b),
+            _ => self.variant_index().cmp(&other.variant_index()),
+        }
+    }
+}
+
+/// An error found when validating a solver
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SolverError {
+    /// the index of the solver in the solvers section, if it is stored there
+    pub index: Option<usize>,
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+impl SolverError {
+    pub fn new(message: String) -> Self {
+        SolverError {
+            index: None,
+            span: None,
+            message,
+        }
+    }
+
+    pub fn index(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self
+    }
+}
+
+impl WithSpan for SolverError {
+    fn span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    fn get_span(&self) -> Option<Span> {
+        self.span
+    }
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid solver")?;
+        if let Some(index) = self.index {
+            write!(f, " #{}", index)?;
+        }
+        if let Some(span) = self.span {
+            write!(f, " at {}", span)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+impl<'ast, T: Field> Solver<'ast, T> {
+    /// check that this solver is well-formed, ie. that it produces values matching its signature
+    /// Only zir and polynomial solvers can be malformed, as the signature of other solvers is fixed
+    pub fn validate(&self) -> Result<(), SolverError> {
+        match self {
+            Solver::Polynomial(p) => {
+                for (index, (_, exponents)) in p.terms.iter().enumerate() {
+                    if exponents.len() != p.variables {
+                        return Err(SolverError::new(format!(
+                            "term {} has {} exponent(s), expected one per variable ({})",
+                            index,
+                            exponents.len(),
+                            p.variables
+                        )));
+                    }
+
+                    let degree = exponents.iter().map(|e| *e as u64).sum::<u64>();
+                    if degree > MAX_POLYNOMIAL_DEGREE as u64 {
+                        return Err(SolverError::new(format!(
+                            "term {} has degree {}, which exceeds the maximum of {}",
+                            index, degree, MAX_POLYNOMIAL_DEGREE
+                        )));
+                    }
+                }
+                Ok(())
+            }
+            Solver::Zir(f) => {
+                if f.arguments.len() != f.signature.inputs.len()
+                    || f.arguments
+                        .iter()
+                        .zip(&f.signature.inputs)
+                        .any(|(a, ty)| &a.id.ty != ty)
+                {
+                    return Err(SolverError::new(format!(
+                        "arguments do not match the declared inputs {:?}",
+                        f.signature.inputs
+                    )));
+                }
+
+                match f.statements.last() {
+                    Some(ZirStatement::Return(r)) => {
+                        let types: Vec<_> = r.inner.iter().map(|e| e.get_type()).collect();
+                        if types != f.signature.outputs {
+                            return Err(SolverError::new(format!(
+                                "returns {} value(s) of type {:?}, but declares outputs {:?}",
+                                types.len(),
+                                types,
+                                f.signature.outputs
+                            )));
+                        }
+                        Ok(())
+                    }
+                    _ => Err(SolverError::new(String::from(
+                        "does not end with a return statement",
+                    ))),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// check that this solver is called with as many inputs and outputs as its signature declares
+    pub fn validate_arity(&self, inputs: usize, outputs: usize) -> Result<(), SolverError> {
+        let (expected_inputs, expected_outputs) = self.get_signature();
+
+        if (inputs, outputs) != (expected_inputs, expected_outputs) {
+            return Err(SolverError::new(format!(
+                "expected {} input(s) and {} output(s), found {} and {}",
+                expected_inputs, expected_outputs, inputs, outputs
+            )));
         }
+
+        Ok(())
     }
 }
 
@@ -74,3 +322,186 @@ impl<'ast, T> Solver<'ast, T> {
         Solver::Bits(width)
     }
 }
+
+/// list the registers `prefix0` to `prefix{count - 1}`, eliding the middle ones if there are many
+fn registers(prefix: &str, count: usize) -> String {
+    match count {
+        0 => String::from("none"),
+        1..=4 => (0..count)
+            .map(|i| format!("{}{}", prefix, i))
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => format!("{}0, ..., {}{}", prefix, prefix, count - 1),
+    }
+}
+
+impl<'ast, T: fmt::Display> Solver<'ast, T> {
+    /// a human-readable listing of the computation of this solver, from its inputs `i*` to its outputs `o*`
+    /// Solvers only compute hints for the witness, so this is meant for auditing and does not say anything about the constraints
+    pub fn disassemble(&self) -> String {
+        let (inputs, outputs) = self.get_signature();
+
+        let steps: Vec<String> = match self {
+            Solver::ConditionEq => vec![
+                "o0 = i0 == 0 ? 0 : 1".into(),
+                "o1 = i0 == 0 ? 1 : 1 / i0".into(),
+            ],
+            Solver::Bits(n) => vec![format!(
+                "{} = the {} least significant bits of i0, most significant first",
+                registers("o", *n),
+                n
+            )],
+            Solver::Div => vec!["o0 = i1 == 0 ? 1 : i0 / i1".into()],
+            Solver::Xor => vec!["o0 = i0 + i1 - 2 * i0 * i1".into()],
+            Solver::Or => vec!["o0 = i0 + i1 - i0 * i1".into()],
+            Solver::ShaAndXorAndXorAnd => {
+                vec!["o0 = i1 * i2 - (2 * i1 * i2 - i1 - i2) * i0".into()]
+            }
+            Solver::ShaCh => vec!["o0 = i0 * (i1 - i2) + i2".into()],
+            Solver::EuclideanDiv => vec![
+                "o0 = i1 == 0 ? 0 : i0 / i1, as integers".into(),
+                "o1 = i0 - i1 * o0, as integers".into(),
+            ],
+            Solver::ConditionalInverse => vec![
+                "o0 = i0 == 0 ? 0 : 1 / i0".into(),
+                "o1 = i0 == 0 ? 1 : 0".into(),
+            ],
+            Solver::BabyJubjubAdd => vec![
+                "o0 = (i0 * i3 + i1 * i2) / (1 + 168696 * i0 * i1 * i2 * i3)".into(),
+                "o1 = (i1 * i3 - 168700 * i0 * i2) / (1 - 168696 * i0 * i1 * i2 * i3)".into(),
+            ],
+            Solver::Zir(f) => vec![format!(
+                "{} = call {} with {}",
+                registers("o", outputs),
+                f.to_string().replace('\n', "\n\t"),
+                registers("i", inputs)
+            )],
+            Solver::Ref(call) => vec![format!(
+                "{} = call solver #{} with {}",
+                registers("o", outputs),
+                call.index,
+                registers("i", inputs)
+            )],
+            #[cfg(feature = "bellman")]
+            Solver::Sha256Round => vec![format!(
+                "{} = sha256 round on message i0, ..., i511 and state i512, ..., i767, computed natively",
+                registers("o", outputs)
+            )],
+            #[cfg(feature = "ark")]
+            Solver::SnarkVerifyBls12377(n) => vec![format!(
+                "{} = verification of a proof over bls12_377 with {} public input(s), computed natively",
+                registers("o", outputs),
+                n
+            )],
+            Solver::Polynomial(p) => vec![format!("o0 = {}", p)],
+        };
+
+        std::iter::once(format!("{}", self))
+            .chain(std::iter::once(format!(
+                "\tinputs: {}",
+                registers("i", inputs)
+            )))
+            .chain(steps.into_iter().map(|s| format!("\t{}", s)))
+            .chain(std::iter::once(format!(
+                "\toutputs: {}",
+                registers("o", outputs)
+            )))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zir::{
+        types::{Signature, Type},
+        FieldElementExpression, Identifier, IdentifierExpression, Parameter, Variable,
+    };
+    use std::ops::Mul;
+    use zokrates_field::Bn128Field;
+
+    fn square(outputs: Vec<Type>) -> Solver<'static, Bn128Field> {
+        let id = IdentifierExpression::new(Identifier::internal(0usize));
+
+        // (field i0) -> i0 * i0
+        Solver::Zir(ZirFunction {
+            arguments: vec![Parameter::private(Variable::field_element(id.id.clone()))],
+            statements: vec![ZirStatement::ret(vec![FieldElementExpression::mul(
+                FieldElementExpression::Identifier(id.clone()),
+                FieldElementExpression::Identifier(id),
+            )
+            .into()])],
+            signature: Signature::new()
+                .inputs(vec![Type::FieldElement])
+                .outputs(outputs),
+        })
+    }
+
+    #[test]
+    fn validate_zir() {
+        assert!(square(vec![Type::FieldElement]).validate().is_ok());
+        assert!(square(vec![Type::FieldElement, Type::FieldElement])
+            .validate()
+            .is_err());
+        assert!(square(vec![Type::Boolean]).validate().is_err());
+    }
+
+    #[test]
+    fn disassemble() {
+        let solver: Solver<Bn128Field> = Solver::Div;
+        assert_eq!(
+            solver.disassemble(),
+            "Div\n\tinputs: i0, i1\n\to0 = i1 == 0 ? 1 : i0 / i1\n\toutputs: o0"
+        );
+
+        let solver: Solver<Bn128Field> = Solver::Bits(8);
+        assert_eq!(
+            solver.disassemble(),
+            "Bits(8)\n\tinputs: i0\n\to0, ..., o7 = the 8 least significant bits of i0, most significant first\n\toutputs: o0, ..., o7"
+        );
+
+        let listing = square(vec![Type::FieldElement]).disassemble();
+        assert!(listing.starts_with("Zir(..)\n\tinputs: i0\n\to0 = call ("));
+        assert!(listing.ends_with("with i0\n\toutputs: o0"));
+    }
+
+    #[test]
+    fn polynomial() {
+        // 3 * i0^2 * i1 + 5
+        let p = Polynomial::new(
+            2,
+            vec![
+                (Bn128Field::from(3), vec![2, 1]),
+                (Bn128Field::from(5), vec![0, 0]),
+            ],
+        );
+        assert_eq!(p.to_string(), "3 * i0^2 * i1 + 5");
+
+        let solver = Solver::Polynomial(p.clone());
+        assert!(solver.validate().is_ok());
+        assert_eq!(solver.get_signature(), (2, 1));
+
+        let malformed =
+            Solver::Polynomial(Polynomial::new(2, vec![(Bn128Field::from(1), vec![1])]));
+        assert!(malformed.validate().is_err());
+
+        let too_large = Solver::Polynomial(Polynomial::new(
+            2,
+            vec![(Bn128Field::from(1), vec![MAX_POLYNOMIAL_DEGREE, 1])],
+        ));
+        assert!(too_large.validate().is_err());
+    }
+
+    #[test]
+    fn validate_arity() {
+        let solver: Solver<Bn128Field> = Solver::Div;
+        assert!(solver.validate_arity(2, 1).is_ok());
+
+        let e = solver.validate_arity(2, 2).unwrap_err().index(3);
+        assert_eq!(
+            e.to_string(),
+            "Invalid solver #3: expected 2 input(s) and 1 output(s), found 2 and 2"
+        );
+    }
+}
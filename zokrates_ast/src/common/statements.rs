@@ -11,7 +11,12 @@ use std::fmt;
 #[derivative(PartialOrd, PartialEq, Eq, Hash, Ord)]
 #[derive(Clone, Debug)]
 pub struct DefinitionStatement<A, E> {
-    #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore",
+        Hash = "ignore"
+    )]
     pub span: Option<Span>,
     pub assignee: A,
     pub rhs: E,
@@ -47,7 +52,12 @@ impl<A: fmt::Display, E: fmt::Display> fmt::Display for DefinitionStatement<A, E
 #[derivative(PartialOrd, PartialEq, Eq, Hash, Ord)]
 #[derive(Clone, Debug)]
 pub struct AssertionStatement<B, E> {
-    #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore",
+        Hash = "ignore"
+    )]
     pub span: Option<Span>,
     pub expression: B,
     pub error: E,
@@ -77,7 +87,12 @@ impl<B, E> WithSpan for AssertionStatement<B, E> {
 #[derivative(PartialOrd, PartialEq, Eq, Hash, Ord)]
 #[derive(Clone, Debug)]
 pub struct ReturnStatement<E> {
-    #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore",
+        Hash = "ignore"
+    )]
     pub span: Option<Span>,
     pub inner: E,
 }
@@ -111,7 +126,12 @@ impl<E: fmt::Display> fmt::Display for ReturnStatement<E> {
 #[derivative(PartialOrd, PartialEq, Eq, Hash, Ord)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LogStatement<E> {
-    #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore",
+        Hash = "ignore"
+    )]
     pub span: Option<Span>,
     pub format_string: FormatString,
     pub expressions: Vec<E>,
@@ -153,9 +173,14 @@ impl<E: fmt::Display> fmt::Display for LogStatement<E> {
 }
 
 #[derive(Derivative, Clone, Debug, Serialize, Deserialize)]
-#[derivative(Hash, PartialEq, Eq)]
+#[derivative(Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DirectiveStatement<I, O, S> {
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
+    #[derivative(
+        Hash = "ignore",
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
     pub span: Option<Span>,
     pub inputs: Vec<I>,
     pub outputs: Vec<O>,
@@ -213,7 +238,12 @@ impl<I: fmt::Display, O: fmt::Display, S: fmt::Display> fmt::Display
 #[derivative(PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AssemblyAssignment<A, E> {
-    #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore",
+        Hash = "ignore"
+    )]
     pub span: Option<Span>,
     pub assignee: A,
     pub expression: E,
@@ -243,7 +273,12 @@ impl<A, E> WithSpan for AssemblyAssignment<A, E> {
 #[derivative(PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AssemblyConstraint<E> {
-    #[derivative(PartialEq = "ignore", PartialOrd = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore",
+        Hash = "ignore"
+    )]
     pub span: Option<Span>,
     pub left: E,
     pub right: E,
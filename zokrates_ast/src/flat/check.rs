@@ -0,0 +1,97 @@
+use super::{FlatExpression, FlatProg, FlatStatement};
+use crate::common::{Span, WithSpan};
+use zokrates_field::Field;
+
+/// check that every statement of `prog` can be expressed as a rank-1 constraint `A * B == C` with `A`, `B` and `C` linear, returning the spans of the offending statements otherwise
+///
+/// The statements of the ir are rank-1 constraints by construction, so this check runs on the flat program they are converted from.
+/// A statement is a violation if it contains a product of two non-constant expressions anywhere but at the top of its quadratic side, so that:
+/// - the quadratic side of an assertion, the right-hand side of a definition and the inputs of a directive must be linear, or a single product of two linear expressions
+/// - the linear side of an assertion and the expressions of a log must be linear
+///
+/// Statements without a span are not reported.
+pub fn check_r1cs<T: Field>(prog: &FlatProg<T>) -> Result<(), Vec<Span>> {
+    let mut violations = vec![];
+    for s in &prog.statements {
+        check_statement(s, &mut violations);
+    }
+
+    match violations.is_empty() {
+        true => Ok(()),
+        false => Err(violations),
+    }
+}
+
+fn check_statement<T: Field>(s: &FlatStatement<T>, violations: &mut Vec<Span>) {
+    let valid = match s {
+        FlatStatement::Condition(s) => is_quadratic(&s.quad) && s.lin.is_linear(),
+        FlatStatement::Definition(s) => is_quadratic(&s.rhs),
+        FlatStatement::Directive(d) => d.inputs.iter().all(is_quadratic),
+        FlatStatement::Log(l) => l
+            .expressions
+            .iter()
+            .all(|(_, e)| e.iter().all(FlatExpression::is_linear)),
+        FlatStatement::Block(b) => {
+            for s in &b.inner {
+                check_statement(s, violations);
+            }
+            true
+        }
+    };
+
+    if !valid {
+        violations.extend(s.get_span());
+    }
+}
+
+/// whether `e` is linear, or the product of two linear expressions
+fn is_quadratic<T: Field>(e: &FlatExpression<T>) -> bool {
+    match e {
+        FlatExpression::Mult(e) => e.left.is_linear() && e.right.is_linear(),
+        e => e.is_linear(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{RuntimeError, SourceSpan};
+    use crate::flat::{Parameter, Variable};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn r1cs() {
+        let span = Span::Source(SourceSpan::mock());
+
+        let x = || FlatExpression::<Bn128Field>::identifier(Variable::new(0));
+
+        // def main(private _0) {
+        //     _1 = _0 * _0
+        //     _0 * _0 == _0 * _0
+        //     _2 = (_0 * _0) * _0
+        // }
+        let p: FlatProg<Bn128Field> = FlatProg {
+            module_map: Default::default(),
+            arguments: vec![Parameter::private(Variable::new(0))],
+            statements: vec![
+                FlatStatement::definition(Variable::new(1), x() * x()).span(Some(span)),
+                FlatStatement::condition(x() * x(), x() * x(), RuntimeError::Inverse)
+                    .span(Some(span)),
+                FlatStatement::block(vec![FlatStatement::definition(
+                    Variable::new(2),
+                    (x() * x()) * x(),
+                )
+                .span(Some(span))]),
+            ],
+            return_count: 0,
+        };
+
+        assert_eq!(check_r1cs(&p), Err(vec![span, span]));
+
+        let p = FlatProg {
+            statements: p.statements[..1].to_vec(),
+            ..p
+        };
+        assert_eq!(check_r1cs(&p), Ok(()));
+    }
+}
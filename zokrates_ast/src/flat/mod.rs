@@ -5,9 +5,13 @@
 //! @author Jacob Eberhardt <jacob.eberhardt@tu-berlin.de>
 //! @date 2017
 
+mod check;
 pub mod folder;
+mod normalize;
 pub mod utils;
 
+pub use self::check::check_r1cs;
+pub use self::normalize::NormalizeSubtraction;
 use crate::common;
 pub use crate::common::flat::Parameter;
 pub use crate::common::flat::Variable;
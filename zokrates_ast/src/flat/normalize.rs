@@ -0,0 +1,125 @@
+use super::folder::{fold_expression, Folder};
+use super::{FlatExpression, FlatProg};
+use crate::common::WithSpan;
+use zokrates_field::Field;
+
+/// Lowers every subtraction `a - b` to `a + (-1) * b`, so that expressions only carry signed coefficients
+/// The negation is pushed down to the constants and variables of `b`, so that linear expressions stay linear and products keep two linear factors.
+#[derive(Default)]
+pub struct NormalizeSubtraction;
+
+impl NormalizeSubtraction {
+    pub fn normalize<T: Field>(p: FlatProg<T>) -> FlatProg<T> {
+        NormalizeSubtraction.fold_program(p)
+    }
+
+    /// return an expression without subtractions for `-e`
+    fn negate<T: Field>(&mut self, e: FlatExpression<T>) -> FlatExpression<T> {
+        let span = e.get_span();
+
+        match e {
+            FlatExpression::Value(n) => FlatExpression::value(T::from(-1) * n.value),
+            e @ FlatExpression::Identifier(_) => FlatExpression::value(T::from(-1)) * e,
+            FlatExpression::Add(e) => self.negate(*e.left) + self.negate(*e.right),
+            FlatExpression::Sub(e) => self.negate(*e.left) + self.fold_expression(*e.right),
+            // negate the constant factor if there is one, so that `x * c` stays linear
+            FlatExpression::Mult(e) => match *e.right {
+                right @ FlatExpression::Value(_) => {
+                    self.fold_expression(*e.left) * self.negate(right)
+                }
+                right => self.negate(*e.left) * self.fold_expression(right),
+            },
+        }
+        .span(span)
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for NormalizeSubtraction {
+    fn fold_expression(&mut self, e: FlatExpression<T>) -> FlatExpression<T> {
+        match e {
+            FlatExpression::Sub(e) => {
+                (self.fold_expression(*e.left) + self.negate(*e.right)).span(e.span)
+            }
+            e => fold_expression(self, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::RuntimeError;
+    use crate::flat::{check_r1cs, FlatStatement, Parameter, Variable};
+    use crate::ir::{from_flat::from_flat, Statement};
+    use zokrates_field::Bn128Field;
+
+    fn has_subtraction<T>(e: &FlatExpression<T>) -> bool {
+        match e {
+            FlatExpression::Sub(_) => true,
+            FlatExpression::Add(e) => has_subtraction(&e.left) || has_subtraction(&e.right),
+            FlatExpression::Mult(e) => has_subtraction(&e.left) || has_subtraction(&e.right),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn normalize() {
+        let x = || FlatExpression::<Bn128Field>::identifier(Variable::new(0));
+        let y = || FlatExpression::<Bn128Field>::identifier(Variable::new(1));
+        let c = |v: u32| FlatExpression::<Bn128Field>::value(Bn128Field::from(v));
+
+        // def main(private _0, private _1) -> (~out_0) {
+        //     _2 = (_0 - (_1 - 3)) * (2 - _0 * 5)
+        //     _0 - _1 == _1 - (_0 - _1 * 4)
+        //     ~out_0 = _2 - 1
+        // }
+        let p = FlatProg {
+            module_map: Default::default(),
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            statements: vec![
+                FlatStatement::definition(
+                    Variable::new(2),
+                    (x() - (y() - c(3))) * (c(2) - x() * c(5)),
+                ),
+                FlatStatement::condition(
+                    x() - y(),
+                    y() - (x() - y() * c(4)),
+                    RuntimeError::Inverse,
+                ),
+                FlatStatement::definition(
+                    Variable::public(0),
+                    FlatExpression::identifier(Variable::new(2)) - c(1),
+                ),
+            ],
+            return_count: 1,
+        };
+
+        let normalized = NormalizeSubtraction::normalize(p.clone());
+
+        assert!(normalized.statements.iter().all(|s| match s {
+            FlatStatement::Definition(d) => !has_subtraction(&d.rhs),
+            FlatStatement::Condition(c) => !has_subtraction(&c.quad) && !has_subtraction(&c.lin),
+            _ => unreachable!(),
+        }));
+        assert!(check_r1cs(&normalized).is_ok());
+
+        // the constraints are the same once their linear combinations are reduced
+        let constraints = |p: FlatProg<'static, Bn128Field>| -> Vec<_> {
+            from_flat(p)
+                .statements
+                .into_iter()
+                .map(|s| match s {
+                    Statement::Constraint(s) => {
+                        (s.quad.left.reduce(), s.quad.right.reduce(), s.lin.reduce())
+                    }
+                    _ => unreachable!(),
+                })
+                .collect()
+        };
+
+        assert_eq!(constraints(normalized), constraints(p));
+    }
+}
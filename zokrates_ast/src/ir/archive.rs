@@ -0,0 +1,283 @@
+use super::serialize::check_header;
+use super::{ProgEnum, ProgHeader, ProgIterator, Statement};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Seek, SeekFrom, Write};
+use zokrates_field::Field;
+
+/// The magic number of archives, `ZKA`, distinct from the magic number of programs
+const ARCHIVE_MAGIC: &[u8; 4] = &[0x5a, 0x4b, 0x41, 0];
+/// The version of the archives written by this library
+const ARCHIVE_VERSION: &[u8; 4] = &[1, 0, 0, 0];
+
+/// A program in an archive, see `Archive`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    /// the offset of the program from the start of the archive
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// A collection of named programs in a single file
+///
+/// An archive starts with its magic number and version, followed by a directory giving the name, offset and length of each program.
+/// The programs follow the directory, each serialized as a standalone program, so that any of them can be read without scanning the others.
+pub struct Archive<R> {
+    r: R,
+    entries: Vec<ArchiveEntry>,
+}
+
+impl Archive<()> {
+    /// write an archive of `programs` to `w`, given the name and the serialized bytes of each program
+    /// Fails if a name is used twice, or if some bytes are not a serialized program
+    pub fn write<W: Write>(mut w: W, programs: &[(&str, &[u8])]) -> Result<(), String> {
+        for (index, (name, bytes)) in programs.iter().enumerate() {
+            if programs[..index].iter().any(|(n, _)| n == name) {
+                return Err(format!("Program `{}` appears twice in the archive", name));
+            }
+
+            ProgHeader::peek_version(*bytes)
+                .map_err(|_| format!("`{}` is not a serialized program", name))?;
+        }
+
+        let directory_length: u64 = 12
+            + programs
+                .iter()
+                .map(|(name, _)| 20 + name.len() as u64)
+                .sum::<u64>();
+
+        w.write_all(ARCHIVE_MAGIC)
+            .and(w.write_all(ARCHIVE_VERSION))
+            .and(w.write_u32::<LittleEndian>(programs.len() as u32))
+            .map_err(|e| e.to_string())?;
+
+        let mut offset = directory_length;
+        for (name, bytes) in programs {
+            w.write_u32::<LittleEndian>(name.len() as u32)
+                .and(w.write_all(name.as_bytes()))
+                .and(w.write_u64::<LittleEndian>(offset))
+                .and(w.write_u64::<LittleEndian>(bytes.len() as u64))
+                .map_err(|e| e.to_string())?;
+            offset += bytes.len() as u64;
+        }
+
+        for (_, bytes) in programs {
+            w.write_all(bytes).map_err(|e| e.to_string())?;
+        }
+
+        w.flush().map_err(|e| e.to_string())
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// read the directory of the archive in `r`, checking that each program lies within `r`
+    pub fn read(mut r: R) -> Result<Self, String> {
+        let invalid = |_| String::from("Invalid archive directory");
+
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic).map_err(invalid)?;
+        if &magic != ARCHIVE_MAGIC {
+            return Err("Invalid archive magic number".to_string());
+        }
+
+        let mut version = [0; 4];
+        r.read_exact(&mut version).map_err(invalid)?;
+        if &version != ARCHIVE_VERSION {
+            return Err(format!("Unsupported archive version {}", version[0]));
+        }
+
+        let count = r.read_u32::<LittleEndian>().map_err(invalid)?;
+
+        let entries = (0..count)
+            .map(|_| {
+                let name_length = r.read_u32::<LittleEndian>()?;
+                let mut name = vec![];
+                r.by_ref().take(name_length as u64).read_to_end(&mut name)?;
+                if name.len() != name_length as usize {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+                Ok(ArchiveEntry {
+                    name: String::from_utf8(name)
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+                    offset: r.read_u64::<LittleEndian>()?,
+                    length: r.read_u64::<LittleEndian>()?,
+                })
+            })
+            .collect::<std::io::Result<Vec<_>>>()
+            .map_err(invalid)?;
+
+        let length = r.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+
+        for entry in &entries {
+            match entry.offset.checked_add(entry.length) {
+                Some(end) if end <= length => {}
+                _ => {
+                    return Err(format!(
+                        "Program `{}` out of bounds, the archive may be corrupted",
+                        entry.name
+                    ))
+                }
+            }
+        }
+
+        Ok(Self { r, entries })
+    }
+
+    /// the programs in this archive, in the order they were written
+    pub fn list(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    /// read the header of the program called `name`
+    pub fn header(&mut self, name: &str) -> Result<ProgHeader, String> {
+        ProgHeader::read(self.open(name)?).map_err(|_| String::from("Invalid header"))
+    }
+
+    /// read the program over `T` called `name`, checking its header and the integrity of its sections like `ProgEnum::deserialize`
+    pub fn get<T: Field>(
+        &mut self,
+        name: &str,
+    ) -> Result<ProgIterator<'_, T, impl IntoIterator<Item = Statement<'_, T>> + '_>, String> {
+        let mut r = self.open(name)?;
+
+        let header = ProgHeader::read(&mut r).map_err(|_| String::from("Invalid header"))?;
+
+        check_header(&header)?;
+
+        header.verify(&mut r)?;
+
+        ProgEnum::try_read::<T>(r, &header)
+    }
+
+    fn open(&mut self, name: &str) -> Result<Window<&mut R>, String> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| format!("Program `{}` not found in the archive", name))?;
+
+        Window::new(&mut self.r, entry.offset, entry.length).map_err(|e| e.to_string())
+    }
+}
+
+/// A view of the `length` bytes of `inner` starting at `offset`, which behaves as a stream of its own
+struct Window<R> {
+    inner: R,
+    offset: u64,
+    length: u64,
+    position: u64,
+}
+
+impl<R: Seek> Window<R> {
+    fn new(mut inner: R, offset: u64, length: u64) -> std::io::Result<Self> {
+        inner.seek(SeekFrom::Start(offset))?;
+        Ok(Self {
+            inner,
+            offset,
+            length,
+            position: 0,
+        })
+    }
+}
+
+impl<R: Read> Read for Window<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.length.saturating_sub(self.position);
+        let max = (buf.len() as u64).min(remaining) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Seek> Seek for Window<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.length.checked_add_signed(n),
+            SeekFrom::Current(n) => self.position.checked_add_signed(n),
+        }
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        self.inner.seek(SeekFrom::Start(self.offset + position))?;
+        self.position = position;
+        Ok(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, Parameter, Prog, QuadComb, Variable};
+    use std::io::Cursor;
+    use zokrates_field::Bn128Field;
+
+    fn prog(return_count: usize) -> Prog<'static, Bn128Field> {
+        Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count,
+            statements: (0..return_count)
+                .map(|i| {
+                    Statement::constraint(
+                        QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                        LinComb::from(Variable::public(i)),
+                        None,
+                    )
+                })
+                .collect(),
+            ..Prog::default()
+        }
+    }
+
+    fn serialize(p: Prog<Bn128Field>) -> Vec<u8> {
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn archive() {
+        let a = serialize(prog(1));
+        let b = serialize(prog(2));
+
+        let mut buffer = vec![];
+        Archive::write(&mut buffer, &[("a", &a[..]), ("b", &b[..])]).unwrap();
+
+        let mut archive = Archive::read(Cursor::new(buffer)).unwrap();
+        assert_eq!(
+            archive
+                .list()
+                .iter()
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+
+        assert_eq!(archive.header("b").unwrap().constraint_count, 2);
+        assert_eq!(archive.get::<Bn128Field>("b").unwrap().collect(), prog(2));
+        assert_eq!(archive.get::<Bn128Field>("a").unwrap().collect(), prog(1));
+
+        assert!(archive.get::<Bn128Field>("c").is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        let a = serialize(prog(1));
+
+        assert!(Archive::write(vec![], &[("a", &a[..]), ("a", &a[..])]).is_err());
+        assert!(Archive::write(vec![], &[("a", &[0u8; 8][..])]).is_err());
+
+        // a program is not an archive
+        assert!(Archive::read(Cursor::new(a.clone())).is_err());
+
+        let mut buffer = vec![];
+        Archive::write(&mut buffer, &[("a", &a[..])]).unwrap();
+        buffer.truncate(buffer.len() - 1);
+        assert!(Archive::read(Cursor::new(buffer)).is_err());
+    }
+}
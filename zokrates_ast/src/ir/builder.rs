@@ -0,0 +1,151 @@
+use crate::common::ModuleMap;
+use crate::ir::check::{detect_unconstrained, UnconstrainedVariable};
+use crate::ir::{LinComb, Parameter, Prog, QuadComb, RuntimeError, Solver, Statement, Variable};
+use zokrates_field::Field;
+
+/// A builder for programs generated outside of the ZoKrates compiler
+///
+/// The builder enforces that:
+/// - parameters are declared at most once
+/// - directives have as many inputs and outputs as their solver expects
+/// - no private parameter or directive output is left unconstrained
+///
+/// Checks which require indexing the solvers, such as the validation of `Solver::Ref` signatures, are deferred to serialization.
+pub struct ProgBuilder<'ast, T> {
+    arguments: Vec<Parameter>,
+    statements: Vec<Statement<'ast, T>>,
+    return_count: usize,
+    module_map: ModuleMap,
+}
+
+impl<'ast, T> Default for ProgBuilder<'ast, T> {
+    fn default() -> Self {
+        Self {
+            arguments: vec![],
+            statements: vec![],
+            return_count: 0,
+            module_map: ModuleMap::default(),
+        }
+    }
+}
+
+impl<'ast, T: Field> ProgBuilder<'ast, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// declare a parameter of the program
+    /// Panics if a parameter with the same variable was already declared
+    pub fn add_parameter(&mut self, p: Parameter) -> &mut Self {
+        assert!(
+            self.arguments.iter().all(|a| a.id != p.id),
+            "parameter {} is declared twice",
+            p.id
+        );
+        self.arguments.push(p);
+        self
+    }
+
+    /// add the constraint `quad == lin`, failing with `error` if it is not satisfied
+    pub fn add_constraint<U: Into<QuadComb<T>>, V: Into<LinComb<T>>>(
+        &mut self,
+        quad: U,
+        lin: V,
+        error: Option<RuntimeError>,
+    ) -> &mut Self {
+        self.statements
+            .push(Statement::constraint(quad, lin, error));
+        self
+    }
+
+    /// add a directive assigning the result of `solver` on `inputs` to `outputs`
+    /// Panics if the arity of `solver` does not match `inputs` and `outputs`
+    pub fn add_directive(
+        &mut self,
+        outputs: Vec<Variable>,
+        solver: Solver<'ast, T>,
+        inputs: Vec<QuadComb<T>>,
+    ) -> &mut Self {
+        self.statements
+            .push(Statement::directive(outputs, solver, inputs));
+        self
+    }
+
+    pub fn set_return_count(&mut self, return_count: usize) -> &mut Self {
+        self.return_count = return_count;
+        self
+    }
+
+    pub fn set_module_map(&mut self, module_map: ModuleMap) -> &mut Self {
+        self.module_map = module_map;
+        self
+    }
+
+    /// build the program, checking that all variables are constrained
+    pub fn build(self) -> Result<Prog<'ast, T>, String> {
+        let prog = Prog::new(
+            self.arguments,
+            self.statements,
+            self.return_count,
+            self.module_map,
+            vec![],
+        );
+
+        match detect_unconstrained(&prog) {
+            Ok(()) => Ok(prog),
+            Err(variables) => Err(UnconstrainedVariable::report(&variables, &prog.module_map)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn build() {
+        // def main(private _0) -> (~out_0) {
+        //     # _1 = Bits(1)(_0)
+        //     _1 * _1 == _1
+        //     _0 == ~out_0
+        // }
+        let mut builder = ProgBuilder::<Bn128Field>::new();
+        builder
+            .add_parameter(Parameter::private(Variable::new(0)))
+            .add_directive(
+                vec![Variable::new(1)],
+                Solver::Bits(1),
+                vec![Variable::new(0).into()],
+            )
+            .add_constraint(
+                QuadComb::new(Variable::new(1).into(), Variable::new(1).into()),
+                Variable::new(1),
+                None,
+            )
+            .add_constraint(Variable::new(0), Variable::public(0), None)
+            .set_return_count(1);
+
+        let prog = builder.build().unwrap();
+
+        assert_eq!(prog.arguments, vec![Parameter::private(Variable::new(0))]);
+        assert_eq!(prog.statements.len(), 3);
+        assert_eq!(prog.return_count, 1);
+    }
+
+    #[test]
+    fn unconstrained() {
+        let mut builder = ProgBuilder::<Bn128Field>::new();
+        builder.add_parameter(Parameter::private(Variable::new(0)));
+
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn duplicate_parameter() {
+        ProgBuilder::<Bn128Field>::new()
+            .add_parameter(Parameter::private(Variable::new(0)))
+            .add_parameter(Parameter::public(Variable::new(0)));
+    }
+}
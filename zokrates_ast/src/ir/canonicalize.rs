@@ -0,0 +1,85 @@
+use super::folder::{fold_linear_combination, Folder};
+use super::{LinComb, ProgIterator, Statement};
+use zokrates_field::Field;
+
+/// Sorts the terms of linear combinations by variable, merging duplicate terms and dropping zero terms
+/// The constant term, on `~one`, is sorted like any other variable, after the outputs and before intermediate variables.
+/// Equivalent programs then serialize to the same bytes, regardless of the order in which terms were introduced.
+#[derive(Default)]
+pub struct Canonicalizer;
+
+impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
+    pub fn canonicalize(
+        self,
+    ) -> ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>> {
+        ProgIterator {
+            module_map: self.module_map,
+            arguments: self.arguments,
+            return_count: self.return_count,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
+            statements: self
+                .statements
+                .into_iter()
+                .flat_map(|s| Canonicalizer.fold_statement(s)),
+            solvers: self.solvers,
+        }
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for Canonicalizer {
+    fn fold_linear_combination(&mut self, e: LinComb<T>) -> LinComb<T> {
+        fold_linear_combination(self, e).reduce()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Parameter, Prog, QuadComb, Variable};
+    use std::io::Cursor;
+    use zokrates_field::Bn128Field;
+
+    fn serialize(p: Prog<Bn128Field>) -> Vec<u8> {
+        let mut buffer = Cursor::new(vec![]);
+        p.canonicalize().collect().serialize(&mut buffer).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn identical_bytes() {
+        let prog = |lin: LinComb<Bn128Field>| Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(1).into()),
+                lin,
+                None,
+            )],
+            ..Prog::default()
+        };
+
+        // ~out_0 + 2 + _0
+        let a = LinComb::new(vec![
+            (Variable::public(0), Bn128Field::from(1)),
+            (Variable::one(), Bn128Field::from(2)),
+            (Variable::new(0), Bn128Field::from(1)),
+        ]);
+
+        // _0 + 3 * _1 + 2 + ~out_0 - 3 * _1 + 0 * _1
+        let b = LinComb::new(vec![
+            (Variable::new(0), Bn128Field::from(1)),
+            (Variable::new(1), Bn128Field::from(3)),
+            (Variable::one(), Bn128Field::from(2)),
+            (Variable::public(0), Bn128Field::from(1)),
+            (Variable::new(1), Bn128Field::from(-3)),
+            (Variable::new(1), Bn128Field::from(0)),
+        ]);
+
+        assert_ne!(prog(a.clone()), prog(b.clone()));
+        assert_eq!(serialize(prog(a)), serialize(prog(b)));
+    }
+}
@@ -0,0 +1,260 @@
+use super::folder::{fold_directive_statement, fold_statement_cases, Folder};
+use super::solver_indexer::remap_solver;
+use super::variables::collect_variables;
+use super::{DirectiveStatement, ProgIterator, ReturnSlot, Statement, Variable};
+use crate::common::{ModuleIdHash, SourceSpan, Span, WithSpan};
+use std::collections::{HashMap, HashSet};
+use zokrates_field::Field;
+
+/// Rewrites the statements of a program appended to another one, so that they refer to the merged solvers and module map and to their own variables
+struct Relinker {
+    solver_offset: usize,
+    modules: HashMap<ModuleIdHash, ModuleIdHash>,
+    /// the arguments of the appended program, which keep their ids
+    arguments: HashSet<Variable>,
+    /// the id the internal variables of the appended program are shifted by
+    variable_offset: usize,
+    /// the index the outputs of the appended program are shifted by
+    output_offset: usize,
+}
+
+impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
+    /// run the statements of `other` after the statements of this program
+    ///
+    /// Both programs are over the same field, so they cannot disagree on the curve.
+    /// The arguments of `other` which are not arguments of this program are appended, and an argument of both programs must have the same visibility.
+    /// Arguments are matched by id and keep it, while the other internal variables of `other` are renumbered after the variables of this program, so that the programs do not share intermediate variables.
+    /// This requires reading the statements of this program first, which are therefore collected, while the statements of `other` are streamed.
+    /// The outputs of `other` follow the outputs of this program, and so do its return slots if both programs have them.
+    /// The solvers of `other` are appended, and its module map is merged into the module map of this program.
+    /// The chained program uses the embeds of both programs.
+    pub fn chain<J: IntoIterator<Item = Statement<'ast, T>>>(
+        mut self,
+        other: ProgIterator<'ast, T, J>,
+    ) -> Result<ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>>, String> {
+        let arguments = other.arguments.iter().map(|a| a.id).collect();
+
+        for argument in other.arguments {
+            match self.arguments.iter().find(|a| a.id == argument.id) {
+                Some(a) if a.visibility != argument.visibility => {
+                    return Err(format!(
+                        "Argument `{}` has visibility {:?} in one program and {:?} in the other",
+                        argument.id, a.visibility, argument.visibility
+                    ))
+                }
+                Some(_) => {}
+                None => self.arguments.push(argument),
+            }
+        }
+
+        let statements: Vec<_> = self.statements.into_iter().collect();
+
+        // the internal variables of `other` come after all the variables of this program and the arguments of both
+        let variable_offset = statements
+            .iter()
+            .flat_map(collect_variables)
+            .chain(self.arguments.iter().map(|a| a.id))
+            .filter(|v| v.id > 0)
+            .map(|v| v.id() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut relinker = Relinker {
+            solver_offset: self.solvers.len(),
+            modules: self.module_map.merge(&other.module_map),
+            arguments,
+            variable_offset,
+            output_offset: self.return_count,
+        };
+
+        self.solvers.extend(other.solvers);
+        self.embeds_used.extend(other.embeds_used);
+
+        // the return slots of a program with outputs but no slots are unknown, and so are those of the chained program
+        let has_returns = |returns: &[ReturnSlot], count: usize| count == 0 || !returns.is_empty();
+        let returns = match has_returns(&self.returns, self.return_count)
+            && has_returns(&other.returns, other.return_count)
+        {
+            true => self
+                .returns
+                .into_iter()
+                .chain(other.returns.into_iter().map(|slot| ReturnSlot {
+                    span: relinker.relink_span(slot.span),
+                    ..slot
+                }))
+                .collect(),
+            false => vec![],
+        };
+
+        Ok(ProgIterator {
+            module_map: self.module_map,
+            arguments: self.arguments,
+            return_count: self.return_count + other.return_count,
+            returns,
+            embeds_used: self.embeds_used,
+            statements: statements.into_iter().chain(
+                other
+                    .statements
+                    .into_iter()
+                    .flat_map(move |s| relinker.fold_statement(s)),
+            ),
+            solvers: self.solvers,
+        })
+    }
+}
+
+impl Relinker {
+    fn relink_span(&self, span: Option<Span>) -> Option<Span> {
+        span.map(|span| match span {
+            Span::Source(s) => Span::Source(SourceSpan {
+                module: self.modules.get(&s.module).copied().unwrap_or(s.module),
+                ..s
+            }),
+            span => span,
+        })
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for Relinker {
+    fn fold_variable(&mut self, v: Variable) -> Variable {
+        match v.id {
+            0 => v,
+            id if id < 0 => Variable::public(self.output_offset + (-id - 1) as usize),
+            _ if self.arguments.contains(&v) => v,
+            _ => Variable::new(self.variable_offset + v.id()),
+        }
+    }
+
+    fn fold_statement(&mut self, s: Statement<'ast, T>) -> Vec<Statement<'ast, T>> {
+        let span = self.relink_span(s.get_span());
+        fold_statement_cases(self, s)
+            .into_iter()
+            .map(|s| s.span(span))
+            .collect()
+    }
+
+    fn fold_directive_statement(
+        &mut self,
+        d: DirectiveStatement<'ast, T>,
+    ) -> Vec<Statement<'ast, T>> {
+        let d = DirectiveStatement {
+            solver: remap_solver(d.solver, self.solver_offset),
+            ..d
+        };
+
+        fold_directive_statement(self, d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{ModuleMap, RefCall};
+    use crate::ir::{Parameter, Prog, QuadComb, Solver, Witness};
+    use zokrates_field::Bn128Field;
+
+    fn cube(input: Variable, solver: usize) -> Prog<'static, Bn128Field> {
+        // def main(private input) -> (~out_0) {
+        //     # _10 = Ref@solver(input * input)
+        //     input * input == _10
+        //     _10 * input == ~out_0
+        // }
+        Prog {
+            arguments: vec![Parameter::private(input)],
+            return_count: 1,
+            statements: vec![
+                Statement::directive(
+                    vec![Variable::new(10)],
+                    Solver::Ref(RefCall {
+                        index: solver,
+                        signature: (1, 1),
+                    }),
+                    vec![QuadComb::new(input.into(), input.into())],
+                ),
+                Statement::constraint(
+                    QuadComb::new(input.into(), input.into()),
+                    Variable::new(10),
+                    None,
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(10).into(), input.into()),
+                    Variable::public(0),
+                    None,
+                ),
+            ],
+            solvers: vec![Solver::Bits(1)],
+            ..Prog::default()
+        }
+    }
+
+    #[test]
+    fn chain() {
+        let chained = cube(Variable::new(0), 0)
+            .chain(cube(Variable::new(1), 0))
+            .unwrap()
+            .collect();
+
+        assert_eq!(
+            chained.arguments,
+            vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1))
+            ]
+        );
+        assert_eq!(chained.return_count, 2);
+        assert_eq!(chained.solvers.len(), 2);
+        assert_eq!(chained.statements.len(), 6);
+        // the intermediate variable of the second program is renumbered after the variables of the first one
+        assert!(matches!(
+            &chained.statements[3],
+            Statement::Directive(d) if d.solver == Solver::Ref(RefCall { index: 1, signature: (1, 1) })
+                && d.outputs == vec![Variable::new(21)]
+        ));
+
+        // the programs do not share intermediate variables nor outputs, so the chained program is satisfiable
+        let witness = Witness(
+            [
+                (Variable::new(0), 2),
+                (Variable::new(10), 4),
+                (Variable::public(0), 8),
+                (Variable::new(1), 3),
+                (Variable::new(21), 9),
+                (Variable::public(1), 27),
+            ]
+            .into_iter()
+            .map(|(v, value)| (v, Bn128Field::from(value)))
+            .collect(),
+        );
+        assert_eq!(chained.check_witness(&witness), Ok(()));
+    }
+
+    #[test]
+    fn conflicting_arguments() {
+        let mut other = cube(Variable::new(0), 0);
+        other.arguments = vec![Parameter::public(Variable::new(0))];
+
+        assert!(cube(Variable::new(0), 0).chain(other).is_err());
+    }
+
+    #[test]
+    fn remap_modules() {
+        let mut p = cube(Variable::new(0), 0);
+        p.module_map = ModuleMap::new(vec!["main.zok".into()]);
+
+        let mut other = cube(Variable::new(0), 0);
+        other.module_map = ModuleMap::new(vec!["main.zok".into()]);
+        let span = SourceSpan {
+            module: other.module_map.insert("other.zok".into()).unwrap(),
+            ..SourceSpan::mock()
+        };
+        other.statements[1] = other.statements[1].clone().span(Some(span.into()));
+
+        let chained = p.chain(other).unwrap().collect();
+
+        assert_eq!(
+            chained.module_map,
+            ModuleMap::new(vec!["main.zok".into(), "other.zok".into()])
+        );
+        assert_eq!(chained.statements[4].get_span(), Some(span.into()));
+    }
+}
@@ -1,15 +1,46 @@
+use crate::common::{ModuleMap, Span};
 use crate::ir::folder::Folder;
 use crate::ir::DirectiveStatement;
+use crate::ir::LinComb;
 use crate::ir::Parameter;
+use crate::ir::Prog;
 use crate::ir::ProgIterator;
 use crate::ir::Statement;
 use crate::ir::Variable;
-use std::collections::HashSet;
+use crate::ir::Witness;
+use std::collections::HashMap;
 use zokrates_field::Field;
 
+/// A variable which is never constrained, along with what is known about where it was introduced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnconstrainedVariable {
+    pub variable: Variable,
+    /// the source-level name of the variable, if it is a named parameter
+    pub name: Option<String>,
+    /// the span of the parameter or directive which introduced the variable
+    pub span: Option<Span>,
+}
+
+impl UnconstrainedVariable {
+    /// a human-readable report listing `variables`, resolving their spans through `module_map`
+    pub fn report(variables: &[UnconstrainedVariable], module_map: &ModuleMap) -> String {
+        let mut res = format!("Error: Found {} unconstrained variable(s)", variables.len());
+        for v in variables {
+            res.push_str(&format!("\n\t{}", v.variable));
+            if let Some(name) = &v.name {
+                res.push_str(&format!(" (`{}`)", name));
+            }
+            if let Some(span) = &v.span {
+                res.push_str(&format!(" introduced at {}", span.display(module_map)));
+            }
+        }
+        res
+    }
+}
+
 #[derive(Debug)]
 pub struct UnconstrainedVariableDetector {
-    pub(self) variables: HashSet<Variable>,
+    pub(self) variables: HashMap<Variable, (Option<String>, Option<Span>)>,
 }
 
 impl UnconstrainedVariableDetector {
@@ -20,20 +51,148 @@ impl UnconstrainedVariableDetector {
             variables: p
                 .arguments
                 .iter()
-                .filter(|p| p.private)
-                .map(|p| p.id)
+                .filter(|p| p.visibility.is_private())
+                .map(|p| (p.id, (p.name.clone(), p.span)))
                 .collect(),
         }
     }
 
-    pub fn finalize(self) -> Result<(), usize> {
+    /// return the variables which were left unconstrained, in ascending order
+    pub fn finalize(self) -> Result<(), Vec<UnconstrainedVariable>> {
         if self.variables.is_empty() {
             return Ok(());
         }
-        Err(self.variables.len())
+        let mut variables: Vec<_> = self
+            .variables
+            .into_iter()
+            .map(|(variable, (name, span))| UnconstrainedVariable {
+                variable,
+                name,
+                span,
+            })
+            .collect();
+        variables.sort_by_key(|v| v.variable);
+        Err(variables)
+    }
+}
+
+/// check that all private parameters and directive outputs of `prog` are constrained, returning the offending variables otherwise
+pub fn detect_unconstrained<T: Field>(prog: &Prog<T>) -> Result<(), Vec<UnconstrainedVariable>> {
+    let mut detector = UnconstrainedVariableDetector::new(prog);
+    for s in &prog.statements {
+        detector.fold_statement(s.clone());
+    }
+    detector.finalize()
+}
+
+/// find the constraints of `prog` which only involve constants and do not hold, so that no witness can satisfy `prog`
+/// This is meant to run on programs whose constants were folded, and constraints without a span are not reported.
+pub fn find_unsatisfiable<T: Field>(prog: &Prog<T>) -> Vec<Span> {
+    let mut spans = vec![];
+    find_unsatisfiable_statements(&prog.statements, &mut spans);
+    spans
+}
+
+fn find_unsatisfiable_statements<T: Field>(statements: &[Statement<T>], spans: &mut Vec<Span>) {
+    for s in statements {
+        match s {
+            Statement::Constraint(c) => match (
+                c.quad.left.clone().try_constant(),
+                c.quad.right.clone().try_constant(),
+                c.lin.clone().try_constant(),
+            ) {
+                (Ok(left), Ok(right), Ok(lin)) if left * right != lin => spans.extend(c.span),
+                _ => {}
+            },
+            Statement::Block(b) => find_unsatisfiable_statements(&b.inner, spans),
+            _ => {}
+        }
     }
 }
 
+/// A constraint which a witness does not satisfy, see `Prog::check_witness`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConstraintFailure<T> {
+    /// the constraint does not hold, with `left` the value of its quadratic side and `right` the value of its linear side
+    Unsatisfied {
+        /// the position of the constraint among all constraints of the program, including those in blocks
+        index: usize,
+        span: Option<Span>,
+        left: T,
+        right: T,
+    },
+    /// the constraint refers to `variable`, which has no value in the witness
+    MissingVariable {
+        index: usize,
+        span: Option<Span>,
+        variable: Variable,
+    },
+}
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// check that `witness` satisfies every constraint of this program, returning the constraints it violates otherwise
+    /// Directives are not run, so the witness must assign every variable of the constraints, except `~one` which defaults to 1.
+    pub fn check_witness(&self, witness: &Witness<T>) -> Result<(), Vec<ConstraintFailure<T>>> {
+        let mut failures = vec![];
+        let mut index = 0;
+        check_statements(&self.statements, witness, &mut index, &mut failures);
+
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(failures),
+        }
+    }
+}
+
+fn check_statements<T: Field>(
+    statements: &[Statement<T>],
+    witness: &Witness<T>,
+    index: &mut usize,
+    failures: &mut Vec<ConstraintFailure<T>>,
+) {
+    for s in statements {
+        match s {
+            Statement::Constraint(c) => {
+                let values = evaluate(&c.quad.left, witness)
+                    .and_then(|left| Ok(left * evaluate(&c.quad.right, witness)?))
+                    .and_then(|left| Ok((left, evaluate(&c.lin, witness)?)));
+
+                match values {
+                    Ok((left, right)) if left == right => {}
+                    Ok((left, right)) => failures.push(ConstraintFailure::Unsatisfied {
+                        index: *index,
+                        span: c.span,
+                        left,
+                        right,
+                    }),
+                    Err(variable) => failures.push(ConstraintFailure::MissingVariable {
+                        index: *index,
+                        span: c.span,
+                        variable,
+                    }),
+                }
+
+                *index += 1;
+            }
+            Statement::Block(b) => check_statements(&b.inner, witness, index, failures),
+            _ => {}
+        }
+    }
+}
+
+/// evaluate `l` on `witness`, failing with the first variable which has no value
+fn evaluate<T: Field>(l: &LinComb<T>, witness: &Witness<T>) -> Result<T, Variable> {
+    l.value
+        .iter()
+        .try_fold(T::zero(), |acc, (variable, coefficient)| {
+            match witness.0.get(variable) {
+                Some(value) => Ok(acc + *value * coefficient),
+                None if *variable == Variable::one() => Ok(acc + *coefficient),
+                None => Err(*variable),
+            }
+        })
+}
+
 impl<'ast, T: Field> Folder<'ast, T> for UnconstrainedVariableDetector {
     fn fold_argument(&mut self, p: Parameter) -> Parameter {
         p
@@ -46,7 +205,213 @@ impl<'ast, T: Field> Folder<'ast, T> for UnconstrainedVariableDetector {
         &mut self,
         d: DirectiveStatement<'ast, T>,
     ) -> Vec<Statement<'ast, T>> {
-        self.variables.extend(d.outputs.iter());
+        self.variables
+            .extend(d.outputs.iter().map(|o| (*o, (None, d.span))));
         vec![Statement::Directive(d)]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, QuadComb, Solver};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn detect() {
+        // def main(private _0, private _1) {
+        //     # _2 = Bits(1)(_0)
+        //     _0 * _0 == _0
+        // }
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            statements: vec![
+                Statement::directive(
+                    vec![Variable::new(2)],
+                    Solver::Bits(1),
+                    vec![Variable::new(0).into()],
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                    Variable::new(0),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        };
+
+        assert_eq!(
+            detect_unconstrained(&p)
+                .unwrap_err()
+                .into_iter()
+                .map(|v| v.variable)
+                .collect::<Vec<_>>(),
+            vec![Variable::new(1), Variable::new(2)]
+        );
+    }
+
+    #[test]
+    fn report() {
+        use crate::common::{SourceSpan, WithSpan};
+
+        let span = Span::Source(SourceSpan::mock());
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))
+                .with_name(Some(String::from("a")))
+                .span(Some(span))],
+            statements: vec![Statement::directive(
+                vec![Variable::new(1)],
+                Solver::Bits(1),
+                vec![Variable::new(0).into()],
+            )],
+            ..Prog::default()
+        };
+
+        let variables = detect_unconstrained(&p).unwrap_err();
+        assert_eq!(
+            variables,
+            vec![
+                UnconstrainedVariable {
+                    variable: Variable::new(0),
+                    name: Some(String::from("a")),
+                    span: Some(span),
+                },
+                UnconstrainedVariable {
+                    variable: Variable::new(1),
+                    name: None,
+                    span: None,
+                }
+            ]
+        );
+
+        let report = UnconstrainedVariable::report(&variables, &p.module_map);
+        assert!(report
+            .starts_with("Error: Found 2 unconstrained variable(s)\n\t_0 (`a`) introduced at "));
+        assert!(report.ends_with("\n\t_1"));
+    }
+
+    #[test]
+    fn check_witness() {
+        // def main(private _0) -> (~out_0) {
+        //     _0 * _0 == ~out_0
+        //     _0 * 1 == _1
+        // }
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                    Variable::public(0),
+                    None,
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), LinComb::one()),
+                    Variable::new(1),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        };
+
+        let witness = |values: Vec<(Variable, u32)>| {
+            Witness(
+                values
+                    .into_iter()
+                    .map(|(v, value)| (v, Bn128Field::from(value)))
+                    .collect(),
+            )
+        };
+
+        assert_eq!(
+            p.check_witness(&witness(vec![
+                (Variable::new(0), 3),
+                (Variable::public(0), 9),
+                (Variable::new(1), 3)
+            ])),
+            Ok(())
+        );
+
+        assert_eq!(
+            p.check_witness(&witness(vec![
+                (Variable::new(0), 3),
+                (Variable::public(0), 8)
+            ])),
+            Err(vec![
+                ConstraintFailure::Unsatisfied {
+                    index: 0,
+                    span: None,
+                    left: Bn128Field::from(9),
+                    right: Bn128Field::from(8),
+                },
+                ConstraintFailure::MissingVariable {
+                    index: 1,
+                    span: None,
+                    variable: Variable::new(1),
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn unsatisfiable() {
+        use crate::common::{SourceSpan, WithSpan};
+
+        let span = Span::Source(SourceSpan::mock());
+
+        // def main(private _0) {
+        //     _0 * _0 == _0
+        //     1 * 1 == 1
+        //     0 * 1 == 1
+        //     2 * 3 == 5
+        //     {
+        //         1 * 1 == 0
+        //     }
+        // }
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            statements: vec![
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                    Variable::new(0),
+                    None,
+                )
+                .span(Some(span)),
+                Statement::constraint(
+                    QuadComb::new(LinComb::one(), LinComb::one()),
+                    LinComb::one(),
+                    None,
+                )
+                .span(Some(span)),
+                Statement::constraint(
+                    QuadComb::new(LinComb::zero(), LinComb::one()),
+                    LinComb::one(),
+                    None,
+                )
+                .span(Some(span)),
+                Statement::constraint(
+                    QuadComb::new(
+                        LinComb::summand(2, Variable::one()),
+                        LinComb::summand(3, Variable::one()),
+                    ),
+                    LinComb::summand(5, Variable::one()),
+                    None,
+                )
+                .span(Some(span)),
+                Statement::block(vec![Statement::constraint(
+                    QuadComb::new(LinComb::one(), LinComb::one()),
+                    LinComb::zero(),
+                    None,
+                )
+                .span(Some(span))]),
+            ],
+            ..Prog::default()
+        };
+
+        assert_eq!(find_unsatisfiable(&p), vec![span, span, span]);
+    }
+}
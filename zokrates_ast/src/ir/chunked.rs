@@ -0,0 +1,499 @@
+use super::folder::Folder;
+use super::serialize::{
+    deserialize_parameters, ChecksumWriter, VariableCounter, CHUNKED_CONSTRAINTS, FILE_VERSION,
+    INCOMPLETE, ZOKRATES_MAGIC,
+};
+use super::solver_indexer::SolverIndexer;
+use super::visitor::Visitor;
+use super::{ModuleMap, Parameter, ProgHeader, Section, SectionType, Solver, Statement};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use serde::Deserialize;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use zokrates_field::Field;
+
+type DynamicError = Box<dyn std::error::Error>;
+
+/// The length of the fixed-size prefix of a frame
+const FRAME_HEADER_LENGTH: u64 = 4 + 4 + 8 + 8 + 4;
+
+/// A batch of statements in the constraints section of a chunked program
+///
+/// A frame is written as:
+/// - the number of statements and the number of constraints in the batch, as `u32`
+/// - the length of the statements and the length of the solvers, as `u64`
+/// - the crc32 digest of the statements followed by the solvers, as `u32`
+/// - the statements, encoded like in the constraints section of a plain program
+/// - the solvers first referenced by this batch, as a cbor array, so that they can be recovered when resuming
+///
+/// A frame is truncated if the stream ends before its last byte, and corrupted if its digest does not match.
+/// Readers stop at the first truncated or corrupted frame, so that the frames before it can still be read.
+#[derive(Default)]
+struct Frame {
+    statement_count: u32,
+    constraint_count: u32,
+    statements: Vec<u8>,
+    solvers: Vec<u8>,
+}
+
+impl Frame {
+    fn checksum(&self) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&self.statements);
+        hasher.update(&self.solvers);
+        hasher.finalize()
+    }
+
+    fn length(&self) -> u64 {
+        FRAME_HEADER_LENGTH + self.statements.len() as u64 + self.solvers.len() as u64
+    }
+
+    fn write<W: Write>(&self, mut w: W) -> std::io::Result<()> {
+        w.write_u32::<LittleEndian>(self.statement_count)?;
+        w.write_u32::<LittleEndian>(self.constraint_count)?;
+        w.write_u64::<LittleEndian>(self.statements.len() as u64)?;
+        w.write_u64::<LittleEndian>(self.solvers.len() as u64)?;
+        w.write_u32::<LittleEndian>(self.checksum())?;
+        w.write_all(&self.statements)?;
+        w.write_all(&self.solvers)
+    }
+
+    /// read the next frame of `r`, returning `None` at the end of `r` or if the frame is truncated or corrupted
+    fn read<R: Read>(mut r: R) -> Option<Self> {
+        let statement_count = r.read_u32::<LittleEndian>().ok()?;
+        let constraint_count = r.read_u32::<LittleEndian>().ok()?;
+        let statements_length = r.read_u64::<LittleEndian>().ok()?;
+        let solvers_length = r.read_u64::<LittleEndian>().ok()?;
+        let checksum = r.read_u32::<LittleEndian>().ok()?;
+
+        let frame = Frame {
+            statement_count,
+            constraint_count,
+            statements: read_bytes(r.by_ref(), statements_length)?,
+            solvers: read_bytes(r.by_ref(), solvers_length)?,
+        };
+
+        match frame.checksum() == checksum {
+            true => Some(frame),
+            false => None,
+        }
+    }
+}
+
+/// read exactly `length` bytes, without allocating more than what `r` actually contains
+fn read_bytes<R: Read>(r: R, length: u64) -> Option<Vec<u8>> {
+    let mut buf = vec![];
+    r.take(length).read_to_end(&mut buf).ok()?;
+
+    match buf.len() as u64 == length {
+        true => Some(buf),
+        false => None,
+    }
+}
+
+/// A reader over the statements of the frames of a chunked constraints section
+/// A truncated or corrupted frame ends the stream, see `Frame`
+pub struct ChunkReader<R> {
+    inner: R,
+    buffer: Cursor<Vec<u8>>,
+    done: bool,
+}
+
+impl<R: Read> ChunkReader<R> {
+    pub(super) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buffer: Cursor::new(vec![]),
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Read for ChunkReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let n = self.buffer.read(buf)?;
+            if n > 0 || buf.is_empty() || self.done {
+                return Ok(n);
+            }
+
+            match Frame::read(&mut self.inner) {
+                Some(frame) => self.buffer = Cursor::new(frame.statements),
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+/// Serializes a program in batches of statements, so that an interrupted serialization can be resumed
+///
+/// The header and the parameters are written by `new`, with the header marked as incomplete.
+/// Each call to `write_batch` then appends a frame to the constraints section, see `Frame`.
+/// If the serialization is interrupted, `resume` recovers the writer after the last complete frame.
+/// Finally, `finalize` writes the solvers and the module map, and rewrites the header with the totals.
+/// Until then, the program cannot be read.
+/// Unconstrained variables are not detected, as the statements are written in several stages.
+pub struct ChunkedProgWriter<'ast, T, W> {
+    w: ChecksumWriter<W>,
+    header: ProgHeader,
+    solver_indexer: SolverIndexer<'ast, T>,
+    /// the number of solvers written to frames so far
+    persisted_solvers: usize,
+    variable_counter: VariableCounter,
+    statement_count: usize,
+}
+
+impl<'ast, T: Field, W: Write + Seek> ChunkedProgWriter<'ast, T, W> {
+    /// start the serialization of a program with `arguments` and `return_count` outputs to `w`
+    pub fn new(w: W, arguments: &[Parameter], return_count: usize) -> Result<Self, DynamicError> {
+        let mut w = ChecksumWriter::new(w);
+
+        // reserve bytes for the header
+        w.write_all(&[0u8; std::mem::size_of::<ProgHeader>()])?;
+
+        let mut parameters = Section::new(SectionType::Parameters);
+        parameters.set_offset(w.stream_position()?);
+        w.checksum();
+
+        serde_cbor::to_writer(&mut w, arguments)?;
+
+        parameters.set_length(w.stream_position()? - parameters.offset);
+        parameters.set_checksum(w.checksum());
+
+        let mut constraints = Section::new(SectionType::Constraints);
+        constraints.set_offset(w.stream_position()?);
+
+        let header = ProgHeader {
+            magic: *ZOKRATES_MAGIC,
+            version: *FILE_VERSION,
+            curve_id: T::id(),
+            constraint_count: 0,
+            return_count: return_count as u32,
+            flags: CHUNKED_CONSTRAINTS | INCOMPLETE,
+            variable_count: None,
+            sections: [
+                parameters,
+                constraints,
+                Section::new(SectionType::Solvers),
+                Section::new(SectionType::Modules),
+            ],
+            constraint_index: None,
+            return_slots: None,
+            embeds: None,
+            source_hash: [0; 32],
+            metadata: None,
+            types: None,
+        };
+
+        // write the header right away, so that `resume` can find the constraints section
+        w.rewind()?;
+        header.write(&mut w)?;
+        w.seek(SeekFrom::Start(header.sections[1].offset))?;
+        w.flush()?;
+        w.checksum();
+
+        let mut variable_counter = VariableCounter::default();
+        for a in arguments {
+            <VariableCounter as Visitor<T>>::visit_argument(&mut variable_counter, a);
+        }
+
+        Ok(Self {
+            w,
+            header,
+            solver_indexer: SolverIndexer::default(),
+            persisted_solvers: 0,
+            variable_counter,
+            statement_count: 0,
+        })
+    }
+
+    /// the number of statements written in complete frames, from which the input should be resumed
+    pub fn statement_count(&self) -> usize {
+        self.statement_count
+    }
+
+    /// the number of constraints written in complete frames
+    pub fn constraint_count(&self) -> usize {
+        self.header.constraint_count as usize
+    }
+
+    /// write `statements` in a new frame, returning the number of constraints written
+    /// After an error, the writer should be dropped and the serialization resumed with `resume`
+    pub fn write_batch<I: IntoIterator<Item = Statement<'ast, T>>>(
+        &mut self,
+        statements: I,
+    ) -> Result<usize, DynamicError> {
+        let mut frame = Frame::default();
+
+        for s in statements {
+            frame.statement_count += 1;
+            if matches!(s, Statement::Constraint(..)) {
+                frame.constraint_count += 1;
+            }
+            let s = self.solver_indexer.fold_statement(s);
+            if let Some(e) = self.solver_indexer.error.take() {
+                return Err(e.into());
+            }
+            for s in s {
+                <VariableCounter as Visitor<T>>::visit_statement(&mut self.variable_counter, &s);
+                serde_cbor::to_writer(&mut frame.statements, &s)?;
+            }
+        }
+
+        serde_cbor::to_writer(
+            &mut frame.solvers,
+            &self.solver_indexer.solvers[self.persisted_solvers..],
+        )?;
+
+        frame.write(&mut self.w)?;
+        self.w.flush()?;
+
+        self.persisted_solvers = self.solver_indexer.solvers.len();
+        self.statement_count += frame.statement_count as usize;
+        self.header.constraint_count += frame.constraint_count;
+
+        Ok(frame.constraint_count as usize)
+    }
+
+    /// write the solvers and `module_map` after the last frame, and the header with the totals, returning the latter
+    pub fn finalize(mut self, module_map: &ModuleMap) -> Result<ProgHeader, DynamicError> {
+        let w = &mut self.w;
+
+        let constraints = &mut self.header.sections[1];
+        constraints.set_length(w.stream_position()? - constraints.offset);
+        constraints.set_checksum(w.checksum());
+
+        let solvers = &mut self.header.sections[2];
+        solvers.set_offset(w.stream_position()?);
+        serde_cbor::to_writer(&mut *w, &self.solver_indexer.solvers)?;
+        solvers.set_length(w.stream_position()? - solvers.offset);
+        solvers.set_checksum(w.checksum());
+
+        let modules = &mut self.header.sections[3];
+        modules.set_offset(w.stream_position()?);
+        serde_cbor::to_writer(&mut *w, module_map)?;
+        modules.set_length(w.stream_position()? - modules.offset);
+        modules.set_checksum(w.checksum());
+
+        self.header.flags &= !INCOMPLETE;
+        self.header.variable_count = Some(self.variable_counter.count());
+
+        // rewind to write the header
+        w.rewind()?;
+        self.header.write(&mut *w)?;
+        w.flush()?;
+
+        Ok(self.header)
+    }
+}
+
+impl<'ast, T: Field, F: Read + Write + Seek> ChunkedProgWriter<'ast, T, F> {
+    /// resume the interrupted serialization in `f`, after its last complete frame
+    /// Truncated or corrupted frames are overwritten by the following batches, see `statement_count` to know where to resume the input.
+    pub fn resume(mut f: F) -> Result<Self, DynamicError> {
+        let mut header = ProgHeader::read(&mut f).map_err(|_| String::from("Invalid header"))?;
+
+        if &header.magic != ZOKRATES_MAGIC || !header.is_chunked() || !header.is_incomplete() {
+            return Err(
+                "Only the incomplete serialization of a chunked program can be resumed".into(),
+            );
+        }
+
+        if &header.version != FILE_VERSION {
+            return Err(format!(
+                "Cannot resume a program written in file version {}",
+                header.version[0]
+            )
+            .into());
+        }
+
+        if header.curve_id != T::id() {
+            return Err(format!(
+                "Expected a program over curve `{}`, found `{}`",
+                T::name(),
+                header.curve_name().unwrap_or("unknown")
+            )
+            .into());
+        }
+
+        let mut variable_counter = VariableCounter::default();
+
+        let parameters = &header.sections[0];
+        f.seek(SeekFrom::Start(parameters.offset))?;
+        for a in deserialize_parameters(f.by_ref().take(parameters.length), &header.version)? {
+            <VariableCounter as Visitor<T>>::visit_argument(&mut variable_counter, &a);
+        }
+
+        let mut position = header.sections[1].offset;
+        f.seek(SeekFrom::Start(position))?;
+
+        // the digest of the complete frames, which the constraints section digest resumes from
+        let mut digest = ChecksumWriter::new(std::io::sink());
+        let mut solvers: Vec<Solver<'ast, T>> = vec![];
+        let mut statement_count = 0;
+        let mut constraint_count = 0;
+
+        while let Some(frame) = Frame::read(&mut f) {
+            let mut p = serde_cbor::Deserializer::from_reader(&frame.solvers[..]);
+            solvers.extend(
+                Vec::<Solver<T>>::deserialize(&mut p)
+                    .map_err(|_| String::from("Cannot read solvers"))?,
+            );
+
+            for s in serde_cbor::Deserializer::from_reader(&frame.statements[..])
+                .into_iter::<Statement<T>>()
+            {
+                let s = s.map_err(|e| format!("Cannot read constraints: {}", e))?;
+                <VariableCounter as Visitor<T>>::visit_statement(&mut variable_counter, &s);
+            }
+
+            frame.write(&mut digest)?;
+            position += frame.length();
+            statement_count += frame.statement_count as usize;
+            constraint_count += frame.constraint_count;
+        }
+
+        f.seek(SeekFrom::Start(position))?;
+        let mut w = ChecksumWriter::new(f);
+        w.hasher = digest.hasher;
+
+        header.constraint_count = constraint_count;
+
+        Ok(Self {
+            w,
+            header,
+            persisted_solvers: solvers.len(),
+            solver_indexer: SolverIndexer::with_solvers(solvers),
+            variable_counter,
+            statement_count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Prog, ProgEnum, QuadComb, Variable};
+    use zokrates_field::Bn128Field;
+
+    fn prog() -> Prog<'static, Bn128Field> {
+        Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: (0..4)
+                .map(|i| {
+                    Statement::constraint(
+                        QuadComb::new(Variable::new(i).into(), Variable::new(i).into()),
+                        Variable::new(i + 1),
+                        None,
+                    )
+                })
+                .chain(std::iter::once(Statement::definition(
+                    Variable::public(0),
+                    Variable::new(4),
+                )))
+                .collect(),
+            ..Prog::default()
+        }
+    }
+
+    /// write the statements of `prog()` in two batches, the first one with `split` statements
+    fn write_batches<W: Write + Seek>(
+        w: W,
+        split: usize,
+    ) -> ChunkedProgWriter<'static, Bn128Field, W> {
+        let p = prog();
+        let mut writer = ChunkedProgWriter::new(w, &p.arguments, p.return_count).unwrap();
+        writer.write_batch(p.statements[..split].to_vec()).unwrap();
+        writer.write_batch(p.statements[split..].to_vec()).unwrap();
+        writer
+    }
+
+    #[test]
+    fn chunked() {
+        let mut buffer = Cursor::new(vec![]);
+        let writer = write_batches(&mut buffer, 2);
+        assert_eq!(writer.statement_count(), 5);
+        assert_eq!(writer.constraint_count(), 5);
+        let header = writer.finalize(&ModuleMap::default()).unwrap();
+        assert!(header.is_chunked());
+        assert!(!header.is_incomplete());
+
+        let mut plain = Cursor::new(vec![]);
+        prog().serialize(&mut plain).unwrap();
+        plain.rewind().unwrap();
+        assert_eq!(
+            header.variable_count,
+            ProgHeader::read(&mut plain).unwrap().variable_count
+        );
+
+        buffer.rewind().unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer.clone()).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(prog()), deserialized_p.collect());
+
+        let bytes = buffer.into_inner();
+        let deserialized_p = ProgEnum::deserialize_stream(&bytes[..]).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(prog()), deserialized_p.collect());
+    }
+
+    #[test]
+    fn incomplete() {
+        let mut buffer = Cursor::new(vec![]);
+        write_batches(&mut buffer, 2);
+
+        buffer.rewind().unwrap();
+        assert_eq!(
+            ProgEnum::deserialize(buffer).err().unwrap(),
+            "Program is incomplete, as its chunked serialization was never finalized"
+        );
+    }
+
+    #[test]
+    fn resume_truncated() {
+        let mut buffer = Cursor::new(vec![]);
+        write_batches(&mut buffer, 2);
+
+        // interrupt the write of the second frame
+        let mut bytes = buffer.into_inner();
+        bytes.truncate(bytes.len() - 3);
+        let mut buffer = Cursor::new(bytes);
+
+        let mut writer = ChunkedProgWriter::<Bn128Field, _>::resume(&mut buffer).unwrap();
+        assert_eq!(writer.statement_count(), 2);
+        assert_eq!(writer.constraint_count(), 2);
+
+        writer
+            .write_batch(prog().statements[writer.statement_count()..].to_vec())
+            .unwrap();
+        writer.finalize(&ModuleMap::default()).unwrap();
+
+        buffer.rewind().unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(prog()), deserialized_p.collect());
+    }
+
+    #[test]
+    fn skip_truncated_frame() {
+        let frame = |statements: &[u8]| Frame {
+            statement_count: 1,
+            constraint_count: 1,
+            statements: statements.to_vec(),
+            solvers: vec![0x80],
+        };
+
+        let mut bytes = vec![];
+        frame(&[1, 2, 3]).write(&mut bytes).unwrap();
+        frame(&[4, 5, 6]).write(&mut bytes).unwrap();
+        bytes.pop();
+
+        let mut read = vec![];
+        ChunkReader::new(&bytes[..]).read_to_end(&mut read).unwrap();
+        assert_eq!(read, vec![1, 2, 3]);
+
+        // a corrupted frame ends the stream like a truncated one
+        bytes[FRAME_HEADER_LENGTH as usize] = 0;
+
+        let mut read = vec![];
+        ChunkReader::new(&bytes[..]).read_to_end(&mut read).unwrap();
+        assert!(read.is_empty());
+    }
+}
@@ -13,6 +13,8 @@ impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'a
             module_map: self.module_map,
             arguments: self.arguments,
             return_count: self.return_count,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
             statements: self
                 .statements
                 .into_iter()
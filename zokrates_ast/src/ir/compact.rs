@@ -0,0 +1,95 @@
+use super::folder::Folder;
+use super::{Prog, Variable};
+use std::collections::HashMap;
+use zokrates_field::Field;
+
+/// Renumbers the intermediate variables of a program to `0..n`, in order of first appearance
+/// Arguments come first, so they keep the lowest ids. `~one` and the outputs are left untouched.
+#[derive(Default)]
+pub struct CompactVariables {
+    substitution: HashMap<Variable, Variable>,
+}
+
+impl CompactVariables {
+    /// compact the variables of `p`, returning the new program and the mapping from old to new variables
+    pub fn compact<T: Field>(p: Prog<T>) -> (Prog<T>, HashMap<Variable, Variable>) {
+        let mut compacter = CompactVariables::default();
+        let p = compacter.fold_program(p);
+        (p, compacter.substitution)
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for CompactVariables {
+    fn fold_variable(&mut self, v: Variable) -> Variable {
+        if v == Variable::one() || v.is_output() {
+            return v;
+        }
+
+        let next = Variable::new(self.substitution.len());
+        *self.substitution.entry(v).or_insert(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, Parameter, QuadComb, Solver, Statement};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn compact() {
+        // _3 = bits(_7)
+        // _3 * _3 == _3
+        // _7 == ~out_0
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(7))],
+            return_count: 1,
+            statements: vec![
+                Statement::directive(
+                    vec![Variable::new(3)],
+                    Solver::Bits(1),
+                    vec![Variable::new(7).into()],
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(3).into(), Variable::new(3).into()),
+                    Variable::new(3),
+                    None,
+                ),
+                Statement::constraint(LinComb::from(Variable::new(7)), Variable::public(0), None),
+            ],
+            ..Prog::default()
+        };
+
+        let (compacted, substitution) = CompactVariables::compact(p);
+
+        assert_eq!(
+            compacted,
+            Prog {
+                arguments: vec![Parameter::private(Variable::new(0))],
+                return_count: 1,
+                statements: vec![
+                    Statement::directive(
+                        vec![Variable::new(1)],
+                        Solver::Bits(1),
+                        vec![Variable::new(0).into()],
+                    ),
+                    Statement::constraint(
+                        QuadComb::new(Variable::new(1).into(), Variable::new(1).into()),
+                        Variable::new(1),
+                        None,
+                    ),
+                    Statement::constraint(
+                        LinComb::from(Variable::new(0)),
+                        Variable::public(0),
+                        None,
+                    ),
+                ],
+                ..Prog::default()
+            }
+        );
+
+        assert_eq!(substitution.len(), 2);
+        assert_eq!(substitution[&Variable::new(7)], Variable::new(0));
+        assert_eq!(substitution[&Variable::new(3)], Variable::new(1));
+    }
+}
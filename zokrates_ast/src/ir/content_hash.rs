@@ -0,0 +1,156 @@
+use super::folder::Folder;
+use super::{Canonicalizer, ConstraintStatement, Prog, Statement, StripSpans};
+use sha2::{Digest, Sha256};
+use zokrates_field::Field;
+
+/// Separates content hashes from other uses of SHA-256, and from future versions of this hash
+const CONTENT_HASH_DOMAIN: &[u8] = b"zokrates-content-hash-v1";
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// a SHA-256 hash of the semantics of this program, suitable as a cache key for setup artifacts
+    ///
+    /// The hash covers, in order: the curve id, the number of outputs, the arguments (variables and visibilities),
+    /// the statements other than logs, and the solvers.
+    /// Spans, the module map, and the runtime errors and origins of constraints are ignored, so that moving an assertion or changing its message
+    /// keeps the hash. Blocks are flattened and linear combinations are canonicalized, see `StripSpans` and `Canonicalizer`.
+    /// Statements are hashed one by one, so the program is never duplicated in memory.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+
+        hasher.update(CONTENT_HASH_DOMAIN);
+        hasher.update(T::id());
+        hasher.update((self.return_count as u64).to_le_bytes());
+
+        let arguments: Vec<_> = self
+            .arguments
+            .iter()
+            .map(|a| (a.id, a.visibility))
+            .collect();
+        // writing to a hasher cannot fail
+        serde_cbor::to_writer(&mut hasher, &arguments).unwrap();
+
+        for s in self.iter_statements() {
+            for s in Folder::fold_statement(&mut StripSpans, s.clone()) {
+                hash_statement(&mut hasher, s);
+            }
+        }
+
+        let solvers: Vec<_> = self
+            .solvers
+            .iter()
+            .map(|s| StripSpans.fold_solver(s.clone()))
+            .collect();
+        serde_cbor::to_writer(&mut hasher, &solvers).unwrap();
+
+        hasher.finalize().into()
+    }
+}
+
+fn hash_statement<T: Field>(hasher: &mut Sha256, s: Statement<T>) {
+    match s {
+        Statement::Block(s) => {
+            for s in s.inner {
+                hash_statement(hasher, s);
+            }
+        }
+        Statement::Log(_) => {}
+        s => {
+            for s in Canonicalizer.fold_statement(s) {
+                let s = match s {
+                    Statement::Constraint(c) => Statement::Constraint(ConstraintStatement {
+                        error: None,
+                        ..c.origin(None)
+                    }),
+                    s => s,
+                };
+                serde_cbor::to_writer(&mut *hasher, &s).unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{ModuleMap, Position, SourceMetadata, SourceSpan, WithSpan};
+    use crate::ir::{LinComb, Parameter, QuadComb, RuntimeError, Variable};
+    use zokrates_field::Bn128Field;
+
+    fn prog(lin: LinComb<Bn128Field>) -> Prog<'static, Bn128Field> {
+        Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                lin,
+                None,
+            )],
+            ..Prog::default()
+        }
+    }
+
+    #[test]
+    fn span_independent() {
+        let a = prog(Variable::public(0).into());
+
+        let mut b = a.clone();
+        b.module_map = ModuleMap::new(vec!["/home/alice/main.zok".into()]);
+        b.statements = vec![Statement::block(
+            b.statements
+                .into_iter()
+                .map(|s| s.span(Some(SourceSpan::mock().into())))
+                .collect(),
+        )];
+
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn assertion_independent() {
+        let assertion = |line: usize| {
+            let mut p = prog(Variable::public(0).into());
+            p.statements = vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                Variable::public(0),
+                Some(RuntimeError::SourceAssertion(SourceMetadata::new(
+                    String::from("main.zok"),
+                    Position { line, col: 5 },
+                ))),
+            )];
+            p
+        };
+
+        // moving the assertion one line down
+        assert_eq!(assertion(3).content_hash(), assertion(4).content_hash());
+        assert_eq!(
+            assertion(3).content_hash(),
+            prog(Variable::public(0).into()).content_hash()
+        );
+    }
+
+    #[test]
+    fn canonical() {
+        // ~out_0 + _0 - _0
+        let a = prog(LinComb::new(vec![
+            (Variable::public(0), Bn128Field::from(1)),
+            (Variable::new(0), Bn128Field::from(1)),
+            (Variable::new(0), Bn128Field::from(-1)),
+        ]));
+        let b = prog(Variable::public(0).into());
+
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn different() {
+        let a = prog(Variable::public(0).into());
+        let b = prog(LinComb::from(Variable::public(0)) * &Bn128Field::from(2));
+
+        assert_ne!(a.content_hash(), b.content_hash());
+
+        let mut c = a.clone();
+        c.arguments = vec![Parameter::public(Variable::new(0))];
+
+        assert_ne!(a.content_hash(), c.content_hash());
+    }
+}
@@ -0,0 +1,237 @@
+use super::{
+    BlockStatement, ConstraintStatement, DirectiveStatement, LinComb, LogStatement, Prog, QuadComb,
+    Solver, Statement,
+};
+use crate::common::Polynomial;
+use std::fmt;
+use zokrates_field::Field;
+
+/// An error raised when converting a program to another field, see `Prog::try_into_field`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// the fields have different moduli, so the constraints of the program would not have the same solutions
+    FieldMismatch {
+        from: &'static str,
+        to: &'static str,
+    },
+    /// a constant of the program does not fit in the target field
+    ValueOutOfRange(String),
+    /// the solver at this index of the solvers of the program, or of a directive if `None`, cannot be converted
+    UnsupportedSolver(Option<usize>),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConversionError::FieldMismatch { from, to } => write!(
+                f,
+                "Cannot convert a program over curve `{}` to curve `{}`, as their scalar fields differ",
+                from, to
+            ),
+            ConversionError::ValueOutOfRange(value) => {
+                write!(f, "Value `{}` does not fit in the target field", value)
+            }
+            ConversionError::UnsupportedSolver(Some(index)) => {
+                write!(f, "Solver {} cannot be converted to another field", index)
+            }
+            ConversionError::UnsupportedSolver(None) => {
+                write!(f, "A directive uses a solver which cannot be converted to another field")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// convert this program to a program over `U`, checking that it keeps its meaning
+    ///
+    /// A program is only convertible between fields with the same modulus: arithmetic wraps around at the modulus, and negative constants are encoded relative to it, so the constraints would have other solutions over another field.
+    /// None of the curves supported by this library share their scalar field, so a program over `T` is only convertible to `T` itself, and other pairs fail with `ConversionError::FieldMismatch`.
+    /// Solvers defined by zir functions are not converted and fail with `ConversionError::UnsupportedSolver`.
+    pub fn try_into_field<U: Field>(self) -> Result<Prog<'ast, U>, ConversionError> {
+        if T::max_value().to_biguint() != U::max_value().to_biguint() {
+            return Err(ConversionError::FieldMismatch {
+                from: T::name(),
+                to: U::name(),
+            });
+        }
+
+        let solvers = self
+            .solvers
+            .into_iter()
+            .enumerate()
+            .map(|(index, s)| convert_solver(s, Some(index)))
+            .collect::<Result<_, _>>()?;
+
+        let statements = self
+            .statements
+            .into_iter()
+            .map(convert_statement)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Prog {
+            module_map: self.module_map,
+            arguments: self.arguments,
+            return_count: self.return_count,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
+            statements,
+            solvers,
+        })
+    }
+}
+
+fn convert_value<T: Field, U: Field>(v: T) -> Result<U, ConversionError> {
+    U::try_from(v.to_biguint()).map_err(|_| ConversionError::ValueOutOfRange(v.to_dec_string()))
+}
+
+fn convert_linear_combination<T: Field, U: Field>(
+    e: LinComb<T>,
+) -> Result<LinComb<U>, ConversionError> {
+    Ok(LinComb {
+        span: e.span,
+        value: e
+            .value
+            .into_iter()
+            .map(|(variable, coefficient)| Ok((variable, convert_value(coefficient)?)))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn convert_quadratic_combination<T: Field, U: Field>(
+    e: QuadComb<T>,
+) -> Result<QuadComb<U>, ConversionError> {
+    Ok(QuadComb {
+        span: e.span,
+        left: convert_linear_combination(e.left)?,
+        right: convert_linear_combination(e.right)?,
+    })
+}
+
+fn convert_statement<'ast, T: Field, U: Field>(
+    s: Statement<'ast, T>,
+) -> Result<Statement<'ast, U>, ConversionError> {
+    Ok(match s {
+        Statement::Constraint(s) => Statement::Constraint(ConstraintStatement {
+            span: s.span,
+            quad: convert_quadratic_combination(s.quad)?,
+            lin: convert_linear_combination(s.lin)?,
+            error: s.error,
+            origin: s.origin,
+        }),
+        Statement::Directive(d) => Statement::Directive(DirectiveStatement {
+            span: d.span,
+            inputs: d
+                .inputs
+                .into_iter()
+                .map(convert_quadratic_combination)
+                .collect::<Result<_, _>>()?,
+            outputs: d.outputs,
+            solver: convert_solver(d.solver, None)?,
+        }),
+        Statement::Log(l) => Statement::Log(LogStatement {
+            span: l.span,
+            format_string: l.format_string,
+            expressions: l
+                .expressions
+                .into_iter()
+                .map(|(ty, e)| {
+                    Ok((
+                        ty,
+                        e.into_iter()
+                            .map(convert_linear_combination)
+                            .collect::<Result<_, _>>()?,
+                    ))
+                })
+                .collect::<Result<_, _>>()?,
+        }),
+        Statement::Block(b) => Statement::Block(BlockStatement {
+            span: b.span,
+            inner: b
+                .inner
+                .into_iter()
+                .map(convert_statement)
+                .collect::<Result<_, _>>()?,
+        }),
+    })
+}
+
+/// convert a solver, failing for zir functions which are reported at `index`
+fn convert_solver<'ast, T: Field, U: Field>(
+    s: Solver<'ast, T>,
+    index: Option<usize>,
+) -> Result<Solver<'ast, U>, ConversionError> {
+    Ok(match s {
+        Solver::ConditionEq => Solver::ConditionEq,
+        Solver::Bits(n) => Solver::Bits(n),
+        Solver::Div => Solver::Div,
+        Solver::Xor => Solver::Xor,
+        Solver::Or => Solver::Or,
+        Solver::ShaAndXorAndXorAnd => Solver::ShaAndXorAndXorAnd,
+        Solver::ShaCh => Solver::ShaCh,
+        Solver::EuclideanDiv => Solver::EuclideanDiv,
+        Solver::Zir(_) => return Err(ConversionError::UnsupportedSolver(index)),
+        Solver::Ref(call) => Solver::Ref(call),
+        Solver::ConditionalInverse => Solver::ConditionalInverse,
+        Solver::BabyJubjubAdd => Solver::BabyJubjubAdd,
+        #[cfg(feature = "bellman")]
+        Solver::Sha256Round => Solver::Sha256Round,
+        #[cfg(feature = "ark")]
+        Solver::SnarkVerifyBls12377(n) => Solver::SnarkVerifyBls12377(n),
+        Solver::Polynomial(p) => Solver::Polynomial(Polynomial {
+            variables: p.variables,
+            terms: p
+                .terms
+                .into_iter()
+                .map(|(coefficient, exponents)| Ok((convert_value(coefficient)?, exponents)))
+                .collect::<Result<_, _>>()?,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Parameter, Variable};
+    use zokrates_field::{Bls12_381Field, Bn128Field};
+
+    fn prog() -> Prog<'static, Bn128Field> {
+        Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                Statement::directive(
+                    vec![Variable::new(1)],
+                    Solver::Bits(1),
+                    vec![Variable::new(0).into()],
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(1).into()),
+                    LinComb::summand(-1, Variable::public(0)),
+                    None,
+                ),
+            ],
+            solvers: vec![Solver::Div],
+            ..Prog::default()
+        }
+    }
+
+    #[test]
+    fn same_field() {
+        let p = prog();
+        let converted: Prog<Bn128Field> = p.clone().try_into_field().unwrap();
+        assert_eq!(converted, p);
+    }
+
+    #[test]
+    fn other_field() {
+        assert_eq!(
+            prog().try_into_field::<Bls12_381Field>().unwrap_err(),
+            ConversionError::FieldMismatch {
+                from: "bn128",
+                to: "bls12_381"
+            }
+        );
+    }
+}
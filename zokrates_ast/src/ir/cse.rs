@@ -0,0 +1,198 @@
+use super::folder::{fold_constraint_statement, Folder};
+use super::{
+    collect_variables_prog, ConstraintStatement, LinComb, Prog, QuadComb, Statement, Variable,
+};
+use std::collections::HashMap;
+use zokrates_field::Field;
+
+/// Replaces the linear combinations which occur several times in the constraints of a program with fresh variables
+///
+/// Combinations are compared once reduced, and only those with at least `min_terms` terms are hoisted, so that the pass is opt-in for large combinations.
+/// `min_terms` is at least 2: a single variable is never hoisted, as a constraint whose linear side is a single variable is the one assigning it.
+/// Each hoisted combination `L` gets a fresh variable `v`, defined by the constraint `L == v` right before the first constraint using `L`,
+/// at which point the variables of `L` are known. Every occurrence of `L` as a side of a constraint is then replaced with `v`.
+/// This keeps the solutions of the program, as `v` is constrained to be `L`, and the variable count of the program grows by one per hoisted combination.
+/// Directives and logs are left untouched.
+pub struct HoistCommonSubexpressions<T> {
+    /// the fresh variable of each hoisted combination, and whether it is defined yet
+    hoisted: HashMap<LinComb<T>, (Variable, bool)>,
+}
+
+impl<T: Field> HoistCommonSubexpressions<T> {
+    /// hoist the combinations of at least `min_terms` terms which occur more than once in `p`, returning the new program and the number of combinations hoisted
+    pub fn hoist(p: Prog<T>, min_terms: usize) -> (Prog<T>, usize) {
+        // hoisting single variables would define them from themselves before they are assigned
+        let min_terms = min_terms.max(2);
+
+        let mut counts = HashMap::new();
+        for s in &p.statements {
+            count_combinations(s, min_terms, &mut counts);
+        }
+
+        // fresh variables come after all the variables of the program
+        let next = collect_variables_prog(&p)
+            .into_iter()
+            .filter(|v| v.id > 0)
+            .map(|v| v.id() + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut repeated: Vec<_> = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(e, _)| e)
+            .collect();
+        // number the fresh variables deterministically
+        repeated.sort();
+
+        let mut hoister = HoistCommonSubexpressions {
+            hoisted: repeated
+                .into_iter()
+                .enumerate()
+                .map(|(i, e)| (e, (Variable::new(next + i), false)))
+                .collect(),
+        };
+
+        let count = hoister.hoisted.len();
+        (hoister.fold_program(p), count)
+    }
+
+    /// replace `e` with the variable of its combination if it is hoisted, pushing the definition of the variable to `definitions` if it is the first use
+    fn replace(
+        &mut self,
+        e: LinComb<T>,
+        definitions: &mut Vec<ConstraintStatement<T>>,
+    ) -> LinComb<T> {
+        let reduced = e.clone().reduce();
+        match self.hoisted.get_mut(&reduced) {
+            Some((v, defined)) => {
+                if !*defined {
+                    *defined = true;
+                    definitions.push(ConstraintStatement::new(
+                        QuadComb::from(reduced),
+                        LinComb::from(*v),
+                        None,
+                    ));
+                }
+                LinComb::from(*v)
+            }
+            None => e,
+        }
+    }
+}
+
+/// count the reduced combinations of at least `min_terms` terms of the constraints of `s`
+fn count_combinations<T: Field>(
+    s: &Statement<T>,
+    min_terms: usize,
+    counts: &mut HashMap<LinComb<T>, usize>,
+) {
+    match s {
+        Statement::Block(b) => {
+            for s in &b.inner {
+                count_combinations(s, min_terms, counts);
+            }
+        }
+        Statement::Constraint(c) => {
+            for e in [&c.quad.left, &c.quad.right, &c.lin] {
+                let e = e.clone().reduce();
+                if e.value.len() >= min_terms {
+                    *counts.entry(e).or_default() += 1;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for HoistCommonSubexpressions<T> {
+    fn fold_constraint_statement(&mut self, s: ConstraintStatement<T>) -> Vec<Statement<'ast, T>> {
+        let mut definitions = vec![];
+
+        let quad = QuadComb::new(
+            self.replace(s.quad.left, &mut definitions),
+            self.replace(s.quad.right, &mut definitions),
+        );
+        let lin = self.replace(s.lin, &mut definitions);
+
+        definitions
+            .into_iter()
+            .map(Statement::Constraint)
+            .chain(fold_constraint_statement(
+                self,
+                ConstraintStatement { quad, lin, ..s },
+            ))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Parameter;
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn hoist() {
+        let sum = || LinComb::from(Variable::new(0)) + LinComb::from(Variable::new(1));
+
+        // def main(private _0, private _1) -> (~out_0)
+        // (_0 + _1) * (_0 + _1) == _2
+        // (_1 + _0) * _2 == ~out_0
+        // _0 * _1 == _0 + _1 + _2
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(QuadComb::new(sum(), sum()), Variable::new(2), None),
+                Statement::constraint(
+                    QuadComb::new(
+                        LinComb::from(Variable::new(1)) + LinComb::from(Variable::new(0)),
+                        Variable::new(2).into(),
+                    ),
+                    Variable::public(0),
+                    None,
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(1).into()),
+                    sum() + LinComb::from(Variable::new(2)),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        };
+
+        // combinations of three terms are never repeated
+        let (unchanged, count) = HoistCommonSubexpressions::hoist(p.clone(), 3);
+        assert_eq!(count, 0);
+        assert_eq!(unchanged, p);
+
+        let (hoisted, count) = HoistCommonSubexpressions::hoist(p.clone(), 2);
+        assert_eq!(count, 1);
+
+        // single variables such as `_2` are never hoisted
+        assert_eq!(HoistCommonSubexpressions::hoist(p, 1), (hoisted.clone(), 1));
+
+        let v = Variable::new(3);
+        assert_eq!(
+            hoisted.statements,
+            vec![
+                Statement::constraint(sum().reduce(), v, None),
+                Statement::constraint(QuadComb::new(v.into(), v.into()), Variable::new(2), None),
+                Statement::constraint(
+                    QuadComb::new(v.into(), Variable::new(2).into()),
+                    Variable::public(0),
+                    None,
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(1).into()),
+                    sum() + LinComb::from(Variable::new(2)),
+                    None,
+                ),
+            ]
+        );
+    }
+}
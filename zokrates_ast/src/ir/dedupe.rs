@@ -0,0 +1,81 @@
+use super::folder::{fold_constraint_statement, Folder};
+use super::{ConstraintStatement, Prog, Statement};
+use std::collections::HashSet;
+use zokrates_field::Field;
+
+/// Removes the constraints which are exact duplicates of an earlier constraint, keeping the first occurrence and its span
+/// Constraints are compared regardless of their span and error, so constraints which differ in a single coefficient or in the order of their terms are kept.
+pub struct DedupeConstraints<T> {
+    seen: HashSet<ConstraintStatement<T>>,
+    removed: usize,
+}
+
+impl<T> Default for DedupeConstraints<T> {
+    fn default() -> Self {
+        Self {
+            seen: HashSet::default(),
+            removed: 0,
+        }
+    }
+}
+
+impl<T: Field> DedupeConstraints<T> {
+    /// remove the duplicate constraints of `p`, returning the new program and the number of constraints removed
+    pub fn dedupe(p: Prog<T>) -> (Prog<T>, usize) {
+        let mut deduper = DedupeConstraints::default();
+        let p = deduper.fold_program(p);
+        (p, deduper.removed)
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for DedupeConstraints<T> {
+    fn fold_constraint_statement(&mut self, s: ConstraintStatement<T>) -> Vec<Statement<'ast, T>> {
+        if self.seen.contains(&s) {
+            self.removed += 1;
+            return vec![];
+        }
+
+        self.seen.insert(s.clone());
+        fold_constraint_statement(self, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{SourceSpan, WithSpan};
+    use crate::ir::{LinComb, Parameter, QuadComb, Variable};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn dedupe() {
+        let span = Some(SourceSpan::mock().into());
+
+        let square = |coefficient: usize| {
+            Statement::constraint(
+                QuadComb::new(
+                    LinComb::summand(coefficient, Variable::new(0)),
+                    Variable::new(0).into(),
+                ),
+                Variable::new(1),
+                None,
+            )
+        };
+
+        // _0 * _0 == _1
+        // _0 * _0 == _1
+        // 2 * _0 * _0 == _1
+        // _0 * _0 == _1
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            statements: vec![square(1).span(span), square(1), square(2), square(1)],
+            ..Prog::default()
+        };
+
+        let (deduped, removed) = DedupeConstraints::dedupe(p);
+
+        assert_eq!(removed, 2);
+        assert_eq!(deduped.statements, vec![square(1), square(2)]);
+        assert_eq!(deduped.statements[0].get_span(), span);
+    }
+}
@@ -0,0 +1,282 @@
+use super::folder::Folder;
+use super::{Canonicalizer, ConstraintStatement, Parameter, Prog, Statement, Variable};
+use crate::common::{Visibility, WithSpan};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use zokrates_field::Field;
+
+/// A change to the arguments of a program
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ParameterChange {
+    Added(Parameter),
+    Removed(Parameter),
+    Visibility {
+        id: Variable,
+        from: Visibility,
+        to: Visibility,
+    },
+}
+
+impl fmt::Display for ParameterChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParameterChange::Added(p) => write!(f, "+ {}", p),
+            ParameterChange::Removed(p) => write!(f, "- {}", p),
+            ParameterChange::Visibility { id, from, to } => {
+                write!(f, "~ {}: {:?} -> {:?}", id, from, to)
+            }
+        }
+    }
+}
+
+/// The semantic differences between two programs, see `diff_progs`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProgDiff<T> {
+    /// the number of constraints of the second program minus the number of constraints of the first one
+    pub constraint_count_delta: isize,
+    /// the constraints of the second program which are not in the first one, canonicalized and without spans
+    pub added: Vec<ConstraintStatement<T>>,
+    /// the constraints of the first program which are not in the second one, canonicalized and without spans
+    pub removed: Vec<ConstraintStatement<T>>,
+    pub parameters: Vec<ParameterChange>,
+    /// the number of outputs of the first and the second program, if they differ
+    pub return_count: Option<(usize, usize)>,
+}
+
+impl<T> ProgDiff<T> {
+    /// whether the two programs have the same arguments, the same number of outputs and the same constraints, up to their order
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.parameters.is_empty()
+            && self.return_count.is_none()
+    }
+}
+
+impl<T: Field> fmt::Display for ProgDiff<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "constraints: {:+} ({} added, {} removed)",
+            self.constraint_count_delta,
+            self.added.len(),
+            self.removed.len()
+        )?;
+        if let Some((from, to)) = self.return_count {
+            writeln!(f, "~ outputs: {} -> {}", from, to)?;
+        }
+        for p in &self.parameters {
+            writeln!(f, "{}", p)?;
+        }
+        for c in &self.removed {
+            writeln!(f, "- {}", c)?;
+        }
+        for c in &self.added {
+            writeln!(f, "+ {}", c)?;
+        }
+        Ok(())
+    }
+}
+
+/// compare the arguments, the number of outputs and the constraints of two programs over the same field
+///
+/// Constraints are canonicalized and compared as a multiset, so that reordering constraints or the terms of linear combinations is not a change.
+/// Constraints nested in blocks are compared like top-level ones.
+/// Only the linear combinations of a constraint are compared: its span, its origin and its runtime error, which holds the source position,
+/// file and message of the assertion it comes from, are dropped, so that moving or rewording an assertion is not a change.
+/// The spans of parameters, directives, logs, solvers, return slots and the module map are ignored.
+pub fn diff_progs<'ast, T: Field>(a: &Prog<'ast, T>, b: &Prog<'ast, T>) -> ProgDiff<T> {
+    let mut constraints = BTreeMap::new();
+
+    let count_a = count_constraints(a, &mut constraints, 1);
+    let count_b = count_constraints(b, &mut constraints, -1);
+
+    let mut added = vec![];
+    let mut removed = vec![];
+
+    for (c, count) in constraints {
+        let target = if count > 0 { &mut removed } else { &mut added };
+        target.extend(std::iter::repeat(c).take(count.unsigned_abs()));
+    }
+
+    ProgDiff {
+        constraint_count_delta: count_b as isize - count_a as isize,
+        added,
+        removed,
+        parameters: diff_parameters(&a.arguments, &b.arguments),
+        return_count: (a.return_count != b.return_count)
+            .then_some((a.return_count, b.return_count)),
+    }
+}
+
+/// add `weight` to the count of each canonical constraint of `p`, returning the number of constraints
+fn count_constraints<'ast, T: Field>(
+    p: &Prog<'ast, T>,
+    constraints: &mut BTreeMap<ConstraintStatement<T>, isize>,
+    weight: isize,
+) -> usize {
+    fn visit<'ast, T: Field>(
+        s: &Statement<'ast, T>,
+        constraints: &mut BTreeMap<ConstraintStatement<T>, isize>,
+        weight: isize,
+    ) -> usize {
+        match s {
+            Statement::Block(b) => b.inner.iter().map(|s| visit(s, constraints, weight)).sum(),
+            Statement::Constraint(c) => {
+                let c = ConstraintStatement::new(
+                    Canonicalizer.fold_quadratic_combination(c.quad.clone()),
+                    Canonicalizer.fold_linear_combination(c.lin.clone()),
+                    None,
+                );
+                *constraints.entry(c).or_default() += weight;
+                1
+            }
+            _ => 0,
+        }
+    }
+
+    p.iter_statements()
+        .map(|s| visit(s, constraints, weight))
+        .sum()
+}
+
+fn diff_parameters(a: &[Parameter], b: &[Parameter]) -> Vec<ParameterChange> {
+    let removed = a
+        .iter()
+        .filter(|p| !b.iter().any(|q| q.id == p.id))
+        .map(|p| ParameterChange::Removed(p.clone().span(None)));
+
+    let changed = a.iter().filter_map(|p| {
+        b.iter()
+            .find(|q| q.id == p.id && q.visibility != p.visibility)
+            .map(|q| ParameterChange::Visibility {
+                id: p.id,
+                from: p.visibility,
+                to: q.visibility,
+            })
+    });
+
+    let added = b
+        .iter()
+        .filter(|q| !a.iter().any(|p| p.id == q.id))
+        .map(|q| ParameterChange::Added(q.clone().span(None)));
+
+    removed.chain(changed).chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::SourceSpan;
+    use crate::ir::{LinComb, QuadComb};
+    use zokrates_field::Bn128Field;
+
+    fn constraint(lin: LinComb<Bn128Field>) -> Statement<'static, Bn128Field> {
+        Statement::constraint(
+            QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+            lin,
+            None,
+        )
+    }
+
+    fn prog(statements: Vec<Statement<'static, Bn128Field>>) -> Prog<'static, Bn128Field> {
+        Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements,
+            ..Prog::default()
+        }
+    }
+
+    #[test]
+    fn no_semantic_change() {
+        let a = prog(vec![
+            constraint(Variable::public(0).into()),
+            constraint(Variable::new(1).into()),
+        ]);
+        // reordered, with spans, nested in a block and with redundant terms
+        let b = prog(vec![Statement::block(vec![
+            constraint(Variable::new(1).into()).span(Some(SourceSpan::mock().into())),
+            constraint(LinComb::new(vec![
+                (Variable::public(0), Bn128Field::from(1)),
+                (Variable::new(2), Bn128Field::from(1)),
+                (Variable::new(2), Bn128Field::from(-1)),
+            ])),
+        ])]);
+
+        let diff = diff_progs(&a, &b);
+        assert!(diff.is_empty());
+        assert_eq!(diff.constraint_count_delta, 0);
+    }
+
+    #[test]
+    fn constraints() {
+        let a = prog(vec![
+            constraint(Variable::public(0).into()),
+            constraint(Variable::public(0).into()),
+        ]);
+        let b = prog(vec![
+            constraint(Variable::public(0).into()),
+            constraint(Variable::new(1).into()),
+            constraint(Variable::new(2).into()),
+        ]);
+
+        let diff = diff_progs(&a, &b);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.constraint_count_delta, 1);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].lin, Variable::public(0).into());
+        assert_eq!(diff.added.len(), 2);
+        assert!(diff.parameters.is_empty());
+        assert_eq!(diff.return_count, None);
+    }
+
+    #[test]
+    fn return_count() {
+        let a = prog(vec![]);
+        let mut b = prog(vec![]);
+        b.return_count = 2;
+
+        let diff = diff_progs(&a, &b);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.return_count, Some((1, 2)));
+        assert_eq!(
+            diff.to_string(),
+            "constraints: +0 (0 added, 0 removed)\n~ outputs: 1 -> 2\n"
+        );
+    }
+
+    #[test]
+    fn parameters() {
+        let a = prog(vec![]);
+        let mut b = prog(vec![]);
+        b.arguments = vec![
+            Parameter::public(Variable::new(0)),
+            Parameter::private(Variable::new(1)),
+        ];
+
+        assert_eq!(
+            diff_progs(&a, &b).parameters,
+            vec![
+                ParameterChange::Visibility {
+                    id: Variable::new(0),
+                    from: Visibility::Private,
+                    to: Visibility::Public
+                },
+                ParameterChange::Added(Parameter::private(Variable::new(1)))
+            ]
+        );
+        assert_eq!(
+            diff_progs(&b, &a).parameters,
+            vec![
+                ParameterChange::Removed(Parameter::private(Variable::new(1))),
+                ParameterChange::Visibility {
+                    id: Variable::new(0),
+                    from: Visibility::Public,
+                    to: Visibility::Private
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use super::{Prog, Statement, Variable};
+use crate::common::{ModuleMap, Span, Visibility};
+use std::fmt::Write;
+use zokrates_field::Field;
+
+/// Numbers the statements of each kind while disassembling a program
+#[derive(Default)]
+struct Counters {
+    constraints: usize,
+    directives: usize,
+    logs: usize,
+}
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// a human-readable listing of this program, with one line per statement
+    ///
+    /// The listing starts with a header giving the curve and the counts of arguments, outputs, constraints, directives, logs and solvers,
+    /// followed by the arguments with their visibility, the statements and the returns.
+    /// Constraints, directives and logs are numbered separately, blocks are flattened, and spans are resolved through `map`.
+    /// Constraints are annotated with their origin when it is known.
+    /// The format is stable, so that listings can be compared in tests.
+    pub fn disassemble(&self, map: &ModuleMap) -> String {
+        let mut counters = Counters::default();
+        let mut statements = String::new();
+        for s in self.iter_statements() {
+            disassemble_statement(s, map, &mut counters, &mut statements);
+        }
+
+        let mut res = String::new();
+
+        // writing to a string cannot fail
+        writeln!(res, ".curve {}", T::name()).unwrap();
+        writeln!(res, ".arguments {}", self.arguments.len()).unwrap();
+        writeln!(res, ".outputs {}", self.return_count).unwrap();
+        writeln!(res, ".constraints {}", counters.constraints).unwrap();
+        writeln!(res, ".directives {}", counters.directives).unwrap();
+        writeln!(res, ".logs {}", counters.logs).unwrap();
+        writeln!(res, ".solvers {}", self.solvers.len()).unwrap();
+
+        writeln!(res).unwrap();
+        writeln!(res, "arguments:").unwrap();
+        for a in &self.arguments {
+            let visibility = match a.visibility {
+                Visibility::Public => "public",
+                Visibility::Private => "private",
+                Visibility::Committed => "committed",
+            };
+            writeln!(res, "\t{} {}{}", visibility, a.id, comment(a.span, map)).unwrap();
+        }
+
+        writeln!(res).unwrap();
+        writeln!(res, "statements:").unwrap();
+        res.push_str(&statements);
+
+        writeln!(res).unwrap();
+        writeln!(res, "returns:").unwrap();
+        if self.returns.is_empty() {
+            for i in 0..self.return_count {
+                writeln!(res, "\t{}", Variable::public(i)).unwrap();
+            }
+        } else {
+            let mut offset = 0;
+            for slot in &self.returns {
+                let outputs = (offset..offset + slot.count())
+                    .map(|i| Variable::public(i).to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                offset += slot.count();
+                writeln!(res, "\t{}: {}{}", slot.ty, outputs, comment(slot.span, map)).unwrap();
+            }
+        }
+
+        res
+    }
+}
+
+fn comment(span: Option<Span>, map: &ModuleMap) -> String {
+    span.map(|s| format!(" ; {}", s.display(map)))
+        .unwrap_or_default()
+}
+
+fn disassemble_statement<T: Field>(
+    s: &Statement<T>,
+    map: &ModuleMap,
+    counters: &mut Counters,
+    res: &mut String,
+) {
+    match s {
+        Statement::Block(b) => {
+            for s in &b.inner {
+                disassemble_statement(s, map, counters, res);
+            }
+        }
+        Statement::Constraint(c) => {
+            writeln!(
+                res,
+                "\tc{}: {}{}{}",
+                counters.constraints,
+                c,
+                c.origin.map(|o| format!(" [{}]", o)).unwrap_or_default(),
+                comment(c.span, map)
+            )
+            .unwrap();
+            counters.constraints += 1;
+        }
+        Statement::Directive(d) => {
+            writeln!(
+                res,
+                "\td{}: {}{}",
+                counters.directives,
+                d,
+                comment(d.span, map)
+            )
+            .unwrap();
+            counters.directives += 1;
+        }
+        Statement::Log(l) => {
+            writeln!(res, "\tl{}: {}{}", counters.logs, s, comment(l.span, map)).unwrap();
+            counters.logs += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{FlatEmbed, Origin, RefCall, SourceSpan, WithSpan};
+    use crate::ir::{ConstraintStatement, Parameter, QuadComb, ReturnSlot, Solver};
+    use crate::typed::ConcreteType;
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn listing() {
+        let mut map = ModuleMap::default();
+        let span = SourceSpan {
+            module: map.insert("main.zok".into()).unwrap(),
+            ..SourceSpan::mock()
+        };
+
+        let directive = Statement::directive(
+            vec![Variable::new(2)],
+            Solver::Ref(RefCall {
+                index: 0,
+                signature: (1, 1),
+            }),
+            vec![Variable::new(1).into()],
+        );
+        let constraint = Statement::constraint(
+            QuadComb::new(Variable::new(0).into(), Variable::new(1).into()),
+            Variable::public(0),
+            None,
+        );
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![
+                Parameter::public(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            return_count: 1,
+            returns: vec![ReturnSlot::new(ConcreteType::FieldElement)],
+            statements: vec![Statement::block(vec![
+                directive.clone(),
+                constraint.clone().span(Some(span.into())),
+            ])],
+            solvers: vec![Solver::Bits(1)],
+            ..Prog::default()
+        };
+
+        assert_eq!(
+            p.disassemble(&map),
+            format!(
+                ".curve bn128
+.arguments 2
+.outputs 1
+.constraints 1
+.directives 1
+.logs 0
+.solvers 1
+
+arguments:
+\tpublic _0
+\tprivate _1
+
+statements:
+\td0: {}
+\tc0: {} ; main.zok:42:42-42
+
+returns:
+\tfield: ~out_0
+",
+                directive, constraint
+            )
+        );
+    }
+
+    #[test]
+    fn origin() {
+        let constraint = ConstraintStatement::new(
+            QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+            Variable::new(0).into(),
+            None,
+        );
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            statements: vec![
+                Statement::Constraint(constraint.clone()),
+                Statement::Constraint(
+                    constraint
+                        .clone()
+                        .origin(Some(Origin::Embed(FlatEmbed::U8ToBits))),
+                ),
+                Statement::Constraint(constraint.clone().origin(Some(Origin::Optimizer))),
+            ],
+            ..Prog::default()
+        };
+
+        let listing = p.disassemble(&ModuleMap::default());
+        assert!(listing.contains(&format!("\tc0: {}\n", constraint)));
+        assert!(listing.contains(&format!("\tc1: {} [embed _U8_TO_BITS]\n", constraint)));
+        assert!(listing.contains(&format!("\tc2: {} [optimizer]\n", constraint)));
+
+        // origins survive serialization, and are not written when absent
+        let mut buffer = vec![];
+        serde_cbor::to_writer(&mut buffer, &constraint).unwrap();
+        let mut tagged = vec![];
+        serde_cbor::to_writer(
+            &mut tagged,
+            &constraint.clone().origin(Some(Origin::Optimizer)),
+        )
+        .unwrap();
+        assert!(buffer.len() < tagged.len());
+        let deserialized: ConstraintStatement<Bn128Field> =
+            serde_cbor::from_slice(&tagged).unwrap();
+        assert_eq!(deserialized.origin, Some(Origin::Optimizer));
+    }
+}
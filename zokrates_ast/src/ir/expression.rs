@@ -8,10 +8,15 @@ use std::ops::{Add, Div, Mul, Sub};
 use zokrates_field::Field;
 
 #[derive(Derivative)]
-#[derivative(PartialEq, Hash)]
+#[derivative(PartialEq, Hash, PartialOrd, Ord)]
 #[derive(Debug, Clone, Serialize, Deserialize, Eq)]
 pub struct QuadComb<T> {
-    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        Hash = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
     pub span: Option<Span>,
     pub left: LinComb<T>,
     pub right: LinComb<T>,
@@ -67,19 +72,29 @@ impl<T: Field> fmt::Display for QuadComb<T> {
 }
 
 #[derive(Derivative)]
-#[derivative(PartialEq, Hash)]
+#[derivative(PartialEq, Hash, PartialOrd, Ord)]
 #[derive(Clone, Debug, Serialize, Deserialize, Eq)]
 pub struct LinComb<T> {
-    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        Hash = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
     pub span: Option<Span>,
     pub value: Vec<(Variable, T)>,
 }
 
 #[derive(Derivative)]
-#[derivative(PartialEq, Hash)]
+#[derivative(PartialEq, Hash, PartialOrd, Ord)]
 #[derive(Clone, Debug, Serialize, Deserialize, Eq)]
 pub struct CanonicalLinComb<T> {
-    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        Hash = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
     pub span: Option<Span>,
     pub value: BTreeMap<Variable, T>,
 }
@@ -96,10 +111,15 @@ impl<T> WithSpan for CanonicalLinComb<T> {
 }
 
 #[derive(Derivative)]
-#[derivative(PartialEq, Hash)]
+#[derivative(PartialEq, Hash, PartialOrd, Ord)]
 #[derive(Clone, Debug, Serialize, Deserialize, Eq)]
 pub struct CanonicalQuadComb<T> {
-    #[derivative(PartialEq = "ignore", Hash = "ignore")]
+    #[derivative(
+        PartialEq = "ignore",
+        Hash = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
     span: Option<Span>,
     left: CanonicalLinComb<T>,
     right: CanonicalLinComb<T>,
@@ -76,10 +76,13 @@ pub fn fold_constraint_statement<'ast, T: Field, F: Folder<'ast, T>>(
     f: &mut F,
     s: ConstraintStatement<T>,
 ) -> Vec<Statement<'ast, T>> {
-    vec![Statement::constraint(
-        f.fold_quadratic_combination(s.quad),
-        f.fold_linear_combination(s.lin),
-        s.error,
+    vec![Statement::Constraint(
+        ConstraintStatement::new(
+            f.fold_quadratic_combination(s.quad),
+            f.fold_linear_combination(s.lin),
+            s.error,
+        )
+        .origin(s.origin),
     )]
 }
 
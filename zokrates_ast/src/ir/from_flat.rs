@@ -25,6 +25,8 @@ pub fn from_flat<'ast, T: Field, I: IntoIterator<Item = FlatStatement<'ast, T>>>
         statements: flat_prog_iterator.statements.into_iter().map(Into::into),
         arguments: flat_prog_iterator.arguments,
         return_count: flat_prog_iterator.return_count,
+        returns: vec![],
+        embeds_used: Default::default(),
         module_map: flat_prog_iterator.module_map,
         solvers: vec![],
     }
@@ -0,0 +1,171 @@
+use super::{LinComb, Parameter, Prog, QuadComb, Solver, Statement, Variable};
+use crate::common::Visibility;
+use arbitrary::{Arbitrary, Result, Unstructured};
+use num_bigint::BigUint;
+use zokrates_field::Field;
+
+/// The number of internal variables random programs draw from, so that their statements share variables
+const MAX_VARIABLES: usize = 64;
+/// The largest number of outputs of a random program
+const MAX_RETURN_COUNT: usize = 4;
+/// The largest number of arguments of a random program
+const MAX_ARGUMENTS: usize = 8;
+/// The largest number of terms of a random linear combination
+const MAX_TERMS: usize = 4;
+
+/// a field element read from `T::byte_width()` bytes of `u`, reduced modulo the modulus
+fn arbitrary_value<T: Field>(u: &mut Unstructured) -> Result<T> {
+    let bytes = (0..T::byte_width())
+        .map(|_| u.arbitrary())
+        .collect::<Result<Vec<u8>>>()?;
+    let modulus = T::max_value().to_biguint() + 1u32;
+
+    Ok(T::try_from(BigUint::from_bytes_le(&bytes) % modulus).unwrap())
+}
+
+/// one of the first `MAX_VARIABLES` internal variables
+fn arbitrary_internal_variable(u: &mut Unstructured) -> Result<Variable> {
+    Ok(Variable::new(u.int_in_range(0..=MAX_VARIABLES - 1)?))
+}
+
+/// a solver which can be evaluated without a solvers section, so that directives hold it directly
+fn arbitrary_solver<'ast, T>(u: &mut Unstructured) -> Result<Solver<'ast, T>> {
+    Ok(match u.int_in_range(0..=6)? {
+        0 => Solver::ConditionEq,
+        1 => Solver::Bits(u.int_in_range(1..=8)?),
+        2 => Solver::Div,
+        3 => Solver::Xor,
+        4 => Solver::Or,
+        5 => Solver::EuclideanDiv,
+        _ => Solver::ConditionalInverse,
+    })
+}
+
+/// `~one`, one of the first `MAX_VARIABLES` internal variables, or one of the first `MAX_RETURN_COUNT` outputs
+impl<'a> Arbitrary<'a> for Variable {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(
+            match u.int_in_range(0..=MAX_VARIABLES + MAX_RETURN_COUNT)? {
+                0 => Variable::one(),
+                i if i <= MAX_VARIABLES => Variable::new(i - 1),
+                i => Variable::public(i - MAX_VARIABLES - 1),
+            },
+        )
+    }
+}
+
+impl<'a> Arbitrary<'a> for Visibility {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=2)? {
+            0 => Visibility::Public,
+            1 => Visibility::Private,
+            _ => Visibility::Committed,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Parameter {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Parameter::new(
+            arbitrary_internal_variable(u)?,
+            u.arbitrary()?,
+        ))
+    }
+}
+
+impl<'a, T: Field> Arbitrary<'a> for LinComb<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let terms = u.int_in_range(0..=MAX_TERMS)?;
+
+        Ok(LinComb::new(
+            (0..terms)
+                .map(|_| Ok((u.arbitrary()?, arbitrary_value(u)?)))
+                .collect::<Result<_>>()?,
+        ))
+    }
+}
+
+impl<'a, T: Field> Arbitrary<'a> for QuadComb<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(QuadComb::new(u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+/// a constraint or a directive, with as many inputs and outputs as its solver expects
+/// Solvers defined by zir functions and references to a solvers section are not generated.
+impl<'a, 'ast, T: Field> Arbitrary<'a> for Statement<'ast, T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        // directives are rarer than constraints, as in compiled programs
+        match u.int_in_range(0..=3)? {
+            0 => {
+                let solver = arbitrary_solver(u)?;
+                let (inputs, outputs) = solver.get_signature();
+
+                let outputs = (0..outputs)
+                    .map(|_| arbitrary_internal_variable(u))
+                    .collect::<Result<_>>()?;
+                let inputs = (0..inputs).map(|_| u.arbitrary()).collect::<Result<_>>()?;
+
+                Ok(Statement::directive(outputs, solver, inputs))
+            }
+            _ => Ok(Statement::constraint(
+                u.arbitrary::<QuadComb<T>>()?,
+                u.arbitrary::<LinComb<T>>()?,
+                None,
+            )),
+        }
+    }
+}
+
+/// a program with distinct arguments and statements drawn from the first variables, see `Variable::arbitrary`
+impl<'a, 'ast, T: Field> Arbitrary<'a> for Prog<'ast, T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let arguments = u.int_in_range(0..=MAX_ARGUMENTS)?;
+
+        Ok(Prog {
+            arguments: (0..arguments)
+                .map(|i| Ok(Parameter::new(Variable::new(i), u.arbitrary()?)))
+                .collect::<Result<_>>()?,
+            return_count: u.int_in_range(0..=MAX_RETURN_COUNT)?,
+            statements: u.arbitrary()?,
+            ..Prog::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{ProgEnum, SerializeError};
+    use std::io::Cursor;
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn round_trip() {
+        // a deterministic stream of bytes, so that failures can be reproduced
+        let mut state: u64 = 42;
+        let bytes: Vec<u8> = (0..1 << 16)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect();
+
+        for chunk in bytes.chunks(1 << 10) {
+            let p: Prog<Bn128Field> = Unstructured::new(chunk).arbitrary().unwrap();
+
+            let mut buffer = Cursor::new(vec![]);
+            match p.clone().serialize(&mut buffer) {
+                // random programs often leave some private arguments unconstrained, but they are written regardless
+                Ok(_) | Err(SerializeError::UnconstrainedVariables { .. }) => {}
+                Err(e) => panic!("{}", e),
+            }
+
+            buffer.set_position(0);
+            let deserialized = ProgEnum::deserialize(buffer).unwrap().collect();
+            assert_eq!(deserialized, ProgEnum::Bn128Program(p));
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// The first two bytes of a gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A reader over a program which is either stored as is or gzip-compressed, see `read_maybe_gzip`
+pub enum MaybeGzip<R> {
+    Plain(R),
+    /// the decompressed bytes of a gzip-compressed program, buffered so that sections can be reached with `Seek`
+    Gzip(Cursor<Vec<u8>>),
+}
+
+/// sniff whether `r` holds a gzip-compressed program and, if so, decompress it in memory, so that the result can be passed to `ProgEnum::deserialize`
+/// Programs which are not compressed are read from `r` directly, starting at its current position.
+/// Decompressing requires the `gzip` feature, without which compressed programs are rejected.
+pub fn read_maybe_gzip<R: Read + Seek>(mut r: R) -> std::io::Result<MaybeGzip<R>> {
+    let start = r.stream_position()?;
+
+    let mut magic = [0; 2];
+    let is_gzip = match r.read_exact(&mut magic) {
+        Ok(()) => magic == GZIP_MAGIC,
+        // too short to be compressed, let the program reader report it
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+        Err(e) => return Err(e),
+    };
+
+    r.seek(SeekFrom::Start(start))?;
+
+    if !is_gzip {
+        return Ok(MaybeGzip::Plain(r));
+    }
+
+    decompress(r).map(MaybeGzip::Gzip)
+}
+
+#[cfg(feature = "gzip")]
+fn decompress<R: Read>(r: R) -> std::io::Result<Cursor<Vec<u8>>> {
+    let mut bytes = vec![];
+    flate2::read::MultiGzDecoder::new(r).read_to_end(&mut bytes)?;
+    Ok(Cursor::new(bytes))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress<R: Read>(_: R) -> std::io::Result<Cursor<Vec<u8>>> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "gzip-compressed programs can only be read with the `gzip` feature",
+    ))
+}
+
+impl<R: Read> Read for MaybeGzip<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            MaybeGzip::Plain(r) => r.read(buf),
+            MaybeGzip::Gzip(r) => r.read(buf),
+        }
+    }
+}
+
+impl<R: Seek> Seek for MaybeGzip<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match self {
+            MaybeGzip::Plain(r) => r.seek(pos),
+            MaybeGzip::Gzip(r) => r.seek(pos),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, Parameter, Prog, ProgEnum, QuadComb, Statement, Variable};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn plain() {
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                LinComb::from(Variable::public(0)),
+                None,
+            )],
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+        buffer.set_position(0);
+
+        let r = read_maybe_gzip(buffer).unwrap();
+        assert!(matches!(r, MaybeGzip::Plain(_)));
+        assert_eq!(
+            ProgEnum::deserialize(r).unwrap().collect(),
+            ProgEnum::Bn128Program(p)
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn gzip() {
+        use std::io::Write;
+
+        // enough repeated constraints for the gzip stream to span several deflate blocks
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: (0..10_000)
+                .map(|_| {
+                    Statement::constraint(
+                        QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                        LinComb::from(Variable::public(0)),
+                        None,
+                    )
+                })
+                .collect(),
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(buffer.get_ref()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let r = read_maybe_gzip(Cursor::new(compressed)).unwrap();
+        assert!(matches!(r, MaybeGzip::Gzip(_)));
+        assert_eq!(
+            ProgEnum::deserialize(r).unwrap().collect(),
+            ProgEnum::Bn128Program(p)
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use super::folder::{fold_constraint_statement, Folder};
+use super::{ConstraintStatement, LinComb, ProgIterator, Statement, Variable};
+use crate::common::WithSpan;
+use std::collections::HashMap;
+use zokrates_field::Field;
+
+/// Replaces some arguments of a program with constant values, specializing it to these values
+/// Constraints which only involve constants after the substitution are dropped if they hold, and kept otherwise so that the program still fails.
+pub struct InlineConstantInputs<T> {
+    values: HashMap<Variable, T>,
+}
+
+impl<T> InlineConstantInputs<T> {
+    pub fn new(values: HashMap<Variable, T>) -> Self {
+        Self { values }
+    }
+}
+
+impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
+    /// replace the arguments in `values` with their value everywhere in the program, including the inputs of directives, and remove them from the arguments
+    /// The outputs of a program are not arguments, so the number of outputs is unchanged.
+    /// Fails if a variable of `values` is not an argument of the program.
+    pub fn inline_constant_inputs(
+        self,
+        values: HashMap<Variable, T>,
+    ) -> Result<ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>>, String> {
+        if let Some(v) = values
+            .keys()
+            .find(|v| !self.arguments.iter().any(|a| a.id == **v))
+        {
+            return Err(format!(
+                "Variable `{}` is not an argument of the program",
+                v
+            ));
+        }
+
+        let mut inliner = InlineConstantInputs::new(values);
+
+        Ok(ProgIterator {
+            module_map: self.module_map,
+            arguments: self
+                .arguments
+                .into_iter()
+                .filter(|a| !inliner.values.contains_key(&a.id))
+                .collect(),
+            return_count: self.return_count,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
+            statements: self
+                .statements
+                .into_iter()
+                .flat_map(move |s| inliner.fold_statement(s)),
+            solvers: self.solvers,
+        })
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for InlineConstantInputs<T> {
+    fn fold_linear_combination(&mut self, e: LinComb<T>) -> LinComb<T> {
+        LinComb::new(
+            e.value
+                .into_iter()
+                .map(|(variable, coefficient)| match self.values.get(&variable) {
+                    Some(value) => (Variable::one(), coefficient * value),
+                    None => (variable, coefficient),
+                })
+                .collect(),
+        )
+        .span(e.span)
+        .reduce()
+    }
+
+    fn fold_constraint_statement(&mut self, s: ConstraintStatement<T>) -> Vec<Statement<'ast, T>> {
+        let folded = fold_constraint_statement(self, s);
+
+        let holds = match folded.as_slice() {
+            [Statement::Constraint(c)] => matches!(
+                (
+                    c.quad.left.clone().try_constant(),
+                    c.quad.right.clone().try_constant(),
+                    c.lin.clone().try_constant(),
+                ),
+                (Ok(left), Ok(right), Ok(lin)) if left * right == lin
+            ),
+            _ => false,
+        };
+
+        match holds {
+            true => vec![],
+            false => folded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::RefCall;
+    use crate::ir::{Parameter, Prog, QuadComb, Solver};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn inline() {
+        // def main(public _0, private _1, private _2) -> (~out_0)
+        // _1 * _2 == ~out_0
+        // # _3 = Ref@0(_1 * _1)
+        // _0 * _1 == 6
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![
+                Parameter::public(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+                Parameter::private(Variable::new(2)),
+            ],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(
+                    QuadComb::new(Variable::new(1).into(), Variable::new(2).into()),
+                    Variable::public(0),
+                    None,
+                ),
+                Statement::directive(
+                    vec![Variable::new(3)],
+                    Solver::Ref(RefCall {
+                        index: 0,
+                        signature: (1, 1),
+                    }),
+                    vec![QuadComb::new(
+                        Variable::new(1).into(),
+                        Variable::new(1).into(),
+                    )],
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(1).into()),
+                    LinComb::summand(6, Variable::one()),
+                    None,
+                ),
+            ],
+            solvers: vec![Solver::Bits(1)],
+            ..Prog::default()
+        };
+
+        let inlined = p
+            .inline_constant_inputs(
+                [
+                    (Variable::new(0), Bn128Field::from(2)),
+                    (Variable::new(1), Bn128Field::from(3)),
+                ]
+                .into_iter()
+                .collect(),
+            )
+            .unwrap()
+            .collect();
+
+        assert_eq!(
+            inlined.arguments,
+            vec![Parameter::private(Variable::new(2))]
+        );
+        assert_eq!(inlined.return_count, 1);
+        assert_eq!(
+            inlined.statements,
+            vec![
+                Statement::constraint(
+                    QuadComb::new(
+                        LinComb::summand(3, Variable::one()),
+                        Variable::new(2).into()
+                    ),
+                    Variable::public(0),
+                    None,
+                ),
+                Statement::directive(
+                    vec![Variable::new(3)],
+                    Solver::Ref(RefCall {
+                        index: 0,
+                        signature: (1, 1),
+                    }),
+                    vec![QuadComb::new(
+                        LinComb::summand(3, Variable::one()),
+                        LinComb::summand(3, Variable::one())
+                    )],
+                ),
+                // 2 * 3 == 6 always holds, so it is dropped
+            ]
+        );
+    }
+
+    #[test]
+    fn unsatisfied() {
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                LinComb::summand(2, Variable::one()),
+                None,
+            )],
+            ..Prog::default()
+        };
+
+        // 3 * 3 == 2 never holds, so it is kept
+        let inlined = p
+            .clone()
+            .inline_constant_inputs([(Variable::new(0), Bn128Field::from(3))].into())
+            .unwrap()
+            .collect();
+        assert_eq!(inlined.statements.len(), 1);
+
+        assert!(p
+            .inline_constant_inputs([(Variable::new(1), Bn128Field::from(3))].into())
+            .is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::ir::solver_indexer::SolverIndexer;
+
+use super::{Parameter, Prog, ProgIterator, ReturnSlot, Solver, Statement};
+use crate::common::{FlatEmbed, ModuleMap};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Write;
+use zokrates_field::Field;
+
+type DynamicError = Box<dyn std::error::Error>;
+
+/// The header of a json program, mirroring the fields of the binary `ProgHeader` which are useful to tooling
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JsonProgHeader {
+    pub curve: String,
+    pub constraint_count: usize,
+    pub return_count: usize,
+}
+
+/// A json representation of a program, with the same logical structure as the sections of the binary format
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonProg<'ast, T> {
+    header: JsonProgHeader,
+    parameters: Vec<Parameter>,
+    #[serde(borrow)]
+    constraints: Vec<Statement<'ast, T>>,
+    #[serde(borrow)]
+    solvers: Vec<Solver<'ast, T>>,
+    module_map: ModuleMap,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    returns: Vec<ReturnSlot>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    embeds_used: BTreeSet<FlatEmbed>,
+}
+
+impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
+    /// serialize a program iterator to a pretty-printed json document, returning the number of constraints serialized
+    /// Note that unlike the binary format, this buffers all statements in memory
+    pub fn serialize_json<W: Write>(self, w: W) -> Result<usize, DynamicError> {
+        use super::folder::Folder;
+
+        let mut solver_indexer: SolverIndexer<'ast, T> = SolverIndexer::default();
+
+        let constraints: Vec<_> = self
+            .statements
+            .into_iter()
+            .flat_map(|s| solver_indexer.fold_statement(s))
+            .collect();
+
+        if let Some(e) = solver_indexer.error.take() {
+            return Err(e.into());
+        }
+
+        let constraint_count = constraints
+            .iter()
+            .filter(|s| matches!(s, Statement::Constraint(..)))
+            .count();
+
+        let prog = JsonProg {
+            header: JsonProgHeader {
+                curve: T::name().to_string(),
+                constraint_count,
+                return_count: self.return_count,
+            },
+            parameters: self.arguments,
+            constraints,
+            solvers: solver_indexer.solvers,
+            module_map: self.module_map,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
+        };
+
+        serde_json::to_writer_pretty(w, &prog)?;
+
+        Ok(constraint_count)
+    }
+}
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// deserialize a program from a json document produced by `serialize_json`
+    pub fn from_json(s: &'ast str) -> Result<Self, DynamicError> {
+        let prog: JsonProg<'ast, T> = serde_json::from_str(s)?;
+
+        if prog.header.curve != T::name() {
+            return Err(format!(
+                "Expected a program over curve `{}`, found `{}`",
+                T::name(),
+                prog.header.curve
+            )
+            .into());
+        }
+
+        Ok(ProgIterator {
+            returns: prog.returns,
+            embeds_used: prog.embeds_used,
+            ..ProgIterator::new(
+                prog.parameters,
+                prog.constraints,
+                prog.header.return_count,
+                prog.module_map,
+                prog.solvers,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, QuadComb, Variable};
+    use zokrates_field::{Bls12_381Field, Bn128Field};
+
+    #[test]
+    fn ser_deser_json() {
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                LinComb::from(Variable::public(0)),
+                None,
+            )],
+            ..Prog::default()
+        };
+
+        let mut buffer = vec![];
+        let count = p.clone().serialize_json(&mut buffer).unwrap();
+        assert_eq!(count, 1);
+
+        let json = String::from_utf8(buffer).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["header"]["constraint_count"], 1);
+        assert_eq!(value["header"]["return_count"], 1);
+
+        let deserialized_p = Prog::<Bn128Field>::from_json(&json).unwrap();
+        assert_eq!(p, deserialized_p);
+    }
+
+    #[test]
+    fn curve_mismatch() {
+        let p: Prog<Bn128Field> = Prog::default();
+
+        let mut buffer = vec![];
+        p.serialize_json(&mut buffer).unwrap();
+
+        let json = String::from_utf8(buffer).unwrap();
+        assert!(Prog::<Bls12_381Field>::from_json(&json).is_err());
+    }
+}
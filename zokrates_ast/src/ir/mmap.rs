@@ -0,0 +1,108 @@
+use super::serialize::{check_header, UnwrappedStreamDeserializer};
+use super::{ProgHeader, ProgIterator, Statement};
+use memmap2::Mmap;
+use serde_cbor::de::SliceRead;
+use std::fs::File;
+use std::io::Cursor;
+use zokrates_field::Field;
+
+/// A serialized program mapped in memory
+/// Statements are deserialized from the mapped bytes directly, which lets the OS page cache serve them without copies.
+pub struct MappedProg {
+    mmap: Mmap,
+}
+
+impl MappedProg {
+    /// map `file` in memory
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, see `memmap2::Mmap::map`
+    pub unsafe fn map(file: &File) -> std::io::Result<Self> {
+        Ok(Self {
+            mmap: Mmap::map(file)?,
+        })
+    }
+
+    /// read and check the header of the program
+    pub fn header(&self) -> Result<ProgHeader, String> {
+        let header = ProgHeader::read(Cursor::new(&self.mmap[..]))
+            .map_err(|_| String::from("Invalid header"))?;
+
+        check_header(&header)?;
+
+        Ok(header)
+    }
+
+    /// read a program over `T`, whose statements are deserialized lazily from the mapped bytes, see `ProgIterator::from_slice`
+    /// Compressed and chunked constraints sections are not supported, as they cannot be read in place
+    pub fn read<T: Field>(
+        &self,
+    ) -> Result<
+        ProgIterator<'_, T, UnwrappedStreamDeserializer<'_, SliceRead<'_>, Statement<'_, T>>>,
+        String,
+    > {
+        ProgIterator::from_slice(&self.mmap[..]).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, Parameter, Prog, QuadComb, Variable};
+    use zokrates_field::{Bls12_381Field, Bn128Field};
+
+    fn mapped(name: &str, p: &Prog<Bn128Field>) -> MappedProg {
+        let path = std::env::temp_dir().join(name);
+        p.clone().serialize(File::create(&path).unwrap()).unwrap();
+
+        let file = File::open(&path).unwrap();
+        // the file is not modified while it is mapped
+        unsafe { MappedProg::map(&file) }.unwrap()
+    }
+
+    #[test]
+    fn read() {
+        // `_0 * _0 == _1`, `_1 * _1 == ~out_0`
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                    LinComb::from(Variable::new(1)),
+                    None,
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(1).into(), Variable::new(1).into()),
+                    LinComb::from(Variable::public(0)),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        };
+
+        let mapped = mapped("zokrates_mmap_read.bin", &p);
+
+        assert_eq!(mapped.header().unwrap().constraint_count, 2);
+        assert_eq!(mapped.read::<Bn128Field>().unwrap().collect(), p);
+    }
+
+    #[test]
+    fn curve_mismatch() {
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                LinComb::from(Variable::public(0)),
+                None,
+            )],
+            ..Prog::default()
+        };
+
+        let mapped = mapped("zokrates_mmap_curve_mismatch.bin", &p);
+
+        assert!(mapped.read::<Bls12_381Field>().is_err());
+    }
+}
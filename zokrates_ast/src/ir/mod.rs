@@ -1,25 +1,87 @@
-use crate::common::{FormatString, ModuleMap, Span, WithSpan};
+use crate::common::{FlatEmbed, FormatString, ModuleMap, Origin, Span, Visibility, WithSpan};
 use crate::typed::ConcreteType;
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use zokrates_field::Field;
 
+mod archive;
+mod builder;
+mod canonicalize;
+mod chain;
 mod check;
+mod chunked;
 mod clean;
+mod compact;
+mod content_hash;
+mod convert;
+mod cse;
+mod dedupe;
+mod diff;
+mod disassemble;
 mod expression;
 pub mod folder;
 pub mod from_flat;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
+mod gzip;
+mod inline;
+mod json;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod prune;
+mod recover;
+mod remap_spans;
 mod serialize;
+mod slice;
 pub mod smtlib2;
 mod solver_indexer;
+mod solver_usage;
+mod strip_spans;
+mod template;
+mod trivial;
+mod variables;
 pub mod visitor;
 mod witness;
 
+pub use self::archive::{Archive, ArchiveEntry};
+pub use self::builder::ProgBuilder;
+pub use self::canonicalize::Canonicalizer;
+pub use self::check::{
+    detect_unconstrained, find_unsatisfiable, ConstraintFailure, UnconstrainedVariable,
+};
+pub use self::chunked::{ChunkReader, ChunkedProgWriter};
+pub use self::compact::CompactVariables;
+pub use self::convert::ConversionError;
+pub use self::cse::HoistCommonSubexpressions;
+pub use self::dedupe::DedupeConstraints;
+pub use self::diff::{diff_progs, ParameterChange, ProgDiff};
 pub use self::expression::QuadComb;
 pub use self::expression::{CanonicalLinComb, LinComb};
-pub use self::serialize::{ProgEnum, ProgHeader};
+pub use self::gzip::{read_maybe_gzip, MaybeGzip};
+pub use self::inline::InlineConstantInputs;
+pub use self::json::JsonProgHeader;
+#[cfg(feature = "mmap")]
+pub use self::mmap::MappedProg;
+#[cfg(feature = "parallel")]
+pub use self::parallel::read_statements_parallel;
+pub use self::prune::PruneUnusedSolvers;
+pub use self::recover::{PartialRead, PartialReadStatus};
+pub use self::remap_spans::RemapSpans;
+pub use self::serialize::{
+    is_compatible_version, read_parameters, read_section_bytes, ProgAppender, ProgEnum, ProgHeader,
+    ProgSummary, ProgressEvent, ReadLimits, Section, SectionType, SerializeError, SerializeOptions,
+    UnconstrainedPolicy, CURRENT_FILE_VERSION,
+};
+pub use self::solver_usage::SolverUsage;
+pub use self::strip_spans::StripSpans;
+pub use self::template::generate_input_template;
+pub use self::trivial::RemoveTrivialConstraints;
+pub use self::variables::{collect_variables, collect_variables_prog};
 pub use crate::common::flat::Parameter;
 pub use crate::common::flat::Variable;
 pub use crate::common::RuntimeError;
@@ -32,14 +94,34 @@ pub type DirectiveStatement<'ast, T> =
     crate::common::statements::DirectiveStatement<QuadComb<T>, Variable, Solver<'ast, T>>;
 
 #[derive(Derivative, Clone, Debug, Serialize, Deserialize)]
-#[derivative(Hash, PartialEq, Eq)]
+#[derivative(Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ConstraintStatement<T> {
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
+    #[derivative(
+        Hash = "ignore",
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
     pub span: Option<Span>,
     pub quad: QuadComb<T>,
     pub lin: LinComb<T>,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
+    #[derivative(
+        Hash = "ignore",
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
     pub error: Option<RuntimeError>,
+    /// where this constraint comes from, when it was recorded
+    /// Absent origins are not serialized, so that programs which do not record them are not larger.
+    #[derivative(
+        Hash = "ignore",
+        PartialEq = "ignore",
+        PartialOrd = "ignore",
+        Ord = "ignore"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Origin>,
 }
 
 impl<T> ConstraintStatement<T> {
@@ -49,8 +131,14 @@ impl<T> ConstraintStatement<T> {
             quad,
             lin,
             error,
+            origin: None,
         }
     }
+
+    pub fn origin(mut self, origin: Option<Origin>) -> Self {
+        self.origin = origin;
+        self
+    }
 }
 
 impl<T> WithSpan for ConstraintStatement<T> {
@@ -105,6 +193,19 @@ impl<'ast, T> WithSpan for BlockStatement<'ast, T> {
     }
 }
 
+// blocks are only ordered over fields, like the solvers they may contain
+impl<'ast, T: Field> PartialOrd for BlockStatement<'ast, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'ast, T: Field> Ord for BlockStatement<'ast, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.cmp(&other.inner)
+    }
+}
+
 impl<'ast, T: Field> fmt::Display for BlockStatement<'ast, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "{{")?;
@@ -129,6 +230,84 @@ pub enum Statement<'ast, T> {
 
 pub type PublicInputs = BTreeSet<Variable>;
 
+/// Where an element of the public input vector of a program comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicSource {
+    /// the argument of the program at this position, counting private arguments
+    Argument(usize),
+    /// the output of the program at this position
+    Output(usize),
+}
+
+/// An element of the public input vector of a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicEntry {
+    /// the position of this element in the public input vector
+    pub index: usize,
+    pub variable: Variable,
+    pub source: PublicSource,
+}
+
+/// The layout of the public input vector of a program, which a verifier must follow to check a proof of it
+/// See `ProgIterator::public_io_layout`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PublicLayout {
+    pub entries: Vec<PublicEntry>,
+}
+
+impl PublicLayout {
+    /// the number of elements of the public input vector
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The role of a variable in a program, see `VariableRoles`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableRole {
+    /// the implicit `~one` variable, whose value is always 1
+    One,
+    /// an argument of the program, with its visibility
+    Input(Visibility),
+    /// an output of the program
+    Output,
+    /// a variable introduced by the program
+    Internal,
+}
+
+/// An index of the arguments and outputs of a program, classifying its variables in constant time
+/// See `ProgIterator::variable_roles`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VariableRoles {
+    inputs: HashMap<Variable, Visibility>,
+    return_count: usize,
+}
+
+impl VariableRoles {
+    /// the role of `v`, which is `Internal` for any variable which is neither `~one`, an argument nor an output, including variables the program does not use
+    pub fn classify_variable(&self, v: Variable) -> VariableRole {
+        if v == Variable::one() {
+            return VariableRole::One;
+        }
+
+        if v.id < 0 {
+            return match ((-v.id - 1) as usize) < self.return_count {
+                true => VariableRole::Output,
+                false => VariableRole::Internal,
+            };
+        }
+
+        match self.inputs.get(&v) {
+            Some(visibility) => VariableRole::Input(*visibility),
+            None => VariableRole::Internal,
+        }
+    }
+}
+
 impl<'ast, T> WithSpan for Statement<'ast, T> {
     fn span(self, span: Option<Span>) -> Self {
         match self {
@@ -149,6 +328,36 @@ impl<'ast, T> WithSpan for Statement<'ast, T> {
     }
 }
 
+impl<'ast, T> Statement<'ast, T> {
+    fn variant_index(&self) -> usize {
+        match self {
+            Statement::Block(_) => 0,
+            Statement::Constraint(_) => 1,
+            Statement::Directive(_) => 2,
+            Statement::Log(_) => 3,
+        }
+    }
+}
+
+impl<'ast, T: Field> PartialOrd for Statement<'ast, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Statements are ordered regardless of their spans, consistently with `PartialEq`
+impl<'ast, T: Field> Ord for Statement<'ast, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Statement::Block(a), Statement::Block(b)) => a.cmp(b),
+            (Statement::Constraint(a), Statement::Constraint(b)) => a.cmp(b),
+            (Statement::Directive(a), Statement::Directive(b)) => a.cmp(b),
+            (Statement::Log(a), Statement::Log(b)) => a.cmp(b),
+            _ => self.variant_index().cmp(&other.variant_index()),
+        }
+    }
+}
+
 impl<'ast, T: Field> Statement<'ast, T> {
     pub fn definition<U: Into<QuadComb<T>>>(v: Variable, e: U) -> Self {
         Statement::constraint(e, v, None)
@@ -208,11 +417,35 @@ impl<'ast, T: Field> fmt::Display for Statement<'ast, T> {
     }
 }
 
+/// A group of consecutive outputs of a program, corresponding to one value returned in the source
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReturnSlot {
+    pub ty: ConcreteType,
+    pub span: Option<Span>,
+}
+
+impl ReturnSlot {
+    pub fn new(ty: ConcreteType) -> Self {
+        Self { ty, span: None }
+    }
+
+    /// the number of outputs in this slot
+    pub fn count(&self) -> usize {
+        self.ty.get_primitive_count()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct ProgIterator<'ast, T, I: IntoIterator<Item = Statement<'ast, T>>> {
     pub module_map: ModuleMap,
     pub arguments: Vec<Parameter>,
     pub return_count: usize,
+    /// the grouping of the outputs in the source, empty if unknown, see `with_returns`
+    #[serde(default)]
+    pub returns: Vec<ReturnSlot>,
+    /// the embeds which were inlined when compiling this program, empty if unknown
+    #[serde(default)]
+    pub embeds_used: BTreeSet<FlatEmbed>,
     pub statements: I,
     #[serde(borrow)]
     pub solvers: Vec<Solver<'ast, T>>,
@@ -226,6 +459,8 @@ impl<'ast, T> Default for Prog<'ast, T> {
             module_map: Default::default(),
             arguments: Default::default(),
             return_count: Default::default(),
+            returns: Default::default(),
+            embeds_used: Default::default(),
             statements: Default::default(),
             solvers: Default::default(),
         }
@@ -243,17 +478,96 @@ impl<'ast, T, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T,
         Self {
             arguments,
             return_count,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements,
             module_map,
             solvers,
         }
     }
 
+    /// attach `returns` to this program, deriving its number of outputs from them
+    pub fn with_returns(self, returns: Vec<ReturnSlot>) -> Self {
+        Self {
+            return_count: returns.iter().map(ReturnSlot::count).sum(),
+            returns,
+            ..self
+        }
+    }
+
+    /// split the values of the outputs of this program along its return slots
+    /// Returns `None` if the program has no return slots or if the number of values does not match
+    pub fn group_returns<'a, V>(&self, values: &'a [V]) -> Option<Vec<(&ReturnSlot, &'a [V])>> {
+        if self.returns.is_empty() || values.len() != self.return_count {
+            return None;
+        }
+
+        let mut rest = values;
+        Some(
+            self.returns
+                .iter()
+                .map(|slot| {
+                    let (group, tail) = rest.split_at(slot.count());
+                    rest = tail;
+                    (slot, group)
+                })
+                .collect(),
+        )
+    }
+
     pub fn collect(self) -> Prog<'ast, T> {
         ProgIterator {
             statements: self.statements.into_iter().collect(),
             arguments: self.arguments,
             return_count: self.return_count,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
+            module_map: self.module_map,
+            solvers: self.solvers,
+        }
+    }
+
+    /// skip the statements of this program preceding constraint `n`, counting top-level constraints from 0
+    /// The statements are read through, see `ProgEnum::try_read_from_constraint` to use the constraint index of a serialized program
+    pub fn seek_to_constraint(
+        self,
+        n: usize,
+    ) -> ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>> {
+        let mut seen = 0;
+
+        ProgIterator {
+            statements: self.statements.into_iter().skip_while(move |s| {
+                if matches!(s, Statement::Constraint(..)) {
+                    if seen == n {
+                        return false;
+                    }
+                    seen += 1;
+                }
+                true
+            }),
+            arguments: self.arguments,
+            return_count: self.return_count,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
+            module_map: self.module_map,
+            solvers: self.solvers,
+        }
+    }
+
+    /// keep only the constraints of this program, skipping directives and log statements
+    /// The resulting program is suitable for analysis, but not for witness computation
+    pub fn constraints_only(
+        self,
+    ) -> ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>> {
+        ProgIterator {
+            statements: self
+                .statements
+                .into_iter()
+                .filter(|s| matches!(s, Statement::Constraint(..))),
+            arguments: self.arguments,
+            return_count: self.return_count,
+            returns: self.returns,
+            embeds_used: self.embeds_used,
             module_map: self.module_map,
             solvers: self.solvers,
         }
@@ -264,24 +578,69 @@ impl<'ast, T, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T,
     }
 
     pub fn public_count(&self) -> usize {
-        self.arguments.iter().filter(|a| !a.private).count() + self.return_count
+        self.arguments
+            .iter()
+            .filter(|a| a.visibility.is_public())
+            .count()
+            + self.return_count
     }
 
     pub fn public_inputs(&self) -> PublicInputs {
         self.arguments
             .iter()
-            .filter(|a| !a.private)
+            .filter(|a| a.visibility.is_public())
             .map(|a| a.id)
             .collect()
     }
 
+    /// the layout of the public input vector of this program, in the order used by `public_inputs_values` and the backends
+    ///
+    /// The public arguments come first, in the order they are declared in, followed by all outputs `~out_0`, `~out_1`, ... in order.
+    /// Private arguments are skipped, but still count in the position of an argument in `PublicSource::Argument`.
+    /// The implicit `~one` variable is not part of the layout.
+    pub fn public_io_layout(&self) -> PublicLayout {
+        let arguments = self
+            .arguments
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| a.visibility.is_public())
+            .map(|(position, a)| (a.id, PublicSource::Argument(position)));
+
+        let outputs = (0..self.return_count)
+            .map(|position| (Variable::public(position), PublicSource::Output(position)));
+
+        PublicLayout {
+            entries: arguments
+                .chain(outputs)
+                .enumerate()
+                .map(|(index, (variable, source))| PublicEntry {
+                    index,
+                    variable,
+                    source,
+                })
+                .collect(),
+        }
+    }
+
+    /// an index of the arguments and outputs of this program, to classify its variables in constant time
+    pub fn variable_roles(&self) -> VariableRoles {
+        VariableRoles {
+            inputs: self
+                .arguments
+                .iter()
+                .map(|a| (a.id, a.visibility))
+                .collect(),
+            return_count: self.return_count,
+        }
+    }
+
     pub fn public_inputs_values(&self, witness: &Witness<T>) -> Vec<T>
     where
         T: Field,
     {
         self.arguments
             .iter()
-            .filter(|p| !p.private)
+            .filter(|p| p.visibility.is_public())
             .map(|p| *witness.0.get(&p.id).unwrap())
             .chain(witness.return_values())
             .collect()
@@ -289,9 +648,13 @@ impl<'ast, T, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T,
 }
 
 impl<'ast, T> Prog<'ast, T> {
+    /// borrow the top-level statements of this program, so that several passes can run without cloning or consuming it
+    pub fn iter_statements(&self) -> impl Iterator<Item = &Statement<'ast, T>> {
+        self.statements.iter()
+    }
+
     pub fn constraint_count(&self) -> usize {
-        self.statements
-            .iter()
+        self.iter_statements()
             .filter(|s| matches!(s, Statement::Constraint(..)))
             .count()
     }
@@ -327,6 +690,7 @@ impl<'ast, T: Field> fmt::Display for Prog<'ast, T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::SourceSpan;
     use zokrates_field::Bn128Field;
 
     mod statement {
@@ -341,5 +705,196 @@ mod tests {
             );
             assert_eq!(format!("{}", c), "(1 * _42) * (1 * _42) == 1 * _42")
         }
+
+        #[test]
+        fn ord_ignores_span() {
+            let c: Statement<Bn128Field> = Statement::constraint(
+                QuadComb::new(Variable::new(42).into(), Variable::new(42).into()),
+                Variable::new(42),
+                None,
+            );
+            let d = c.clone().span(Some(SourceSpan::mock().into()));
+            let e: Statement<Bn128Field> =
+                Statement::definition(Variable::new(43), Variable::new(42));
+
+            assert_eq!(c.cmp(&d), Ordering::Equal);
+            assert_ne!(c.cmp(&e), Ordering::Equal);
+
+            let set: BTreeSet<_> = vec![c, d, e].into_iter().collect();
+            assert_eq!(set.len(), 2);
+        }
+    }
+
+    mod prog {
+        use super::*;
+
+        #[test]
+        fn classify_variable() {
+            let p: Prog<Bn128Field> = Prog {
+                arguments: vec![
+                    Parameter::private(Variable::new(0)),
+                    Parameter::public(Variable::new(1)),
+                ],
+                return_count: 1,
+                ..Prog::default()
+            };
+
+            let roles = p.variable_roles();
+
+            assert_eq!(roles.classify_variable(Variable::one()), VariableRole::One);
+            assert_eq!(
+                roles.classify_variable(Variable::new(0)),
+                VariableRole::Input(Visibility::Private)
+            );
+            assert_eq!(
+                roles.classify_variable(Variable::new(1)),
+                VariableRole::Input(Visibility::Public)
+            );
+            assert_eq!(
+                roles.classify_variable(Variable::public(0)),
+                VariableRole::Output
+            );
+            assert_eq!(
+                roles.classify_variable(Variable::public(1)),
+                VariableRole::Internal
+            );
+            assert_eq!(
+                roles.classify_variable(Variable::new(2)),
+                VariableRole::Internal
+            );
+        }
+
+        #[test]
+        fn public_io_layout() {
+            let p: Prog<Bn128Field> = Prog {
+                arguments: vec![
+                    Parameter::private(Variable::new(0)),
+                    Parameter::public(Variable::new(1)),
+                    Parameter::public(Variable::new(2)),
+                ],
+                return_count: 2,
+                ..Prog::default()
+            };
+
+            let layout = p.public_io_layout();
+
+            assert_eq!(layout.len(), p.public_count());
+            assert_eq!(
+                layout.entries,
+                vec![
+                    PublicEntry {
+                        index: 0,
+                        variable: Variable::new(1),
+                        source: PublicSource::Argument(1),
+                    },
+                    PublicEntry {
+                        index: 1,
+                        variable: Variable::new(2),
+                        source: PublicSource::Argument(2),
+                    },
+                    PublicEntry {
+                        index: 2,
+                        variable: Variable::public(0),
+                        source: PublicSource::Output(0),
+                    },
+                    PublicEntry {
+                        index: 3,
+                        variable: Variable::public(1),
+                        source: PublicSource::Output(1),
+                    },
+                ]
+            );
+
+            let witness = Witness(
+                vec![
+                    (Variable::new(0), Bn128Field::from(7)),
+                    (Variable::new(1), Bn128Field::from(1)),
+                    (Variable::new(2), Bn128Field::from(2)),
+                    (Variable::public(0), Bn128Field::from(3)),
+                    (Variable::public(1), Bn128Field::from(4)),
+                ]
+                .into_iter()
+                .collect(),
+            );
+            assert_eq!(
+                p.public_inputs_values(&witness),
+                (1..=4u32).map(Bn128Field::from).collect::<Vec<_>>()
+            );
+        }
+
+        #[test]
+        fn constraints_only() {
+            let constraint = Statement::constraint(
+                QuadComb::new(Variable::new(1).into(), Variable::new(1).into()),
+                Variable::new(1),
+                None,
+            );
+
+            let p: Prog<Bn128Field> = Prog {
+                arguments: vec![Parameter::private(Variable::new(0))],
+                statements: vec![
+                    Statement::Directive(DirectiveStatement::new(
+                        vec![Variable::new(1)],
+                        Solver::Bits(1),
+                        vec![Variable::new(0).into()],
+                    )),
+                    constraint.clone(),
+                ],
+                ..Prog::default()
+            };
+
+            let p = p.constraints_only().collect();
+
+            assert_eq!(p.arguments, vec![Parameter::private(Variable::new(0))]);
+            assert_eq!(p.statements, vec![constraint]);
+        }
+
+        #[test]
+        fn iter_statements() {
+            let p: Prog<Bn128Field> = Prog {
+                statements: vec![
+                    Statement::definition(Variable::new(0), Variable::one()),
+                    Statement::block(vec![Statement::definition(
+                        Variable::new(1),
+                        Variable::new(0),
+                    )]),
+                ],
+                ..Prog::default()
+            };
+
+            assert_eq!(p.iter_statements().count(), 2);
+            assert_eq!(
+                p.iter_statements().cloned().collect::<Vec<_>>(),
+                p.statements
+            );
+            assert_eq!(p.constraint_count(), 1);
+        }
+
+        #[test]
+        fn group_returns() {
+            use crate::typed::{ConcreteTupleType, GArrayType};
+
+            let p: Prog<Bn128Field> = Prog::default().with_returns(vec![
+                ReturnSlot::new(ConcreteType::Boolean),
+                ReturnSlot::new(ConcreteType::Array(GArrayType::new(
+                    ConcreteType::FieldElement,
+                    2u32,
+                ))),
+                ReturnSlot::new(ConcreteType::Tuple(ConcreteTupleType::new(vec![]))),
+            ]);
+
+            assert_eq!(p.return_count, 3);
+
+            let groups = p.group_returns(&[1, 2, 3]).unwrap();
+            assert_eq!(
+                groups.iter().map(|(_, v)| *v).collect::<Vec<_>>(),
+                vec![&[1][..], &[2, 3][..], &[][..]]
+            );
+
+            assert!(p.group_returns(&[1, 2]).is_none());
+            assert!(Prog::<Bn128Field>::default()
+                .group_returns(&[0; 0])
+                .is_none());
+        }
     }
 }
@@ -0,0 +1,210 @@
+use super::serialize::check_header;
+use super::{Prog, ProgEnum, ProgHeader, ReadLimits, Statement};
+use rayon::prelude::*;
+use serde::de::{Error, IgnoredAny};
+use std::io::Cursor;
+use zokrates_field::Field;
+
+/// The number of chunks given to each thread, so that threads which finish early can pick up more work
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// find the offsets at which the statements in `bytes` start, followed by the length of `bytes`
+/// Skipping a CBOR item is much cheaper than deserializing it, so this sequential pass is not the bottleneck
+/// Fails once there are more than `max_statements` statements, before allocating for the rest of them.
+fn statement_boundaries(
+    bytes: &[u8],
+    max_statements: u64,
+) -> Result<Vec<usize>, serde_cbor::Error> {
+    let mut boundaries = vec![0];
+    let mut s = serde_cbor::Deserializer::from_slice(bytes).into_iter::<IgnoredAny>();
+
+    while let Some(item) = s.next() {
+        item?;
+
+        if boundaries.len() as u64 > max_statements {
+            return Err(serde_cbor::Error::custom(format!(
+                "Program has more than {} statements, which exceeds the limit",
+                max_statements
+            )));
+        }
+
+        boundaries.push(s.byte_offset());
+    }
+
+    Ok(boundaries)
+}
+
+/// deserialize the statements of a constraints section on the rayon thread pool, preserving their order
+/// Statements exceeding `limits` are rejected, see `ReadLimits::check_statement`.
+pub fn read_statements_parallel<'ast, T: Field>(
+    bytes: &'ast [u8],
+    limits: &ReadLimits,
+) -> Result<Vec<Statement<'ast, T>>, serde_cbor::Error> {
+    let boundaries = statement_boundaries(bytes, limits.max_statements)?;
+    let statement_count = boundaries.len() - 1;
+
+    let chunk_count = rayon::current_num_threads() * CHUNKS_PER_THREAD;
+    let chunk_size = (statement_count / chunk_count).max(1);
+
+    let chunks: Vec<(usize, usize, usize)> = (0..statement_count)
+        .step_by(chunk_size)
+        .map(|start| {
+            let end = (start + chunk_size).min(statement_count);
+            (start, boundaries[start], boundaries[end])
+        })
+        .collect();
+
+    let statements = chunks
+        .into_par_iter()
+        .map(|(start, from, to)| {
+            serde_cbor::Deserializer::from_slice(&bytes[from..to])
+                .into_iter::<Statement<'ast, T>>()
+                .zip(start as u64 + 1..)
+                .map(|(statement, n)| -> Result<_, serde_cbor::Error> {
+                    let statement = statement?;
+                    limits
+                        .check_statement(n, &statement)
+                        .map_err(serde_cbor::Error::custom)?;
+                    Ok(statement)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(statements.into_iter().flatten().collect())
+}
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// read a serialized program from memory, deserializing its constraints section in parallel
+    /// Compressed constraints sections are not supported, as they cannot be split without being decompressed first
+    pub fn read_parallel(bytes: &'ast [u8]) -> Result<Self, String> {
+        Self::read_parallel_with_limits(bytes, &ReadLimits::default())
+    }
+
+    /// read a serialized program from memory like `read_parallel`, rejecting it if it exceeds `limits`
+    pub fn read_parallel_with_limits(
+        bytes: &'ast [u8],
+        limits: &ReadLimits,
+    ) -> Result<Self, String> {
+        let mut r = Cursor::new(bytes);
+
+        let header = ProgHeader::read(&mut r).map_err(|_| String::from("Invalid header"))?;
+
+        check_header(&header)?;
+
+        if header.is_compressed() {
+            return Err(String::from(
+                "Compressed programs cannot be read in parallel",
+            ));
+        }
+
+        if header.is_chunked() {
+            return Err(String::from("Chunked programs cannot be read in parallel"));
+        }
+
+        header.verify(&mut r)?;
+
+        let prog = ProgEnum::try_read_with_limits::<T>(&mut r, &header, limits)?;
+
+        let section = &header.sections[1];
+        let bytes = &bytes[section.offset as usize..(section.offset + section.length) as usize];
+
+        let statements = read_statements_parallel(bytes, limits)
+            .map_err(|e| format!("Cannot read constraints: {}", e))?;
+
+        Ok(Prog::new(
+            prog.arguments,
+            statements,
+            prog.return_count,
+            prog.module_map,
+            prog.solvers,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, Parameter, QuadComb, Variable};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn read_parallel() {
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: (0..1000)
+                .map(|i| {
+                    Statement::constraint(
+                        QuadComb::new(Variable::new(i).into(), Variable::new(i).into()),
+                        LinComb::from(Variable::new(i + 1)),
+                        None,
+                    )
+                })
+                .chain(std::iter::once(Statement::constraint(
+                    Variable::new(1000),
+                    Variable::public(0),
+                    None,
+                )))
+                .collect(),
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        assert_eq!(Prog::<Bn128Field>::read_parallel(&bytes).unwrap(), p);
+    }
+
+    #[test]
+    fn limits() {
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: (0..10)
+                .map(|i| {
+                    Statement::constraint(
+                        QuadComb::new(Variable::new(i).into(), Variable::new(i).into()),
+                        LinComb::from(Variable::new(i + 1)),
+                        None,
+                    )
+                })
+                .chain(std::iter::once(Statement::constraint(
+                    Variable::new(10),
+                    Variable::public(0),
+                    None,
+                )))
+                .collect(),
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        let limits = ReadLimits {
+            max_statements: 10,
+            ..ReadLimits::default()
+        };
+        let error = Prog::<Bn128Field>::read_parallel_with_limits(&bytes, &limits).unwrap_err();
+        assert!(error.contains("more than 10 statements"));
+
+        let limits = ReadLimits {
+            max_statements: 11,
+            ..ReadLimits::default()
+        };
+        assert!(Prog::<Bn128Field>::read_parallel_with_limits(&bytes, &limits).is_ok());
+    }
+
+    #[test]
+    fn empty() {
+        let p: Prog<Bn128Field> = Prog::default();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        assert_eq!(Prog::<Bn128Field>::read_parallel(&bytes).unwrap(), p);
+    }
+}
@@ -0,0 +1,118 @@
+use super::folder::{fold_directive_statement, Folder};
+use super::{DirectiveStatement, Prog, Solver, Statement};
+use crate::common::RefCall;
+use zokrates_field::Field;
+
+/// Removes the solvers which no directive refers to, and renumbers the references to the remaining ones
+/// Solvers are indexed as directives are serialized, so they only linger when a pass removes directives from a program which was read back, see `Prog::solver_usage`.
+/// This should run after all other passes, right before the program is serialized.
+pub struct PruneUnusedSolvers {
+    /// the new index of each solver, or `None` if it is removed
+    indices: Vec<Option<usize>>,
+}
+
+impl PruneUnusedSolvers {
+    /// remove the unused solvers of `p`, returning the new program and the number of solvers removed
+    pub fn prune<T: Field>(p: Prog<T>) -> (Prog<T>, usize) {
+        let usage = p.solver_usage();
+
+        let mut kept = 0;
+        let indices = usage
+            .iter()
+            .map(|u| match u.is_unused() {
+                true => None,
+                false => {
+                    kept += 1;
+                    Some(kept - 1)
+                }
+            })
+            .collect();
+
+        let mut pruner = PruneUnusedSolvers { indices };
+        let p = pruner.fold_program(p);
+
+        let solvers = p
+            .solvers
+            .into_iter()
+            .zip(&pruner.indices)
+            .filter_map(|(solver, index)| index.map(|_| solver))
+            .collect();
+
+        (Prog { solvers, ..p }, usage.len() - kept)
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for PruneUnusedSolvers {
+    fn fold_directive_statement(
+        &mut self,
+        d: DirectiveStatement<'ast, T>,
+    ) -> Vec<Statement<'ast, T>> {
+        let solver = match d.solver {
+            // references to missing solvers are kept as they are, to be reported when serializing the program
+            Solver::Ref(call) => match self.indices.get(call.index) {
+                Some(Some(index)) => Solver::Ref(RefCall {
+                    index: *index,
+                    ..call
+                }),
+                _ => Solver::Ref(call),
+            },
+            solver => solver,
+        };
+
+        fold_directive_statement(self, DirectiveStatement { solver, ..d })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Parameter, ProgEnum, Variable};
+    use std::io::Cursor;
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn prune() {
+        let call = |index: usize| {
+            Statement::directive(
+                vec![Variable::new(1)],
+                Solver::Ref(RefCall {
+                    index,
+                    signature: (1, 1),
+                }),
+                vec![Variable::new(0).into()],
+            )
+        };
+
+        // def main(public _0) {
+        //     # _1 = Ref@0(_0)
+        //     # _1 = Ref@1(_0)
+        //     # _1 = Ref@2(_0)
+        // }
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::public(Variable::new(0))],
+            statements: vec![call(0), call(1), call(2)],
+            solvers: vec![Solver::Bits(1), Solver::Xor, Solver::Div],
+            ..Prog::default()
+        };
+
+        // a pass removes the directive referring to the second solver
+        let p = Prog {
+            statements: vec![call(0), call(2)],
+            ..p
+        };
+
+        let (pruned, removed) = PruneUnusedSolvers::prune(p);
+
+        assert_eq!(removed, 1);
+        assert_eq!(pruned.solvers, vec![Solver::Bits(1), Solver::Div]);
+        assert_eq!(pruned.statements, vec![call(0), call(1)]);
+        assert!(pruned.solver_usage().iter().all(|u| !u.is_unused()));
+
+        // the orphaned solver is not serialized
+        let mut buffer = Cursor::new(vec![]);
+        pruned.clone().serialize(&mut buffer).unwrap();
+        buffer.set_position(0);
+        let deserialized = ProgEnum::deserialize(buffer).unwrap().collect();
+        assert_eq!(deserialized, ProgEnum::Bn128Program(pruned));
+    }
+}
@@ -0,0 +1,327 @@
+use super::serialize::{
+    check_header, deserialize_parameters, ConstraintsReader, ReadLimits, INCOMPLETE, ZOKRATES_MAGIC,
+};
+use super::variables::collect_variables;
+use super::{Parameter, Prog, ProgEnum, ProgHeader, Statement};
+use serde::Deserialize;
+use std::io::{Read, Seek, SeekFrom};
+use zokrates_field::Field;
+
+/// How the statements read by `Prog::read_partial` ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartialReadStatus {
+    /// the input ended between two statements, so that none was lost
+    Eof,
+    /// the input ended in the middle of a statement, which was dropped
+    Truncated,
+    /// a statement could not be decoded, for this reason
+    Invalid(String),
+}
+
+/// The statements recovered from a possibly truncated program, see `Prog::read_partial`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialRead<'ast, T> {
+    /// the program made of the statements decoded before the first error
+    pub program: Prog<'ast, T>,
+    /// the number of complete constraints recovered
+    pub constraints: usize,
+    pub status: PartialReadStatus,
+}
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// read the statements of the program in `r` up to the first one which cannot be decoded
+    ///
+    /// This salvages the constraints written before a serialization was interrupted: the header is only written once all sections are, so it is left zeroed, but the statements written so far are self-delimiting cbor records which can be decoded one by one.
+    /// Without a header, the parameters are expected right after the space reserved for it and the constraints section is assumed not to be compressed.
+    /// The solvers and module map are then unknown and left empty, and the return count is inferred from the outputs the recovered statements refer to.
+    /// If the constraints section was written entirely, the solvers section following it is reported as `PartialReadStatus::Invalid`.
+    /// Programs with a header are read like with `ProgEnum::try_read`, without verifying their checksums.
+    /// If their sections exceed the input, or if they are chunked programs whose serialization was never finalized, only the parameters and the
+    /// statements are read from the offsets given by the header, and the solvers and module map are left empty.
+    /// The statements of a chunked program then end with its last complete frame, see `ChunkedProgWriter`.
+    pub fn read_partial<R: Read + Seek>(mut r: R) -> Result<PartialRead<'ast, T>, String> {
+        let header = ProgHeader::read(&mut r)
+            .ok()
+            .filter(|header| &header.magic == ZOKRATES_MAGIC);
+
+        match header {
+            Some(header) if header.is_incomplete() || header.check_bounds(r.by_ref()).is_err() => {
+                // the header of an unfinished chunked program is otherwise valid
+                check_header(&ProgHeader {
+                    flags: header.flags & !INCOMPLETE,
+                    ..header.clone()
+                })?;
+
+                if header.curve_id != T::id() {
+                    return Err(format!(
+                        "Expected a program over curve `{}`, found `{}`",
+                        T::name(),
+                        header.curve_name().unwrap_or("unknown")
+                    ));
+                }
+
+                let parameters = &header.sections[0];
+                r.seek(SeekFrom::Start(parameters.offset))
+                    .map_err(|e| e.to_string())?;
+                let arguments =
+                    deserialize_parameters(r.by_ref().take(parameters.length), &header.version)?;
+
+                // the length of the constraints section is only known once it is written entirely
+                let constraints = &header.sections[1];
+                let length = match header.is_incomplete() {
+                    true => u64::MAX,
+                    false => constraints.length,
+                };
+                r.seek(SeekFrom::Start(constraints.offset))
+                    .map_err(|e| e.to_string())?;
+                let r = ConstraintsReader::new(r.take(length), &header, &ReadLimits::default())
+                    .map_err(|e| e.to_string())?;
+                let (statements, status) =
+                    recover(serde_cbor::Deserializer::from_reader(r).into_iter::<Statement<T>>());
+
+                Ok(PartialRead::new(
+                    Prog {
+                        arguments,
+                        return_count: header.return_count as usize,
+                        statements,
+                        ..Prog::default()
+                    },
+                    status,
+                ))
+            }
+            Some(header) => {
+                check_header(&header)?;
+
+                let p = ProgEnum::try_read::<T>(r, &header)?;
+                let (statements, status) = recover(p.statements.into_fallible());
+
+                Ok(PartialRead::new(
+                    Prog {
+                        module_map: p.module_map,
+                        arguments: p.arguments,
+                        return_count: p.return_count,
+                        returns: p.returns,
+                        embeds_used: p.embeds_used,
+                        statements,
+                        solvers: p.solvers,
+                    },
+                    status,
+                ))
+            }
+            None => {
+                r.seek(SeekFrom::Start(std::mem::size_of::<ProgHeader>() as u64))
+                    .map_err(|e| e.to_string())?;
+
+                // the statements follow the parameters in the same stream, so they are read with the same deserializer
+                let mut p = serde_cbor::Deserializer::from_reader(r);
+                let arguments = Vec::<Parameter>::deserialize(&mut p)
+                    .map_err(|_| String::from("Cannot read parameters"))?;
+                let (statements, status) = recover(p.into_iter::<Statement<T>>());
+
+                let return_count = statements
+                    .iter()
+                    .flat_map(collect_variables)
+                    .filter(|v| v.is_output())
+                    .map(|v| (-v.id) as usize)
+                    .max()
+                    .unwrap_or(0);
+
+                Ok(PartialRead::new(
+                    Prog {
+                        arguments,
+                        return_count,
+                        statements,
+                        ..Prog::default()
+                    },
+                    status,
+                ))
+            }
+        }
+    }
+}
+
+impl<'ast, T: Field> PartialRead<'ast, T> {
+    fn new(program: Prog<'ast, T>, status: PartialReadStatus) -> Self {
+        Self {
+            constraints: program.constraint_count(),
+            program,
+            status,
+        }
+    }
+}
+
+/// collect `statements` up to the first error, returning them with how they ended
+fn recover<'ast, T>(
+    statements: impl Iterator<Item = Result<Statement<'ast, T>, serde_cbor::Error>>,
+) -> (Vec<Statement<'ast, T>>, PartialReadStatus) {
+    let mut recovered = vec![];
+
+    for s in statements {
+        match s {
+            Ok(s) => recovered.push(s),
+            Err(e) if e.is_eof() => return (recovered, PartialReadStatus::Truncated),
+            Err(e) => return (recovered, PartialReadStatus::Invalid(e.to_string())),
+        }
+    }
+
+    (recovered, PartialReadStatus::Eof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::ModuleMap;
+    use crate::ir::{ChunkedProgWriter, LinComb, QuadComb, Variable};
+    use std::io::Cursor;
+    use zokrates_field::Bn128Field;
+
+    fn prog() -> Prog<'static, Bn128Field> {
+        // def main(private _0) -> (~out_0) {
+        //     _0 * _0 == _1
+        //     _1 * _0 == _2
+        //     _2 * _0 == ~out_0
+        // }
+        Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                    Variable::new(1),
+                    None,
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(1).into(), Variable::new(0).into()),
+                    Variable::new(2),
+                    None,
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(2).into(), Variable::new(0).into()),
+                    LinComb::from(Variable::public(0)),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        }
+    }
+
+    /// the serialization of `prog()` with its header zeroed, as left by an interruption, with the offset and length of its constraints section
+    fn interrupted() -> (Vec<u8>, u64, u64) {
+        let mut buffer = Cursor::new(vec![]);
+        prog().serialize(&mut buffer).unwrap();
+
+        buffer.set_position(0);
+        let constraints = ProgHeader::read(&mut buffer).unwrap().sections[1].clone();
+
+        let mut bytes = buffer.into_inner();
+        bytes[..std::mem::size_of::<ProgHeader>()].fill(0);
+        (bytes, constraints.offset, constraints.length)
+    }
+
+    #[test]
+    fn complete() {
+        let mut buffer = Cursor::new(vec![]);
+        prog().serialize(&mut buffer).unwrap();
+        buffer.set_position(0);
+
+        let read = Prog::<Bn128Field>::read_partial(buffer).unwrap();
+
+        assert_eq!(read.status, PartialReadStatus::Eof);
+        assert_eq!(read.constraints, 3);
+        assert_eq!(read.program, prog());
+    }
+
+    #[test]
+    fn clean_eof() {
+        let (mut bytes, offset, length) = interrupted();
+        bytes.truncate((offset + length) as usize);
+
+        let read = Prog::<Bn128Field>::read_partial(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(read.status, PartialReadStatus::Eof);
+        assert_eq!(read.constraints, 3);
+        assert_eq!(read.program, prog());
+    }
+
+    #[test]
+    fn truncated() {
+        let (mut bytes, offset, length) = interrupted();
+        bytes.truncate((offset + length - 1) as usize);
+
+        let read = Prog::<Bn128Field>::read_partial(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(read.status, PartialReadStatus::Truncated);
+        assert_eq!(read.constraints, 2);
+        assert_eq!(read.program.statements, prog().statements[..2].to_vec());
+        // the output is only defined by the dropped constraint
+        assert_eq!(read.program.return_count, 0);
+    }
+
+    #[test]
+    fn truncated_with_header() {
+        let mut buffer = Cursor::new(vec![]);
+        prog().serialize(&mut buffer).unwrap();
+
+        buffer.set_position(0);
+        let constraints = ProgHeader::read(&mut buffer).unwrap().sections[1].clone();
+
+        let mut bytes = buffer.into_inner();
+        bytes.truncate((constraints.offset + constraints.length - 1) as usize);
+
+        let read = Prog::<Bn128Field>::read_partial(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(read.status, PartialReadStatus::Truncated);
+        assert_eq!(read.constraints, 2);
+        assert_eq!(read.program.arguments, prog().arguments);
+        assert_eq!(read.program.statements, prog().statements[..2].to_vec());
+        // the return count is read from the header
+        assert_eq!(read.program.return_count, 1);
+    }
+
+    #[test]
+    fn unfinished_chunked() {
+        let p = prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        let mut writer =
+            ChunkedProgWriter::<Bn128Field, _>::new(&mut buffer, &p.arguments, p.return_count)
+                .unwrap();
+        writer.write_batch(p.statements[..2].to_vec()).unwrap();
+        writer.write_batch(p.statements[2..].to_vec()).unwrap();
+        drop(writer);
+
+        // the second frame is cut short
+        let mut bytes = buffer.into_inner();
+        bytes.pop();
+
+        let read = Prog::<Bn128Field>::read_partial(Cursor::new(bytes.clone())).unwrap();
+
+        assert_eq!(read.status, PartialReadStatus::Eof);
+        assert_eq!(read.constraints, 2);
+        assert_eq!(read.program.arguments, p.arguments);
+        assert_eq!(read.program.statements, p.statements[..2].to_vec());
+
+        // once finalized, the program is read entirely
+        let mut buffer = Cursor::new(vec![]);
+        let mut writer =
+            ChunkedProgWriter::<Bn128Field, _>::new(&mut buffer, &p.arguments, p.return_count)
+                .unwrap();
+        writer.write_batch(p.statements.clone()).unwrap();
+        writer.finalize(&ModuleMap::default()).unwrap();
+        buffer.set_position(0);
+
+        let read = Prog::<Bn128Field>::read_partial(buffer).unwrap();
+        assert_eq!(read.status, PartialReadStatus::Eof);
+        assert_eq!(read.program, p);
+    }
+
+    #[test]
+    fn followed_by_solvers() {
+        let (bytes, _, _) = interrupted();
+
+        let read = Prog::<Bn128Field>::read_partial(Cursor::new(bytes)).unwrap();
+
+        assert!(matches!(read.status, PartialReadStatus::Invalid(_)));
+        assert_eq!(read.constraints, 3);
+    }
+}
@@ -0,0 +1,163 @@
+use super::folder::{
+    fold_argument, fold_linear_combination, fold_quadratic_combination, fold_statement_cases,
+    Folder,
+};
+use super::{LinComb, Parameter, ProgIterator, QuadComb, ReturnSlot, Statement};
+use crate::common::{Span, SpanMap, WithSpan};
+use zokrates_field::Field;
+
+/// Rewrites the spans of a program through a `SpanMap`, so that a program compiled from preprocessed source points to the original source
+/// Spans are rewritten on statements, expressions, parameters and return slots. Spans without a mapping are kept as they are.
+/// The modules of the original spans should be added to the module map of the program for them to be displayed, see `ModuleMap::insert`.
+pub struct RemapSpans {
+    map: SpanMap,
+}
+
+impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
+    pub fn remap_spans(
+        self,
+        map: SpanMap,
+    ) -> ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>> {
+        let mut remapper = RemapSpans { map };
+
+        ProgIterator {
+            module_map: self.module_map,
+            arguments: self
+                .arguments
+                .into_iter()
+                .map(|a| <RemapSpans as Folder<T>>::fold_argument(&mut remapper, a))
+                .collect(),
+            return_count: self.return_count,
+            returns: self
+                .returns
+                .into_iter()
+                .map(|slot| ReturnSlot {
+                    span: remapper.remap(slot.span),
+                    ..slot
+                })
+                .collect(),
+            embeds_used: self.embeds_used,
+            statements: self
+                .statements
+                .into_iter()
+                .flat_map(move |s| Folder::fold_statement(&mut remapper, s)),
+            solvers: self.solvers,
+        }
+    }
+}
+
+impl RemapSpans {
+    fn remap(&self, span: Option<Span>) -> Option<Span> {
+        span.map(|span| self.map.resolve(span))
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for RemapSpans {
+    fn fold_argument(&mut self, p: Parameter) -> Parameter {
+        let p = fold_argument(self, p);
+        let span = self.remap(p.get_span());
+        p.span(span)
+    }
+
+    fn fold_statement(&mut self, s: Statement<'ast, T>) -> Vec<Statement<'ast, T>> {
+        fold_statement_cases(self, s)
+            .into_iter()
+            .map(|s| {
+                let span = self.remap(s.get_span());
+                s.span(span)
+            })
+            .collect()
+    }
+
+    fn fold_linear_combination(&mut self, e: LinComb<T>) -> LinComb<T> {
+        let e = fold_linear_combination(self, e);
+        let span = self.remap(e.get_span());
+        e.span(span)
+    }
+
+    fn fold_quadratic_combination(&mut self, e: QuadComb<T>) -> QuadComb<T> {
+        let e = fold_quadratic_combination(self, e);
+        let span = self.remap(e.get_span());
+        e.span(span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{LocalSourceSpan, Position};
+    use crate::ir::{Prog, Variable};
+    use crate::typed::ConcreteType;
+    use std::path::Path;
+    use zokrates_field::Bn128Field;
+
+    fn lines(module: &str, from: usize, to: usize) -> Span {
+        LocalSourceSpan {
+            from: Position { line: from, col: 1 },
+            to: Position { line: to, col: 1 },
+        }
+        .in_module(Path::new(module))
+        .into()
+    }
+
+    #[test]
+    fn remap() {
+        let constraint = |span: Span| {
+            Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                Variable::public(0),
+                None,
+            )
+            .span(Some(span))
+        };
+
+        // def main(private _0) -> (~out_0) {
+        //     _0 * _0 == ~out_0
+        //     {
+        //         _0 * _0 == ~out_0
+        //     }
+        // }
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0)).span(Some(lines(
+                "generated.zok",
+                1,
+                1,
+            )))],
+            statements: vec![
+                constraint(lines("generated.zok", 2, 2)),
+                Statement::block(vec![constraint(lines("generated.zok", 20, 20))]),
+            ],
+            ..Prog::default()
+        }
+        .with_returns(vec![ReturnSlot {
+            span: Some(lines("generated.zok", 1, 1)),
+            ..ReturnSlot::new(ConcreteType::FieldElement)
+        }]);
+
+        // the first lines of the generated module were expanded from the third line of the original one
+        let original = lines("main.zok", 3, 3);
+        let mut map = SpanMap::default();
+        map.insert(
+            LocalSourceSpan {
+                from: Position { line: 1, col: 1 },
+                to: Position { line: 5, col: 1 },
+            }
+            .in_module(Path::new("generated.zok")),
+            original,
+        );
+
+        let remapped = p.remap_spans(map).collect();
+
+        assert_eq!(remapped.arguments[0].get_span(), Some(original));
+        assert_eq!(remapped.returns[0].span, Some(original));
+        assert_eq!(remapped.statements[0].get_span(), Some(original));
+
+        // spans without a mapping are kept, including in nested blocks
+        match &remapped.statements[1] {
+            Statement::Block(b) => {
+                assert_eq!(b.inner[0].get_span(), Some(lines("generated.zok", 20, 20)))
+            }
+            _ => unreachable!(),
+        }
+    }
+}
@@ -1,17 +1,311 @@
-use crate::ir::{check::UnconstrainedVariableDetector, solver_indexer::SolverIndexer};
-
-use super::{ProgIterator, Statement};
+use crate::ir::{
+    check::{UnconstrainedVariable, UnconstrainedVariableDetector},
+    solver_indexer::SolverIndexer,
+    visitor::Visitor,
+};
+
+use super::chunked::ChunkReader;
+use super::{Parameter, ProgIterator, ReturnSlot, Statement, Variable};
+use crate::common::{FlatEmbed, SolverError, Span, Visibility};
 use crate::ir::ModuleMap;
+use crate::typed::abi::Abi;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_cbor::{self, StreamDeserializer};
-use std::io::{Read, Seek, Write};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{Cursor, Read, Seek, SeekFrom, Take, Write};
 use zokrates_field::*;
 
 type DynamicError = Box<dyn std::error::Error>;
 
-const ZOKRATES_MAGIC: &[u8; 4] = &[0x5a, 0x4f, 0x4b, 0];
-const FILE_VERSION: &[u8; 4] = &[3, 0, 0, 0];
+/// An error raised when serializing a program
+#[derive(Debug)]
+pub enum SerializeError {
+    Io(std::io::Error),
+    Cbor(serde_cbor::Error),
+    /// a directive refers to a solver which is invalid
+    Solver(SolverError),
+    /// some private variables are never constrained, so the program is likely unsound
+    /// The program was fully written, so this can be treated as a warning.
+    UnconstrainedVariables {
+        variables: Vec<UnconstrainedVariable>,
+        /// the module map of the program, to resolve the spans of the variables
+        module_map: ModuleMap,
+    },
+    /// the program or the options it is serialized with are invalid, for example it has too few constraints
+    Invalid(String),
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerializeError::Io(e) => write!(f, "{}", e),
+            SerializeError::Cbor(e) => write!(f, "{}", e),
+            SerializeError::Solver(e) => write!(f, "{}", e),
+            SerializeError::UnconstrainedVariables {
+                variables,
+                module_map,
+            } => write!(
+                f,
+                "{}",
+                UnconstrainedVariable::report(variables, module_map)
+            ),
+            SerializeError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SerializeError::Io(e) => Some(e),
+            SerializeError::Cbor(e) => Some(e),
+            SerializeError::Solver(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for SerializeError {
+    fn from(e: std::io::Error) -> Self {
+        SerializeError::Io(e)
+    }
+}
+
+impl From<serde_cbor::Error> for SerializeError {
+    fn from(e: serde_cbor::Error) -> Self {
+        SerializeError::Cbor(e)
+    }
+}
+
+impl From<SolverError> for SerializeError {
+    fn from(e: SolverError) -> Self {
+        SerializeError::Solver(e)
+    }
+}
+
+pub(super) const ZOKRATES_MAGIC: &[u8; 4] = &[0x5a, 0x4f, 0x4b, 0];
+pub(super) const FILE_VERSION: &[u8; 4] = &CURRENT_FILE_VERSION;
+/// The version of the files written by this library
+pub const CURRENT_FILE_VERSION: [u8; 4] = [12, 0, 0, 0];
+/// The oldest file version which can still be read
+const MIN_FILE_VERSION: &[u8; 4] = &[3, 0, 0, 0];
+/// The first file version in which the header carries flags and sections carry a checksum
+const EXTENDED_HEADER_VERSION: &[u8; 4] = &[4, 0, 0, 0];
+
+/// The first file version in which the header carries the number of variables
+const VARIABLE_COUNT_VERSION: &[u8; 4] = &[5, 0, 0, 0];
+/// The first file version in which the header can point to a constraint index section
+const CONSTRAINT_INDEX_VERSION: &[u8; 4] = &[6, 0, 0, 0];
+/// The first file version in which parameters carry a visibility rather than a `private` flag
+const VISIBILITY_VERSION: &[u8; 4] = &[7, 0, 0, 0];
+/// The first file version in which the header can point to a return slots section
+const RETURN_SLOTS_VERSION: &[u8; 4] = &[8, 0, 0, 0];
+/// The first file version in which the header can point to a section listing the embeds used by the program
+const EMBEDS_VERSION: &[u8; 4] = &[9, 0, 0, 0];
+/// The first file version in which the header carries a hash of the source files
+const SOURCE_HASH_VERSION: &[u8; 4] = &[10, 0, 0, 0];
+/// The first file version in which the header can point to a metadata section
+const METADATA_VERSION: &[u8; 4] = &[11, 0, 0, 0];
+/// The first file version in which the header can point to a section of the declared types of the inputs and outputs
+const TYPES_VERSION: &[u8; 4] = &[12, 0, 0, 0];
+
+/// whether files written in version `v` can be read by this library
+pub fn is_compatible_version(v: [u8; 4]) -> bool {
+    &v >= MIN_FILE_VERSION && &v <= FILE_VERSION
+}
+
+fn has_extended_header(version: &[u8; 4]) -> bool {
+    version >= EXTENDED_HEADER_VERSION
+}
+
+fn has_variable_count(version: &[u8; 4]) -> bool {
+    version >= VARIABLE_COUNT_VERSION
+}
+
+fn has_constraint_index(version: &[u8; 4]) -> bool {
+    version >= CONSTRAINT_INDEX_VERSION
+}
+
+fn has_return_slots(version: &[u8; 4]) -> bool {
+    version >= RETURN_SLOTS_VERSION
+}
+
+fn has_embeds(version: &[u8; 4]) -> bool {
+    version >= EMBEDS_VERSION
+}
+
+fn has_source_hash(version: &[u8; 4]) -> bool {
+    version >= SOURCE_HASH_VERSION
+}
+
+fn has_metadata(version: &[u8; 4]) -> bool {
+    version >= METADATA_VERSION
+}
+
+fn has_types(version: &[u8; 4]) -> bool {
+    version >= TYPES_VERSION
+}
+
+/// A parameter as written in files older than version 7
+#[derive(Deserialize)]
+struct LegacyParameter {
+    span: Option<Span>,
+    id: Variable,
+    private: bool,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+impl From<LegacyParameter> for Parameter {
+    fn from(p: LegacyParameter) -> Self {
+        let visibility = match p.private {
+            true => Visibility::Private,
+            false => Visibility::Public,
+        };
+
+        Parameter::new(p.id, visibility)
+            .with_name(p.name)
+            .span(p.span)
+    }
+}
+
+/// deserialize a parameters section written in file version `version`
+pub(super) fn deserialize_parameters<R: Read>(
+    r: R,
+    version: &[u8; 4],
+) -> Result<Vec<Parameter>, String> {
+    let mut p = serde_cbor::Deserializer::from_reader(r);
+
+    let parameters = match version >= VISIBILITY_VERSION {
+        true => Vec::deserialize(&mut p),
+        false => Vec::<LegacyParameter>::deserialize(&mut p)
+            .map(|parameters| parameters.into_iter().map(Parameter::from).collect()),
+    };
+
+    parameters.map_err(|_| String::from("Cannot read parameters"))
+}
+
+/// read the parameters of the program described by `header`, without reading its other sections
+pub fn read_parameters<R: Read + Seek>(
+    mut r: R,
+    header: &ProgHeader,
+) -> Result<Vec<Parameter>, DynamicError> {
+    check_header(header)?;
+
+    let section = &header.sections[0];
+    r.seek(SeekFrom::Start(section.offset))?;
+
+    let mut bytes = vec![];
+    r.take(section.length).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != section.length {
+        return Err("Parameters section out of bounds".into());
+    }
+    section.verify_bytes(&bytes)?;
+
+    Ok(deserialize_parameters(&bytes[..], &header.version)?)
+}
+
+/// read the bytes of `section` from `r` verbatim, for example to sign them, leaving the position of `r` unchanged
+/// The bytes are neither deserialized nor checked against the checksum of the section, see `ProgHeader::verify`
+/// Only the bytes actually present in `r` are allocated, so that a corrupted length cannot exhaust memory.
+pub fn read_section_bytes<R: Read + Seek>(mut r: R, section: &Section) -> std::io::Result<Vec<u8>> {
+    let position = r.stream_position()?;
+
+    r.seek(SeekFrom::Start(section.offset))?;
+    let mut bytes = vec![];
+    let res = r
+        .by_ref()
+        .take(section.length)
+        .read_to_end(&mut bytes)
+        .and_then(|_| match bytes.len() as u64 == section.length {
+            true => Ok(()),
+            false => Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("Section {:?} out of bounds", section.ty),
+            )),
+        });
+
+    r.seek(SeekFrom::Start(position))?;
+    res.map(|_| bytes)
+}
+
+/// Header flag set when the constraints section is zstd-compressed
+const COMPRESSED_CONSTRAINTS: u8 = 1;
+/// Header flag set when the constraints section is a sequence of frames, see `ChunkedProgWriter`
+pub(super) const CHUNKED_CONSTRAINTS: u8 = 2;
+/// Header flag set while a chunked program is being written, and cleared when it is finalized
+pub(super) const INCOMPLETE: u8 = 4;
+/// All header flags known to this version
+const KNOWN_FLAGS: u8 = COMPRESSED_CONSTRAINTS | CHUNKED_CONSTRAINTS | INCOMPLETE;
+
+/// The zstd compression level used for the constraints section
+/// Higher levels barely improve the ratio on repetitive CBOR statements while being much slower to write, see `benches/compression.rs`
+#[cfg(feature = "compression")]
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The number of constraints written between two progress events, see `ProgressEvent`
+const PROGRESS_BATCH: u64 = 10_000;
+
+/// The progress of a serialization, reported after every batch of constraints and once all statements are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressEvent {
+    /// the number of constraints written so far
+    pub constraints: usize,
+    /// the number of bytes of statements written so far, before compression if the constraints are compressed
+    pub bytes: u64,
+}
+
+/// What to do with the private variables which are never constrained when serializing a program, see `serialize_with_unconstrained_policy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnconstrainedPolicy {
+    /// fail with `SerializeError::UnconstrainedVariables`, once the program is written
+    #[default]
+    Error,
+    /// report the variables to a callback, and succeed
+    Warn,
+    /// succeed without reporting the variables
+    Ignore,
+}
+
+/// A callback receiving the unconstrained variables of a program, along with its module map to resolve their spans
+type UnconstrainedCallback<'a> = &'a mut dyn FnMut(Vec<UnconstrainedVariable>, &ModuleMap);
+
+/// The options of a serialization, which each public serialization function sets differently
+/// The options recorded in the output can also be combined with the builder methods below, see `ProgIterator::serialize_with_options`.
+#[derive(Default)]
+pub struct SerializeOptions<'a> {
+    flags: u8,
+    index_stride: Option<usize>,
+    min_constraints: usize,
+    source_hash: [u8; 32],
+    metadata: Option<&'a BTreeMap<String, String>>,
+    types: Option<&'a Abi>,
+    on_progress: Option<&'a mut dyn FnMut(ProgressEvent)>,
+    unconstrained: UnconstrainedPolicy,
+    on_unconstrained: Option<UnconstrainedCallback<'a>>,
+}
+
+impl<'a> SerializeOptions<'a> {
+    /// record `source_hash` in the header, see `ProgIterator::serialize_with_source_hash`
+    pub fn source_hash(mut self, source_hash: [u8; 32]) -> Self {
+        self.source_hash = source_hash;
+        self
+    }
+
+    /// write `metadata` to a section of key-value annotations, see `ProgIterator::serialize_with_metadata`
+    pub fn metadata(mut self, metadata: &'a BTreeMap<String, String>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// write `types` to a section of the declared types of the inputs and outputs, see `ProgIterator::serialize_with_types`
+    pub fn types(mut self, types: &'a Abi) -> Self {
+        self.types = Some(types);
+        self
+    }
+}
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum ProgEnum<
@@ -80,6 +374,11 @@ pub enum SectionType {
     Constraints = 2,
     Solvers = 3,
     Modules = 4,
+    ConstraintIndex = 5,
+    ReturnSlots = 6,
+    Embeds = 7,
+    Metadata = 8,
+    Types = 9,
 }
 
 impl TryFrom<u32> for SectionType {
@@ -91,6 +390,11 @@ impl TryFrom<u32> for SectionType {
             2 => Ok(SectionType::Constraints),
             3 => Ok(SectionType::Solvers),
             4 => Ok(SectionType::Modules),
+            5 => Ok(SectionType::ConstraintIndex),
+            6 => Ok(SectionType::ReturnSlots),
+            7 => Ok(SectionType::Embeds),
+            8 => Ok(SectionType::Metadata),
+            9 => Ok(SectionType::Types),
             _ => Err("invalid section type".to_string()),
         }
     }
@@ -101,6 +405,8 @@ pub struct Section {
     pub ty: SectionType,
     pub offset: u64,
     pub length: u64,
+    /// crc32 digest of the section bytes, absent in files older than version 4
+    pub checksum: Option<u32>,
 }
 
 impl Section {
@@ -109,6 +415,7 @@ impl Section {
             ty,
             offset: 0,
             length: 0,
+            checksum: None,
         }
     }
 
@@ -119,6 +426,81 @@ impl Section {
     pub fn set_length(&mut self, length: u64) {
         self.length = length;
     }
+
+    pub fn set_checksum(&mut self, checksum: u32) {
+        self.checksum = Some(checksum);
+    }
+
+    /// check that `bytes` match the checksum of this section, if any
+    fn verify_bytes(&self, bytes: &[u8]) -> Result<(), String> {
+        match self.checksum {
+            Some(checksum) if crc32fast::hash(bytes) != checksum => Err(self.checksum_error()),
+            _ => Ok(()),
+        }
+    }
+
+    /// check that the bytes of this section in `r` match its checksum, if any
+    fn verify<R: Read + Seek>(&self, mut r: R) -> Result<(), String> {
+        if let Some(checksum) = self.checksum {
+            r.seek(SeekFrom::Start(self.offset))
+                .map_err(|e| e.to_string())?;
+
+            let mut w = ChecksumWriter::new(std::io::sink());
+            let read =
+                std::io::copy(&mut r.take(self.length), &mut w).map_err(|e| e.to_string())?;
+
+            if read != self.length || w.checksum() != checksum {
+                return Err(self.checksum_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn checksum_error(&self) -> String {
+        format!(
+            "Checksum mismatch in section {:?}, the file may be corrupted",
+            self.ty
+        )
+    }
+}
+
+/// A writer which computes a crc32 digest of the bytes written through it
+pub(super) struct ChecksumWriter<W> {
+    inner: W,
+    pub(super) hasher: crc32fast::Hasher,
+}
+
+impl<W> ChecksumWriter<W> {
+    pub(super) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+
+    /// return the digest of the bytes written since the last call, and reset it
+    pub(super) fn checksum(&mut self) -> u32 {
+        std::mem::replace(&mut self.hasher, crc32fast::Hasher::new()).finalize()
+    }
+}
+
+impl<W: Write> Write for ChecksumWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Seek> Seek for ChecksumWriter<W> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -128,7 +510,78 @@ pub struct ProgHeader {
     pub curve_id: [u8; 4],
     pub constraint_count: u32,
     pub return_count: u32,
+    /// a bitset of options, absent in files older than version 4
+    pub flags: u8,
+    /// the number of witness variables, absent in files older than version 5, see `variable_count`
+    pub variable_count: Option<u32>,
     pub sections: [Section; 4],
+    /// the constraint index section, absent in files older than version 6 or when no index was written
+    pub constraint_index: Option<Section>,
+    /// the return slots section, absent in files older than version 8 or when the program has no return slots
+    pub return_slots: Option<Section>,
+    /// the section listing the embeds used by the program, absent in files older than version 9 or when they are unknown
+    pub embeds: Option<Section>,
+    /// a hash of the source files of the program, see `ModuleMap::source_hash`
+    /// Zero when it was not recorded, which is the case of files older than version 10
+    pub source_hash: [u8; 32],
+    /// the section of key-value annotations, absent in files older than version 11 or when there are none
+    pub metadata: Option<Section>,
+    /// the section of the declared types of the inputs and outputs, absent in files older than version 12 or when they are unknown
+    pub types: Option<Section>,
+}
+
+/// The byte offsets of every `stride`-th constraint in the constraints section, so that constraints can be reached without reading the preceding ones
+/// Offsets are relative to the start of the section, once decompressed if it is compressed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ConstraintIndex {
+    stride: u64,
+    offsets: Vec<u64>,
+}
+
+/// Statistics about a program which can be obtained from its header alone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgSummary {
+    pub curve: &'static str,
+    pub constraint_count: u32,
+    pub return_count: u32,
+    pub variable_count: Option<u32>,
+    pub compressed: bool,
+    /// the type, offset and length of each section
+    pub sections: Vec<(SectionType, u64, u64)>,
+    pub source_hash: Option<[u8; 32]>,
+}
+
+impl fmt::Display for ProgSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<17} {}", "curve:", self.curve)?;
+        writeln!(f, "{:<17} {}", "constraint_count:", self.constraint_count)?;
+        writeln!(f, "{:<17} {}", "return_count:", self.return_count)?;
+        if let Some(variable_count) = self.variable_count {
+            writeln!(f, "{:<17} {}", "variable_count:", variable_count)?;
+        }
+        writeln!(f, "{:<17} {}", "compressed:", self.compressed)?;
+        if let Some(source_hash) = self.source_hash {
+            writeln!(
+                f,
+                "{:<17} {}",
+                "source_hash:",
+                source_hash
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            )?;
+        }
+        for (ty, offset, length) in &self.sections {
+            writeln!(
+                f,
+                "{:<17} offset {}, length {}",
+                format!("{:?}:", ty).to_lowercase(),
+                offset,
+                length
+            )?;
+        }
+        Ok(())
+    }
 }
 
 impl ProgHeader {
@@ -139,15 +592,94 @@ impl ProgHeader {
         w.write_u32::<LittleEndian>(self.constraint_count)?;
         w.write_u32::<LittleEndian>(self.return_count)?;
 
+        let extended = has_extended_header(&self.version);
+
+        if extended {
+            w.write_u8(self.flags)?;
+        }
+
+        if has_variable_count(&self.version) {
+            w.write_u32::<LittleEndian>(self.variable_count.unwrap_or_default())?;
+        }
+
         for s in &self.sections {
-            w.write_u32::<LittleEndian>(s.ty as u32)?;
-            w.write_u64::<LittleEndian>(s.offset)?;
-            w.write_u64::<LittleEndian>(s.length)?;
+            Self::write_section(w.by_ref(), s, extended)?;
+        }
+
+        if has_constraint_index(&self.version) {
+            // an empty section marks the absence of an index
+            let empty = Section::new(SectionType::ConstraintIndex);
+            Self::write_section(
+                w.by_ref(),
+                self.constraint_index.as_ref().unwrap_or(&empty),
+                extended,
+            )?;
+        }
+
+        if has_return_slots(&self.version) {
+            let empty = Section::new(SectionType::ReturnSlots);
+            Self::write_section(
+                w.by_ref(),
+                self.return_slots.as_ref().unwrap_or(&empty),
+                extended,
+            )?;
+        }
+
+        if has_embeds(&self.version) {
+            let empty = Section::new(SectionType::Embeds);
+            Self::write_section(w.by_ref(), self.embeds.as_ref().unwrap_or(&empty), extended)?;
         }
 
+        if has_source_hash(&self.version) {
+            w.write_all(&self.source_hash)?;
+        }
+
+        if has_metadata(&self.version) {
+            let empty = Section::new(SectionType::Metadata);
+            Self::write_section(
+                w.by_ref(),
+                self.metadata.as_ref().unwrap_or(&empty),
+                extended,
+            )?;
+        }
+
+        if has_types(&self.version) {
+            let empty = Section::new(SectionType::Types);
+            Self::write_section(w.by_ref(), self.types.as_ref().unwrap_or(&empty), extended)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_section<W: Write>(mut w: W, s: &Section, checksum: bool) -> std::io::Result<()> {
+        w.write_u32::<LittleEndian>(s.ty as u32)?;
+        w.write_u64::<LittleEndian>(s.offset)?;
+        w.write_u64::<LittleEndian>(s.length)?;
+        if checksum {
+            w.write_u32::<LittleEndian>(s.checksum.unwrap_or_default())?;
+        }
         Ok(())
     }
 
+    /// read the file version of a program, consuming only its magic number and version
+    /// Fails if `r` does not start with the magic number of a program
+    pub fn peek_version<R: Read>(mut r: R) -> std::io::Result<[u8; 4]> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+
+        if &magic != ZOKRATES_MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid magic number",
+            ));
+        }
+
+        let mut version = [0; 4];
+        r.read_exact(&mut version)?;
+
+        Ok(version)
+    }
+
     pub fn read<R: Read>(mut r: R) -> std::io::Result<Self> {
         let mut magic = [0; 4];
         r.read_exact(&mut magic)?;
@@ -161,10 +693,55 @@ impl ProgHeader {
         let constraint_count = r.read_u32::<LittleEndian>()?;
         let return_count = r.read_u32::<LittleEndian>()?;
 
-        let parameters = Self::read_section(r.by_ref())?;
-        let constraints = Self::read_section(r.by_ref())?;
-        let solvers = Self::read_section(r.by_ref())?;
-        let module_map = Self::read_section(r.by_ref())?;
+        let extended = has_extended_header(&version);
+
+        let flags = if extended { r.read_u8()? } else { 0 };
+
+        let variable_count = if has_variable_count(&version) {
+            Some(r.read_u32::<LittleEndian>()?)
+        } else {
+            None
+        };
+
+        let parameters = Self::read_section(r.by_ref(), extended)?;
+        let constraints = Self::read_section(r.by_ref(), extended)?;
+        let solvers = Self::read_section(r.by_ref(), extended)?;
+        let module_map = Self::read_section(r.by_ref(), extended)?;
+
+        let constraint_index = if has_constraint_index(&version) {
+            Some(Self::read_section(r.by_ref(), extended)?).filter(|s| s.length > 0)
+        } else {
+            None
+        };
+
+        let return_slots = if has_return_slots(&version) {
+            Some(Self::read_section(r.by_ref(), extended)?).filter(|s| s.length > 0)
+        } else {
+            None
+        };
+
+        let embeds = if has_embeds(&version) {
+            Some(Self::read_section(r.by_ref(), extended)?).filter(|s| s.length > 0)
+        } else {
+            None
+        };
+
+        let mut source_hash = [0; 32];
+        if has_source_hash(&version) {
+            r.read_exact(&mut source_hash)?;
+        }
+
+        let metadata = if has_metadata(&version) {
+            Some(Self::read_section(r.by_ref(), extended)?).filter(|s| s.length > 0)
+        } else {
+            None
+        };
+
+        let types = if has_types(&version) {
+            Some(Self::read_section(r.by_ref(), extended)?).filter(|s| s.length > 0)
+        } else {
+            None
+        };
 
         Ok(ProgHeader {
             magic,
@@ -172,11 +749,19 @@ impl ProgHeader {
             curve_id,
             constraint_count,
             return_count,
+            flags,
+            variable_count,
             sections: [parameters, constraints, solvers, module_map],
+            constraint_index,
+            return_slots,
+            embeds,
+            source_hash,
+            metadata,
+            types,
         })
     }
 
-    fn read_section<R: Read>(mut r: R) -> std::io::Result<Section> {
+    fn read_section<R: Read>(mut r: R, checksum: bool) -> std::io::Result<Section> {
         let id = r.read_u32::<LittleEndian>()?;
         let mut section = Section::new(
             SectionType::try_from(id)
@@ -184,244 +769,3215 @@ impl ProgHeader {
         );
         section.set_offset(r.read_u64::<LittleEndian>()?);
         section.set_length(r.read_u64::<LittleEndian>()?);
+        if checksum {
+            section.set_checksum(r.read_u32::<LittleEndian>()?);
+        }
         Ok(section)
     }
-}
-
-impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
-    /// serialize a program iterator, returning the number of constraints serialized
-    /// Note that we only return constraints, not other statements such as directives
-    pub fn serialize<W: Write + Seek>(self, mut w: W) -> Result<usize, DynamicError> {
-        use super::folder::Folder;
 
-        // reserve bytes for the header
-        w.write_all(&[0u8; std::mem::size_of::<ProgHeader>()])?;
+    /// whether the constraints section is zstd-compressed
+    pub fn is_compressed(&self) -> bool {
+        self.flags & COMPRESSED_CONSTRAINTS != 0
+    }
 
-        // write parameters section
-        let parameters = {
-            let mut section = Section::new(SectionType::Parameters);
-            section.set_offset(w.stream_position()?);
+    /// whether the constraints section is a sequence of frames, see `ChunkedProgWriter`
+    pub fn is_chunked(&self) -> bool {
+        self.flags & CHUNKED_CONSTRAINTS != 0
+    }
 
-            serde_cbor::to_writer(&mut w, &self.arguments)?;
+    /// whether this is the header of a chunked program which was not finalized
+    pub fn is_incomplete(&self) -> bool {
+        self.flags & INCOMPLETE != 0
+    }
 
-            section.set_length(w.stream_position()? - section.offset);
-            section
-        };
+    /// the name of the curve this program is defined over, if it is known
+    pub fn curve_name(&self) -> Option<&'static str> {
+        match self.curve_id {
+            m if m == Bls12_381Field::id() => Some(Bls12_381Field::name()),
+            m if m == Bn128Field::id() => Some(Bn128Field::name()),
+            m if m == Bls12_377Field::id() => Some(Bls12_377Field::name()),
+            m if m == Bw6_761Field::id() => Some(Bw6_761Field::name()),
+            m if m == PallasField::id() => Some(PallasField::name()),
+            m if m == VestaField::id() => Some(VestaField::name()),
+            _ => None,
+        }
+    }
 
-        let mut solver_indexer: SolverIndexer<'ast, T> = SolverIndexer::default();
-        let mut unconstrained_variable_detector = UnconstrainedVariableDetector::new(&self);
-        let mut count: usize = 0;
+    /// the name of the `Field` type to read this program with, if its curve is known
+    pub fn field_type_name(&self) -> Option<&'static str> {
+        match self.curve_id {
+            m if m == Bls12_381Field::id() => Some("Bls12_381Field"),
+            m if m == Bn128Field::id() => Some("Bn128Field"),
+            m if m == Bls12_377Field::id() => Some("Bls12_377Field"),
+            m if m == Bw6_761Field::id() => Some("Bw6_761Field"),
+            m if m == PallasField::id() => Some("PallasField"),
+            m if m == VestaField::id() => Some("VestaField"),
+            _ => None,
+        }
+    }
 
-        // write constraints section
-        let constraints = {
-            let mut section = Section::new(SectionType::Constraints);
-            section.set_offset(w.stream_position()?);
+    /// summarize the program based on its header only, without reading any section
+    pub fn summary(&self) -> ProgSummary {
+        ProgSummary {
+            curve: self.curve_name().unwrap_or("unknown"),
+            constraint_count: self.constraint_count,
+            return_count: self.return_count,
+            variable_count: self.variable_count,
+            compressed: self.is_compressed(),
+            sections: self
+                .all_sections()
+                .map(|s| (s.ty, s.offset, s.length))
+                .collect(),
+            source_hash: Some(self.source_hash).filter(|h| *h != [0; 32]),
+        }
+    }
 
-            let statements = self.statements.into_iter();
-            for s in statements {
-                if matches!(s, Statement::Constraint(..)) {
-                    count += 1;
-                }
-                let s: Vec<Statement<T>> = solver_indexer
-                    .fold_statement(s)
-                    .into_iter()
-                    .flat_map(|s| unconstrained_variable_detector.fold_statement(s))
-                    .collect();
-                for s in s {
-                    serde_cbor::to_writer(&mut w, &s)?;
-                }
-            }
+    /// the number of witness variables of the program in `r`, which is read from the header if present
+    /// Files older than version 5 do not record it, in which case the program is read over `T` to count them
+    pub fn variable_count<T: Field, R: Read + Seek>(&self, r: R) -> Result<u32, String> {
+        if let Some(variable_count) = self.variable_count {
+            return Ok(variable_count);
+        }
 
-            section.set_length(w.stream_position()? - section.offset);
-            section
-        };
+        let prog = ProgEnum::try_read::<T>(r, self)?;
 
-        // write solvers section
-        let solvers = {
-            let mut section = Section::new(SectionType::Solvers);
-            section.set_offset(w.stream_position()?);
+        let mut counter = VariableCounter::default();
+        for a in &prog.arguments {
+            <VariableCounter as Visitor<T>>::visit_argument(&mut counter, a);
+        }
+        for s in prog.statements.into_fallible() {
+            counter.visit_statement(&s.map_err(|e| format!("Cannot read constraints: {}", e))?);
+        }
 
-            serde_cbor::to_writer(&mut w, &solver_indexer.solvers)?;
+        Ok(counter.count())
+    }
 
-            section.set_length(w.stream_position()? - section.offset);
-            section
-        };
+    /// read the constraint index of the program in `r`, if it has one
+    fn read_constraint_index<R: Read + Seek>(
+        &self,
+        mut r: R,
+    ) -> Result<Option<ConstraintIndex>, String> {
+        match &self.constraint_index {
+            Some(section) => {
+                r.seek(SeekFrom::Start(section.offset))
+                    .map_err(|e| e.to_string())?;
+
+                let mut p = serde_cbor::Deserializer::from_reader(r.take(section.length));
+                ConstraintIndex::deserialize(&mut p)
+                    .map(Some)
+                    .map_err(|_| String::from("Cannot read constraint index"))
+            }
+            None => Ok(None),
+        }
+    }
 
-        // write module map section
-        let module_map = {
-            let mut section = Section::new(SectionType::Solvers);
-            section.set_offset(w.stream_position()?);
+    /// read the return slots of the program in `r`, which are empty if it has none
+    fn read_return_slots<R: Read + Seek>(&self, mut r: R) -> Result<Vec<ReturnSlot>, String> {
+        match &self.return_slots {
+            Some(section) => {
+                r.seek(SeekFrom::Start(section.offset))
+                    .map_err(|e| e.to_string())?;
 
-            serde_cbor::to_writer(&mut w, &self.module_map)?;
+                deserialize_return_slots(r.take(section.length))
+            }
+            None => Ok(vec![]),
+        }
+    }
 
-            section.set_length(w.stream_position()? - section.offset);
-            section
-        };
+    /// read the embeds used by the program in `r`, without reading the rest of the program
+    /// The set is empty if the file does not record them, which is the case of files older than version 9
+    pub fn read_embeds_used<R: Read + Seek>(
+        &self,
+        mut r: R,
+    ) -> Result<BTreeSet<FlatEmbed>, String> {
+        match &self.embeds {
+            Some(section) => {
+                r.seek(SeekFrom::Start(section.offset))
+                    .map_err(|e| e.to_string())?;
 
-        let header = ProgHeader {
-            magic: *ZOKRATES_MAGIC,
-            version: *FILE_VERSION,
-            curve_id: T::id(),
-            constraint_count: count as u32,
-            return_count: self.return_count as u32,
-            sections: [parameters, constraints, solvers, module_map],
-        };
+                deserialize_embeds_used(r.take(section.length))
+            }
+            None => Ok(BTreeSet::new()),
+        }
+    }
 
-        // rewind to write the header
-        w.rewind()?;
-        header.write(&mut w)?;
+    /// read the key-value annotations of the program in `r`, which are empty if it has none
+    pub fn read_metadata<R: Read + Seek>(
+        &self,
+        mut r: R,
+    ) -> Result<BTreeMap<String, String>, String> {
+        match &self.metadata {
+            Some(section) => {
+                r.seek(SeekFrom::Start(section.offset))
+                    .map_err(|e| e.to_string())?;
 
-        unconstrained_variable_detector
-            .finalize()
-            .map(|_| count)
-            .map_err(|count| format!("Error: Found {} unconstrained variable(s)", count).into())
+                deserialize_metadata(r.take(section.length))
+            }
+            None => Ok(BTreeMap::new()),
+        }
     }
-}
 
-pub struct UnwrappedStreamDeserializer<'de, R, T> {
-    s: StreamDeserializer<'de, R, T>,
-}
+    /// read the declared types of the inputs and outputs of the program in `r`, if they were recorded, see `serialize_with_types`
+    pub fn read_types<R: Read + Seek>(&self, mut r: R) -> Result<Option<Abi>, String> {
+        match &self.types {
+            Some(section) => {
+                r.seek(SeekFrom::Start(section.offset))
+                    .map_err(|e| e.to_string())?;
 
-impl<'de, R: serde_cbor::de::Read<'de>, T: serde::Deserialize<'de>> Iterator
-    for UnwrappedStreamDeserializer<'de, R, T>
-{
-    type Item = T;
+                deserialize_types(r.take(section.length)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// verify the integrity of each section against its checksum, if the file version has them
+    /// The bounds of the sections are checked first, see `check_bounds`.
+    /// This reads every section in full, but does not deserialize them
+    pub fn verify<R: Read + Seek>(&self, mut r: R) -> Result<(), String> {
+        self.check_bounds(r.by_ref())?;
+
+        for section in self.all_sections() {
+            section.verify(r.by_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// check that each section lies within the stream `r` and that no two sections overlap, leaving the position of `r` unchanged
+    pub fn check_bounds<R: Seek>(&self, mut r: R) -> Result<(), String> {
+        let position = r.stream_position().map_err(|e| e.to_string())?;
+        let length = r.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+        r.seek(SeekFrom::Start(position))
+            .map_err(|e| e.to_string())?;
+
+        let mut sections: Vec<_> = self.all_sections().collect();
+
+        for section in &sections {
+            match section.offset.checked_add(section.length) {
+                Some(end) if end <= length => {}
+                _ => {
+                    return Err(format!(
+                        "Section {:?} out of bounds, the file may be corrupted",
+                        section.ty
+                    ))
+                }
+            }
+        }
+
+        // empty sections cannot overlap with anything
+        sections.retain(|s| s.length > 0);
+        sections.sort_by_key(|s| s.offset);
+
+        for pair in sections.windows(2) {
+            if pair[0].offset + pair[0].length > pair[1].offset {
+                return Err(format!(
+                    "Sections {:?} and {:?} overlap, the file may be corrupted",
+                    pair[0].ty, pair[1].ty
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn all_sections(&self) -> impl Iterator<Item = &Section> {
+        self.sections
+            .iter()
+            .chain(&self.constraint_index)
+            .chain(&self.return_slots)
+            .chain(&self.embeds)
+            .chain(&self.metadata)
+            .chain(&self.types)
+    }
+}
+
+impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
+    /// serialize a program iterator, returning the number of constraints serialized
+    /// Note that we only return constraints, not other statements such as directives
+    ///
+    /// Statements are pulled from the iterator one at a time and written before the next one is pulled, so a program whose statements are
+    /// produced lazily, for example by a generator wrapped in `std::iter::from_fn`, is serialized without ever holding its statements in memory.
+    /// Only the solvers and the variables seen so far are kept, to index the solvers and to detect unconstrained variables.
+    pub fn serialize<W: Write + Seek>(self, w: W) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(w, SerializeOptions::default())
+    }
+
+    /// serialize a program iterator like `serialize`, writing an index of the offsets of every `stride`-th constraint
+    /// A smaller stride makes `ProgEnum::try_read_from_constraint` skip fewer constraints at the cost of a larger index
+    pub fn serialize_with_index<W: Write + Seek>(
+        self,
+        w: W,
+        stride: usize,
+    ) -> Result<usize, SerializeError> {
+        if stride == 0 {
+            return Err(SerializeError::Invalid(
+                "The stride of a constraint index must be positive".into(),
+            ));
+        }
+
+        self.serialize_with_flags(
+            w,
+            SerializeOptions {
+                index_stride: Some(stride),
+                ..SerializeOptions::default()
+            },
+        )
+    }
+
+    /// serialize a program iterator like `serialize`, failing if it has fewer than `min_constraints` constraints
+    /// A program with no constraints is usually the result of a bug in the frontend which produced it.
+    /// On failure, the header is left blank so that the output cannot be read as a program.
+    pub fn serialize_strict<W: Write + Seek>(
+        self,
+        w: W,
+        min_constraints: usize,
+    ) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(
+            w,
+            SerializeOptions {
+                min_constraints,
+                ..SerializeOptions::default()
+            },
+        )
+    }
+
+    /// serialize a program iterator like `serialize`, compressing the constraints section with zstd
+    /// Statements are compressed as they are written, so the program is never held in memory
+    #[cfg(feature = "compression")]
+    pub fn serialize_compressed<W: Write + Seek>(self, w: W) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(
+            w,
+            SerializeOptions {
+                flags: COMPRESSED_CONSTRAINTS,
+                ..SerializeOptions::default()
+            },
+        )
+    }
+
+    /// serialize a program iterator like `serialize`, recording `source_hash` in the header
+    /// See `ModuleMap::source_hash` to compute it from the source files of the program
+    pub fn serialize_with_source_hash<W: Write + Seek>(
+        self,
+        w: W,
+        source_hash: [u8; 32],
+    ) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(
+            w,
+            SerializeOptions {
+                source_hash,
+                ..SerializeOptions::default()
+            },
+        )
+    }
+
+    /// serialize a program iterator like `serialize`, writing `metadata` to a section of key-value annotations
+    /// Annotations are written in the order of their keys, so that the output is reproducible
+    pub fn serialize_with_metadata<W: Write + Seek>(
+        self,
+        w: W,
+        metadata: &BTreeMap<String, String>,
+    ) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(
+            w,
+            SerializeOptions {
+                metadata: Some(metadata),
+                ..SerializeOptions::default()
+            },
+        )
+    }
+
+    /// serialize a program iterator like `serialize`, writing `types` to a section of the declared types of the inputs and outputs
+    /// The program itself only has field variables, so this lets verifiers present its inputs and outputs as they are declared in the source, see `ProgHeader::read_types`
+    pub fn serialize_with_types<W: Write + Seek>(
+        self,
+        w: W,
+        types: &Abi,
+    ) -> Result<usize, SerializeError> {
+        self.serialize_with_options(w, SerializeOptions::default().types(types))
+    }
+
+    /// serialize a program iterator like `serialize`, with `options` combining several of the other serialization functions
+    pub fn serialize_with_options<W: Write + Seek>(
+        self,
+        w: W,
+        options: SerializeOptions,
+    ) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(w, options)
+    }
+
+    /// serialize a program iterator like `serialize`, calling `on_progress` after every batch of constraints and once all statements are written
+    pub fn serialize_with_progress<W: Write + Seek>(
+        self,
+        w: W,
+        mut on_progress: impl FnMut(ProgressEvent),
+    ) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(
+            w,
+            SerializeOptions {
+                on_progress: Some(&mut on_progress),
+                ..SerializeOptions::default()
+            },
+        )
+    }
+
+    /// serialize a program iterator like `serialize`, handling the private variables which are never constrained according to `policy`
+    /// With `UnconstrainedPolicy::Warn`, `on_unconstrained` is called with these variables if there are any, and the program is still written successfully
+    pub fn serialize_with_unconstrained_policy<W: Write + Seek>(
+        self,
+        w: W,
+        policy: UnconstrainedPolicy,
+        mut on_unconstrained: impl FnMut(Vec<UnconstrainedVariable>, &ModuleMap),
+    ) -> Result<usize, SerializeError> {
+        self.serialize_with_flags(
+            w,
+            SerializeOptions {
+                unconstrained: policy,
+                on_unconstrained: Some(&mut on_unconstrained),
+                ..SerializeOptions::default()
+            },
+        )
+    }
+
+    /// serialize a program iterator like `serialize` to a stream which cannot seek, such as a pipe or a socket
+    /// The header comes first but is only known once all sections are written, so the whole file is written to memory first and then copied to `w`.
+    /// This holds the serialized program in memory, where `serialize` only keeps the solvers and the variables seen so far.
+    pub fn serialize_no_seek<W: Write>(self, mut w: W) -> Result<usize, SerializeError> {
+        let mut buffer = Cursor::new(vec![]);
+        let res = self.serialize(&mut buffer);
+
+        // the program is fully written even if some variables are unconstrained
+        if matches!(
+            res,
+            Ok(_) | Err(SerializeError::UnconstrainedVariables { .. })
+        ) {
+            w.write_all(buffer.get_ref())?;
+        }
+
+        res
+    }
+
+    /// count the constraints `serialize` would write, running the same checks without encoding anything
+    pub fn count_constraints(self) -> Result<usize, SerializeError> {
+        let mut solver_indexer: SolverIndexer<'ast, T> = SolverIndexer::default();
+        let mut unconstrained_variable_detector = UnconstrainedVariableDetector::new(&self);
+
+        let mut count = 0;
+
+        for s in self.statements {
+            if matches!(s, Statement::Constraint(..)) {
+                count += 1;
+            }
+            fold_statement(s, &mut solver_indexer, &mut unconstrained_variable_detector)?;
+        }
+
+        unconstrained_variable_detector
+            .finalize()
+            .map(|_| count)
+            .map_err(|variables| SerializeError::UnconstrainedVariables {
+                variables,
+                module_map: self.module_map,
+            })
+    }
+
+    /// count the constraints `serialize` would write like `count_constraints`, streaming the statements in constant memory
+    /// Unlike `count_constraints`, the solvers are not checked and unconstrained variables are not detected, as both require state which grows with the program.
+    pub fn count_constraints_streaming(self) -> usize {
+        self.statements
+            .into_iter()
+            .filter(|s| matches!(s, Statement::Constraint(..)))
+            .count()
+    }
+
+    fn serialize_with_flags<W: Write + Seek>(
+        self,
+        w: W,
+        options: SerializeOptions,
+    ) -> Result<usize, SerializeError> {
+        let SerializeOptions {
+            flags,
+            index_stride,
+            min_constraints,
+            source_hash,
+            metadata,
+            types,
+            mut on_progress,
+            unconstrained,
+            on_unconstrained,
+        } = options;
+
+        let mut w = ChecksumWriter::new(w);
+
+        // reserve bytes for the header
+        w.write_all(&[0u8; std::mem::size_of::<ProgHeader>()])?;
+
+        // write parameters section
+        let parameters = {
+            let mut section = Section::new(SectionType::Parameters);
+            section.set_offset(w.stream_position()?);
+            w.checksum();
+
+            serde_cbor::to_writer(&mut w, &self.arguments)?;
+
+            section.set_length(w.stream_position()? - section.offset);
+            section.set_checksum(w.checksum());
+            section
+        };
+
+        let mut unconstrained_variable_detector = UnconstrainedVariableDetector::new(&self);
+        // keep the solvers the directives of the program may already refer to
+        let mut solver_indexer = SolverIndexer::with_solvers(self.solvers);
+
+        let mut variable_counter = VariableCounter::default();
+        for a in &self.arguments {
+            <VariableCounter as Visitor<T>>::visit_argument(&mut variable_counter, a);
+        }
+
+        let mut index = index_stride.map(|stride| ConstraintIndex {
+            stride: stride as u64,
+            offsets: vec![],
+        });
+
+        // write constraints section
+        let (constraints, count) = {
+            let mut section = Section::new(SectionType::Constraints);
+            section.set_offset(w.stream_position()?);
+            w.checksum();
+
+            let statements = self.statements.into_iter();
+
+            let count = if flags & COMPRESSED_CONSTRAINTS == 0 {
+                write_statements(
+                    &mut w,
+                    statements,
+                    &mut solver_indexer,
+                    &mut unconstrained_variable_detector,
+                    &mut variable_counter,
+                    index.as_mut(),
+                    on_progress.as_deref_mut(),
+                )?
+            } else {
+                #[cfg(feature = "compression")]
+                {
+                    let mut encoder = zstd::stream::write::Encoder::new(&mut w, COMPRESSION_LEVEL)?;
+                    let count = write_statements(
+                        &mut encoder,
+                        statements,
+                        &mut solver_indexer,
+                        &mut unconstrained_variable_detector,
+                        &mut variable_counter,
+                        index.as_mut(),
+                        on_progress.as_deref_mut(),
+                    )?;
+                    encoder.finish()?;
+                    count
+                }
+                #[cfg(not(feature = "compression"))]
+                unreachable!(
+                    "compressed programs can only be written with the `compression` feature"
+                )
+            };
+
+            section.set_length(w.stream_position()? - section.offset);
+            section.set_checksum(w.checksum());
+            (section, count)
+        };
+
+        if count < min_constraints {
+            return Err(SerializeError::Invalid(format!(
+                "Expected at least {} constraints, found {}",
+                min_constraints, count
+            )));
+        }
+
+        // write solvers section
+        let solvers = {
+            let mut section = Section::new(SectionType::Solvers);
+            section.set_offset(w.stream_position()?);
+            w.checksum();
+
+            serde_cbor::to_writer(&mut w, &solver_indexer.solvers)?;
+
+            section.set_length(w.stream_position()? - section.offset);
+            section.set_checksum(w.checksum());
+            section
+        };
+
+        // write module map section
+        let module_map = {
+            let mut section = Section::new(SectionType::Modules);
+            section.set_offset(w.stream_position()?);
+            w.checksum();
+
+            serde_cbor::to_writer(&mut w, &self.module_map)?;
+
+            section.set_length(w.stream_position()? - section.offset);
+            section.set_checksum(w.checksum());
+            section
+        };
+
+        // write constraint index section, if requested
+        let constraint_index = index
+            .map(|index| -> Result<_, SerializeError> {
+                let mut section = Section::new(SectionType::ConstraintIndex);
+                section.set_offset(w.stream_position()?);
+                w.checksum();
+
+                serde_cbor::to_writer(&mut w, &index)?;
+
+                section.set_length(w.stream_position()? - section.offset);
+                section.set_checksum(w.checksum());
+                Ok(section)
+            })
+            .transpose()?;
+
+        // write return slots section, if the program has any
+        let return_slots = match self.returns.is_empty() {
+            true => None,
+            false => Some(write_return_slots(&mut w, &self.returns)?),
+        };
+
+        // write embeds section, if they are known
+        let embeds = match self.embeds_used.is_empty() {
+            true => None,
+            false => Some(write_embeds_used(&mut w, &self.embeds_used)?),
+        };
+
+        // write metadata section, if there are annotations
+        let metadata = match metadata.filter(|m| !m.is_empty()) {
+            None => None,
+            Some(metadata) => Some(write_metadata(&mut w, metadata)?),
+        };
+
+        // write types section, if they are known
+        let types = match types {
+            None => None,
+            Some(types) => Some(write_types(&mut w, types)?),
+        };
+
+        let header = ProgHeader {
+            magic: *ZOKRATES_MAGIC,
+            version: *FILE_VERSION,
+            curve_id: T::id(),
+            constraint_count: count as u32,
+            return_count: self.return_count as u32,
+            flags,
+            variable_count: Some(variable_counter.count()),
+            sections: [parameters, constraints, solvers, module_map],
+            constraint_index,
+            return_slots,
+            embeds,
+            source_hash,
+            metadata,
+            types,
+        };
+
+        // rewind to write the header
+        w.rewind()?;
+        header.write(&mut w)?;
+
+        match (unconstrained_variable_detector.finalize(), unconstrained) {
+            (Ok(()), _) | (Err(_), UnconstrainedPolicy::Ignore) => Ok(count),
+            (Err(variables), UnconstrainedPolicy::Warn) => {
+                if let Some(on_unconstrained) = on_unconstrained {
+                    on_unconstrained(variables, &self.module_map);
+                }
+                Ok(count)
+            }
+            (Err(variables), UnconstrainedPolicy::Error) => {
+                Err(SerializeError::UnconstrainedVariables {
+                    variables,
+                    module_map: self.module_map,
+                })
+            }
+        }
+    }
+}
+
+/// write `returns` to a new section at the current position of `w`
+fn write_return_slots<W: Write + Seek>(
+    w: &mut ChecksumWriter<W>,
+    returns: &[ReturnSlot],
+) -> Result<Section, SerializeError> {
+    let mut section = Section::new(SectionType::ReturnSlots);
+    section.set_offset(w.stream_position()?);
+    w.checksum();
+
+    serde_cbor::to_writer(&mut *w, returns)?;
+
+    section.set_length(w.stream_position()? - section.offset);
+    section.set_checksum(w.checksum());
+    Ok(section)
+}
+
+fn deserialize_return_slots<R: Read>(r: R) -> Result<Vec<ReturnSlot>, String> {
+    let mut p = serde_cbor::Deserializer::from_reader(r);
+    Vec::deserialize(&mut p).map_err(|_| String::from("Cannot read return slots"))
+}
+
+/// write `embeds` to a new section at the current position of `w`
+fn write_embeds_used<W: Write + Seek>(
+    w: &mut ChecksumWriter<W>,
+    embeds: &BTreeSet<FlatEmbed>,
+) -> Result<Section, SerializeError> {
+    let mut section = Section::new(SectionType::Embeds);
+    section.set_offset(w.stream_position()?);
+    w.checksum();
+
+    serde_cbor::to_writer(&mut *w, embeds)?;
+
+    section.set_length(w.stream_position()? - section.offset);
+    section.set_checksum(w.checksum());
+    Ok(section)
+}
+
+/// Embeds behind a feature which is disabled in this build cannot be read
+fn deserialize_embeds_used<R: Read>(r: R) -> Result<BTreeSet<FlatEmbed>, String> {
+    let mut p = serde_cbor::Deserializer::from_reader(r);
+    BTreeSet::deserialize(&mut p).map_err(|_| String::from("Cannot read embeds"))
+}
+
+/// write `metadata` to a new section at the current position of `w`
+fn write_metadata<W: Write + Seek>(
+    w: &mut ChecksumWriter<W>,
+    metadata: &BTreeMap<String, String>,
+) -> Result<Section, SerializeError> {
+    let mut section = Section::new(SectionType::Metadata);
+    section.set_offset(w.stream_position()?);
+    w.checksum();
+
+    serde_cbor::to_writer(&mut *w, metadata)?;
+
+    section.set_length(w.stream_position()? - section.offset);
+    section.set_checksum(w.checksum());
+    Ok(section)
+}
+
+fn deserialize_metadata<R: Read>(r: R) -> Result<BTreeMap<String, String>, String> {
+    let mut p = serde_cbor::Deserializer::from_reader(r);
+    BTreeMap::deserialize(&mut p).map_err(|_| String::from("Cannot read metadata"))
+}
+
+/// write `types` to a new section at the current position of `w`
+fn write_types<W: Write + Seek>(
+    w: &mut ChecksumWriter<W>,
+    types: &Abi,
+) -> Result<Section, SerializeError> {
+    let mut section = Section::new(SectionType::Types);
+    section.set_offset(w.stream_position()?);
+    w.checksum();
+
+    serde_cbor::to_writer(&mut *w, types)?;
+
+    section.set_length(w.stream_position()? - section.offset);
+    section.set_checksum(w.checksum());
+    Ok(section)
+}
+
+fn deserialize_types<R: Read>(r: R) -> Result<Abi, String> {
+    let mut p = serde_cbor::Deserializer::from_reader(r);
+    Abi::deserialize(&mut p).map_err(|_| String::from("Cannot read types"))
+}
+
+/// write `statements` to `w`, indexing solvers and tracking unconstrained variables and the variable count on the way
+/// If `index` is provided, the offset of every `index.stride`-th constraint is recorded in it
+/// Returns the number of constraints written
+fn write_statements<'ast, T: Field, W: Write>(
+    w: W,
+    statements: impl Iterator<Item = Statement<'ast, T>>,
+    solver_indexer: &mut SolverIndexer<'ast, T>,
+    unconstrained_variable_detector: &mut UnconstrainedVariableDetector,
+    variable_counter: &mut VariableCounter,
+    mut index: Option<&mut ConstraintIndex>,
+    mut on_progress: Option<&mut (dyn FnMut(ProgressEvent) + '_)>,
+) -> Result<usize, SerializeError> {
+    let mut w = PositionWriter::new(w);
+    let mut count: u64 = 0;
+
+    for s in statements {
+        let is_constraint = matches!(s, Statement::Constraint(..));
+        if is_constraint {
+            if let Some(index) = index.as_mut() {
+                if count % index.stride == 0 {
+                    index.offsets.push(w.position);
+                }
+            }
+            count += 1;
+        }
+        for s in fold_statement(s, solver_indexer, unconstrained_variable_detector)? {
+            variable_counter.visit_statement(&s);
+            serde_cbor::to_writer(&mut w, &s)?;
+        }
+        if let Some(on_progress) = on_progress.as_mut() {
+            if is_constraint && count % PROGRESS_BATCH == 0 {
+                on_progress(ProgressEvent {
+                    constraints: count as usize,
+                    bytes: w.position,
+                });
+            }
+        }
+    }
+
+    if let Some(on_progress) = on_progress {
+        on_progress(ProgressEvent {
+            constraints: count as usize,
+            bytes: w.position,
+        });
+    }
+
+    Ok(count as usize)
+}
+
+/// A writer which keeps track of the number of bytes written through it
+struct PositionWriter<W> {
+    inner: W,
+    position: u64,
+}
+
+impl<W> PositionWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, position: 0 }
+    }
+}
+
+impl<W: Write> Write for PositionWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Counts the witness variables of a program: `~one`, the outputs and the intermediate variables
+/// Outputs and intermediate variables are each assumed to be numbered from 0, so that the count is the largest index of each plus one.
+#[derive(Default)]
+pub(super) struct VariableCounter {
+    outputs: usize,
+    intermediates: usize,
+}
+
+impl VariableCounter {
+    /// a counter which already accounts for `variable_count` variables, `return_count` of them being outputs
+    fn resume(variable_count: u32, return_count: u32) -> Self {
+        Self {
+            outputs: return_count as usize,
+            intermediates: (variable_count as usize).saturating_sub(1 + return_count as usize),
+        }
+    }
+
+    pub(super) fn count(&self) -> u32 {
+        (1 + self.outputs + self.intermediates) as u32
+    }
+}
+
+impl<T: Field> Visitor<T> for VariableCounter {
+    fn visit_variable(&mut self, v: &Variable) {
+        match v.id {
+            i if i > 0 => self.intermediates = self.intermediates.max(i as usize),
+            i if i < 0 => self.outputs = self.outputs.max(-i as usize),
+            _ => {}
+        }
+    }
+}
+
+/// run `s` through the folds applied before serialization
+fn fold_statement<'ast, T: Field>(
+    s: Statement<'ast, T>,
+    solver_indexer: &mut SolverIndexer<'ast, T>,
+    unconstrained_variable_detector: &mut UnconstrainedVariableDetector,
+) -> Result<Vec<Statement<'ast, T>>, SerializeError> {
+    use super::folder::Folder;
+
+    let s: Vec<Statement<T>> = solver_indexer
+        .fold_statement(s)
+        .into_iter()
+        .flat_map(|s| unconstrained_variable_detector.fold_statement(s))
+        .collect();
+
+    match solver_indexer.error.take() {
+        Some(e) => Err(e.into()),
+        None => Ok(s),
+    }
+}
+
+/// Appends statements to the constraints section of a serialized program, in place
+///
+/// The solvers, module map, return slots, embeds, metadata and types sections, which follow the constraints section, are kept in memory and written back by `close`.
+/// Unconstrained variables are not detected, as the statements may be appended in several stages.
+pub struct ProgAppender<'ast, T, F> {
+    w: ChecksumWriter<F>,
+    header: ProgHeader,
+    solver_indexer: SolverIndexer<'ast, T>,
+    module_map: ModuleMap,
+    returns: Vec<ReturnSlot>,
+    embeds_used: BTreeSet<FlatEmbed>,
+    metadata: BTreeMap<String, String>,
+    types: Option<Abi>,
+    count: usize,
+    variable_counter: Option<VariableCounter>,
+}
+
+impl<'ast, T: Field, F: Read + Write + Seek> ProgAppender<'ast, T, F> {
+    /// open the program in `f`, checking its header and the integrity of its sections
+    pub fn open(mut f: F) -> Result<Self, DynamicError> {
+        let header = ProgHeader::read(&mut f).map_err(|_| String::from("Invalid header"))?;
+
+        check_header(&header)?;
+
+        if header.is_compressed() {
+            return Err("Cannot append to a program with a compressed constraints section".into());
+        }
+
+        if header.is_chunked() {
+            return Err(
+                "Cannot append to a chunked program, use `ChunkedProgWriter::resume` instead"
+                    .into(),
+            );
+        }
+
+        header.verify(&mut f)?;
+
+        let (solver_indexer, module_map, returns, embeds_used) = {
+            let prog = ProgEnum::try_read::<T>(&mut f, &header)?;
+            (
+                SolverIndexer::with_solvers(prog.solvers),
+                prog.module_map,
+                prog.returns,
+                prog.embeds_used,
+            )
+        };
+
+        let metadata = header.read_metadata(&mut f)?;
+        let types = header.read_types(&mut f)?;
+
+        // resume the digest of the constraints section from its end
+        let constraints = &header.sections[1];
+        f.seek(SeekFrom::Start(constraints.offset + constraints.length))?;
+        let mut w = ChecksumWriter::new(f);
+        w.hasher = crc32fast::Hasher::new_with_initial(constraints.checksum.unwrap_or_default());
+
+        let variable_counter = header
+            .variable_count
+            .map(|variable_count| VariableCounter::resume(variable_count, header.return_count));
+
+        Ok(Self {
+            w,
+            header,
+            solver_indexer,
+            module_map,
+            returns,
+            embeds_used,
+            metadata,
+            types,
+            count: 0,
+            variable_counter,
+        })
+    }
+
+    /// append `statements` to the constraints section, returning the number of constraints appended
+    pub fn append<I: IntoIterator<Item = Statement<'ast, T>>>(
+        &mut self,
+        statements: I,
+    ) -> Result<usize, DynamicError> {
+        use super::folder::Folder;
+
+        let mut count = 0;
+
+        for s in statements {
+            if matches!(s, Statement::Constraint(..)) {
+                count += 1;
+            }
+            let s = self.solver_indexer.fold_statement(s);
+            if let Some(e) = self.solver_indexer.error.take() {
+                return Err(e.into());
+            }
+            for s in s {
+                if let Some(variable_counter) = self.variable_counter.as_mut() {
+                    <VariableCounter as Visitor<T>>::visit_statement(variable_counter, &s);
+                }
+                serde_cbor::to_writer(&mut self.w, &s)?;
+            }
+        }
+
+        self.count += count;
+
+        Ok(count)
+    }
+
+    /// write back the sections following the constraints and the updated header, returning the latter
+    pub fn close(mut self) -> Result<ProgHeader, DynamicError> {
+        let w = &mut self.w;
+        let extended = has_extended_header(&self.header.version);
+
+        let constraints = &mut self.header.sections[1];
+        constraints.set_length(w.stream_position()? - constraints.offset);
+        let checksum = w.checksum();
+        if extended {
+            constraints.set_checksum(checksum);
+        }
+
+        let solvers = &mut self.header.sections[2];
+        solvers.set_offset(w.stream_position()?);
+        serde_cbor::to_writer(&mut *w, &self.solver_indexer.solvers)?;
+        solvers.set_length(w.stream_position()? - solvers.offset);
+        let checksum = w.checksum();
+        if extended {
+            solvers.set_checksum(checksum);
+        }
+
+        let module_map = &mut self.header.sections[3];
+        module_map.set_offset(w.stream_position()?);
+        serde_cbor::to_writer(&mut *w, &self.module_map)?;
+        module_map.set_length(w.stream_position()? - module_map.offset);
+        let checksum = w.checksum();
+        if extended {
+            module_map.set_checksum(checksum);
+        }
+
+        // the return slots section follows the module map, so it was overwritten as well
+        self.header.return_slots = match self.returns.is_empty() {
+            true => None,
+            false => {
+                let mut section = write_return_slots(w, &self.returns)?;
+                if !extended {
+                    section.checksum = None;
+                }
+                Some(section)
+            }
+        };
+
+        // as are the embeds, which follow the return slots
+        self.header.embeds = match self.embeds_used.is_empty() {
+            true => None,
+            false => {
+                let mut section = write_embeds_used(w, &self.embeds_used)?;
+                if !extended {
+                    section.checksum = None;
+                }
+                Some(section)
+            }
+        };
+
+        // and the metadata, which follow the embeds
+        self.header.metadata = match self.metadata.is_empty() {
+            true => None,
+            false => {
+                let mut section = write_metadata(w, &self.metadata)?;
+                if !extended {
+                    section.checksum = None;
+                }
+                Some(section)
+            }
+        };
+
+        // and the types, which follow the metadata
+        self.header.types = match &self.types {
+            None => None,
+            Some(types) => {
+                let mut section = write_types(w, types)?;
+                if !extended {
+                    section.checksum = None;
+                }
+                Some(section)
+            }
+        };
+
+        self.header.constraint_count += self.count as u32;
+        // the index does not cover the appended constraints, and its section was overwritten
+        self.header.constraint_index = None;
+        self.header.variable_count = self.variable_counter.as_ref().map(VariableCounter::count);
+
+        // rewind to write the header
+        w.rewind()?;
+        self.header.write(&mut *w)?;
+        w.flush()?;
+
+        Ok(self.header)
+    }
+}
+
+/// A reader over the constraints section, decompressing it or reading it frame by frame on the fly if needed
+pub enum ConstraintsReader<R: Read> {
+    Plain(R),
+    #[cfg(feature = "compression")]
+    Compressed(DecompressionLimit<zstd::stream::read::Decoder<'static, std::io::BufReader<R>>>),
+    Chunked(ChunkReader<R>),
+}
+
+impl<R: Read> ConstraintsReader<R> {
+    /// read the constraints section of the program described by `header` from `r`, which is expected to end with the section
+    /// A compressed section fails to read once it decompresses to more bytes than `limits` allow for it.
+    #[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+    pub(super) fn new(r: R, header: &ProgHeader, limits: &ReadLimits) -> std::io::Result<Self> {
+        if header.is_chunked() {
+            return Ok(ConstraintsReader::Chunked(ChunkReader::new(r)));
+        }
+
+        match header.is_compressed() {
+            false => Ok(ConstraintsReader::Plain(r)),
+            #[cfg(feature = "compression")]
+            // the section is a single frame, followed by the bytes of the next section
+            true => Ok(ConstraintsReader::Compressed(DecompressionLimit {
+                inner: zstd::stream::read::Decoder::new(r)?.single_frame(),
+                remaining: limits.max_constraints_length(header),
+            })),
+            #[cfg(not(feature = "compression"))]
+            true => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "compressed programs can only be read with the `compression` feature",
+            )),
+        }
+    }
+}
+
+impl<R: Read> Read for ConstraintsReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            ConstraintsReader::Plain(r) => r.read(buf),
+            #[cfg(feature = "compression")]
+            ConstraintsReader::Compressed(r) => r.read(buf),
+            ConstraintsReader::Chunked(r) => r.read(buf),
+        }
+    }
+}
+
+/// A reader failing once more than `remaining` bytes are read from `inner`, bounding the decompressed length of the constraints section
+#[cfg(feature = "compression")]
+pub struct DecompressionLimit<R> {
+    inner: R,
+    remaining: u64,
+}
+
+#[cfg(feature = "compression")]
+impl<R: Read> Read for DecompressionLimit<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // read one byte past the limit, so that sections decompressing to exactly the limit are accepted
+        let max = usize::try_from(self.remaining.saturating_add(1)).unwrap_or(usize::MAX);
+        let max = buf.len().min(max);
+        let n = self.inner.read(&mut buf[..max])?;
+
+        match self.remaining.checked_sub(n as u64) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                Ok(n)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Constraints section decompresses to more bytes than the limit",
+            )),
+        }
+    }
+}
+
+/// A stream of statements which silently ends on the first deserialization error or statement exceeding its `ReadLimits`
+pub struct UnwrappedStreamDeserializer<'de, R, T> {
+    s: FallibleStreamDeserializer<'de, R, T>,
+}
+
+impl<'de, R: serde_cbor::de::Read<'de>, T: Field> Iterator
+    for UnwrappedStreamDeserializer<'de, R, Statement<'de, T>>
+{
+    type Item = Statement<'de, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.s.next().and_then(|v| v.ok())
+    }
+}
+
+impl<'de, R, T> UnwrappedStreamDeserializer<'de, R, T> {
+    pub(super) fn new(s: StreamDeserializer<'de, R, T>, limits: &ReadLimits) -> Self {
+        Self {
+            s: FallibleStreamDeserializer {
+                s,
+                limits: *limits,
+                count: 0,
+                failed: false,
+            },
+        }
+    }
+
+    /// turn this into an iterator which yields deserialization errors instead of silently ending on them
+    pub fn into_fallible(self) -> FallibleStreamDeserializer<'de, R, T> {
+        self.s
+    }
+}
+
+/// A stream deserializer which yields deserialization errors, ending after the first one
+/// Statements exceeding `limits` are reported as errors, see `ReadLimits::check_statement`.
+pub struct FallibleStreamDeserializer<'de, R, T> {
+    s: StreamDeserializer<'de, R, T>,
+    limits: ReadLimits,
+    /// the number of statements read so far
+    count: u64,
+    failed: bool,
+}
+
+impl<'de, R: serde_cbor::de::Read<'de>, T: Field> Iterator
+    for FallibleStreamDeserializer<'de, R, Statement<'de, T>>
+{
+    type Item = Result<Statement<'de, T>, serde_cbor::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let next = self
+            .s
+            .next()
+            .map(|statement| -> Result<_, serde_cbor::Error> {
+                let statement = statement?;
+                self.count += 1;
+
+                self.limits
+                    .check_statement(self.count, &statement)
+                    .map_err(<serde_cbor::Error as serde::de::Error>::custom)?;
+
+                Ok(statement)
+            });
+        self.failed = matches!(next, Some(Err(_)));
+        next
+    }
+}
+
+impl<'de, R: Read + Seek>
+    ProgEnum<
+        'de,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+            Statement<'de, Bls12_381Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+            Statement<'de, Bn128Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+            Statement<'de, Bls12_377Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+            Statement<'de, Bw6_761Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+            Statement<'de, PallasField>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+            Statement<'de, VestaField>,
+        >,
+    >
+{
+    /// read a program over `T` from `r`, given its header
+    /// Unlike `deserialize`, this does not check the header nor verify the section checksums
+    pub fn try_read<T: Field>(
+        r: R,
+        header: &ProgHeader,
+    ) -> Result<
+        ProgIterator<
+            'de,
+            T,
+            UnwrappedStreamDeserializer<
+                'de,
+                serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+                Statement<'de, T>,
+            >,
+        >,
+        String,
+    > {
+        Self::try_read_with_limits(r, header, &ReadLimits::default())
+    }
+
+    /// read a program over `T` from `r` like `try_read`, rejecting it if it exceeds `limits`
+    pub fn try_read_with_limits<T: Field>(
+        r: R,
+        header: &ProgHeader,
+        limits: &ReadLimits,
+    ) -> Result<
+        ProgIterator<
+            'de,
+            T,
+            UnwrappedStreamDeserializer<
+                'de,
+                serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+                Statement<'de, T>,
+            >,
+        >,
+        String,
+    > {
+        Self::try_read_at(r, header, 0, limits)
+    }
+
+    /// read a program over `T` from `r` like `try_read`, with statements starting at constraint `n`
+    /// The constraint index is used to skip to the closest preceding indexed constraint, if the program has one.
+    /// The remaining constraints, or all of them without an index, are skipped by reading through them.
+    pub fn try_read_from_constraint<T: Field>(
+        mut r: R,
+        header: &ProgHeader,
+        n: usize,
+    ) -> Result<ProgIterator<'de, T, impl IntoIterator<Item = Statement<'de, T>>>, String> {
+        let (first, offset) = match header.read_constraint_index(r.by_ref())? {
+            Some(index) if !index.offsets.is_empty() => {
+                let i = ((n as u64 / index.stride) as usize).min(index.offsets.len() - 1);
+                ((i as u64 * index.stride) as usize, index.offsets[i])
+            }
+            _ => (0, 0),
+        };
+
+        Ok(
+            Self::try_read_at(r, header, offset, &ReadLimits::default())?
+                .seek_to_constraint(n - first),
+        )
+    }
+
+    /// read a program over `T` from `r`, with statements starting `offset` bytes into the constraints section
+    /// Each section is read within its declared length, which is checked against `limits` first.
+    fn try_read_at<T: Field>(
+        mut r: R,
+        header: &ProgHeader,
+        offset: u64,
+        limits: &ReadLimits,
+    ) -> Result<
+        ProgIterator<
+            'de,
+            T,
+            UnwrappedStreamDeserializer<
+                'de,
+                serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+                Statement<'de, T>,
+            >,
+        >,
+        String,
+    > {
+        if header.curve_id != T::id() {
+            return Err(match (header.curve_name(), header.field_type_name()) {
+                (Some(curve), Some(field_type)) => format!(
+                    "Expected a program over curve `{}`, found a program over curve `{}`, which should be read with `{}`",
+                    T::name(),
+                    curve,
+                    field_type
+                ),
+                _ => format!(
+                    "Expected a program over curve `{}`, found a program over an unknown curve",
+                    T::name()
+                ),
+            });
+        }
+
+        limits.check(header)?;
+        header.check_bounds(r.by_ref())?;
+
+        let parameters = {
+            let section = &header.sections[0];
+            r.seek(std::io::SeekFrom::Start(section.offset))
+                .map_err(|e| e.to_string())?;
+
+            deserialize_parameters(r.by_ref().take(section.length), &header.version)?
+        };
+        limits.check_length("parameters", parameters.len())?;
+
+        let solvers: Vec<_> = {
+            let section = &header.sections[2];
+            r.seek(std::io::SeekFrom::Start(section.offset))
+                .map_err(|e| e.to_string())?;
+
+            let mut p = serde_cbor::Deserializer::from_reader(r.by_ref().take(section.length));
+            Vec::deserialize(&mut p).map_err(|_| String::from("Cannot read solvers"))?
+        };
+        limits.check_length("solvers", solvers.len())?;
+
+        let module_map = {
+            let section = &header.sections[3];
+            r.seek(std::io::SeekFrom::Start(section.offset))
+                .map_err(|e| e.to_string())?;
+
+            let mut p = serde_cbor::Deserializer::from_reader(r.by_ref().take(section.length));
+            ModuleMap::deserialize(&mut p).map_err(|_| String::from("Cannot read module map"))?
+        };
+
+        let returns = header.read_return_slots(r.by_ref())?;
+        let embeds_used = header.read_embeds_used(r.by_ref())?;
+
+        let statements_deserializer = {
+            let section = &header.sections[1];
+
+            // offsets into a compressed or chunked section can only be reached once decoded
+            let (start, skip) = match header.is_compressed() || header.is_chunked() {
+                false => (offset.min(section.length), 0),
+                true => (0, offset),
+            };
+
+            r.seek(std::io::SeekFrom::Start(section.offset + start))
+                .map_err(|e| e.to_string())?;
+
+            // bound the reader to the section, so that the stream ends with it
+            let mut r = ConstraintsReader::new(r.take(section.length - start), header, limits)
+                .map_err(|e| e.to_string())?;
+            std::io::copy(&mut r.by_ref().take(skip), &mut std::io::sink())
+                .map_err(|e| e.to_string())?;
+
+            let p = serde_cbor::Deserializer::from_reader(r);
+            let s = p.into_iter::<Statement<T>>();
+
+            UnwrappedStreamDeserializer::new(s, limits)
+        };
+
+        Ok(ProgIterator {
+            returns,
+            embeds_used,
+            ..ProgIterator::new(
+                parameters,
+                statements_deserializer,
+                header.return_count as usize,
+                module_map,
+                solvers,
+            )
+        })
+    }
+
+    /// read a program over `T` from `r`, given its header
+    /// Panics if the program cannot be read, see `try_read` for a fallible version
+    pub fn read<T: Field>(
+        r: R,
+        header: &ProgHeader,
+    ) -> ProgIterator<
+        'de,
+        T,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Take<R>>>,
+            Statement<'de, T>,
+        >,
+    > {
+        Self::try_read(r, header).unwrap()
+    }
+
+    pub fn deserialize(mut r: R) -> Result<Self, String> {
+        let header = ProgHeader::read(&mut r).map_err(|_| String::from("Invalid header"))?;
+
+        check_header(&header)?;
+
+        Self::read_verified(r, &header, &ReadLimits::default())
+    }
+
+    /// deserialize a program like `deserialize`, rejecting it before reading any section if its header exceeds `limits`
+    /// This is meant for programs from untrusted sources, which could otherwise declare sections large enough to exhaust memory.
+    /// The statements are checked against `limits` as they are read, see `ReadLimits::check_statement`.
+    pub fn deserialize_with_limits(mut r: R, limits: &ReadLimits) -> Result<Self, String> {
+        let header = ProgHeader::read(&mut r).map_err(|_| String::from("Invalid header"))?;
+
+        check_header(&header)?;
+        limits.check(&header)?;
+        limits.check_decompressed(r.by_ref(), &header)?;
+
+        Self::read_verified(r, &header, limits)
+    }
+
+    /// verify the sections of the program described by `header` and read it over the field it declares
+    fn read_verified(mut r: R, header: &ProgHeader, limits: &ReadLimits) -> Result<Self, String> {
+        header.verify(&mut r)?;
+
+        match header.curve_id {
+            m if m == Bls12_381Field::id() => Ok(ProgEnum::Bls12_381Program(
+                Self::try_read_with_limits(r, header, limits)?,
+            )),
+            m if m == Bn128Field::id() => Ok(ProgEnum::Bn128Program(Self::try_read_with_limits(
+                r, header, limits,
+            )?)),
+            m if m == Bls12_377Field::id() => Ok(ProgEnum::Bls12_377Program(
+                Self::try_read_with_limits(r, header, limits)?,
+            )),
+            m if m == Bw6_761Field::id() => Ok(ProgEnum::Bw6_761Program(
+                Self::try_read_with_limits(r, header, limits)?,
+            )),
+            m if m == PallasField::id() => Ok(ProgEnum::PallasProgram(Self::try_read_with_limits(
+                r, header, limits,
+            )?)),
+            m if m == VestaField::id() => Ok(ProgEnum::VestaProgram(Self::try_read_with_limits(
+                r, header, limits,
+            )?)),
+            _ => Err(String::from("Unknown curve identifier")),
+        }
+    }
+}
+
+/// Bounds on the size of a program read from an untrusted source, see `ProgEnum::deserialize_with_limits`
+/// The lengths declared in the header are checked before any section is read, and each section is decoded within its declared length.
+/// The decoded collections and the number of statements are bounded as well, so that a section cannot expand to an unbounded allocation.
+/// A compressed constraints section fails to read once its decompressed length exceeds the limit of its compressed length.
+/// Every reader of this module applies the default limits, which accept any program ZoKrates compiles in practice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadLimits {
+    /// the maximum number of constraints a program may declare, 2^28 by default
+    pub max_constraints: u32,
+    /// the maximum length in bytes of the constraints section per declared constraint, 64 KiB by default
+    /// For a program declaring `n` constraints, the constraints section and the constraint index may span `max(n, 1) * max_bytes_per_constraint` bytes.
+    pub max_bytes_per_constraint: u64,
+    /// the maximum length in bytes of the parameters, solvers and module map sections, as well as the optional sections, 64 MiB by default
+    pub max_section_length: u64,
+    /// the maximum number of statements read from the constraints section, 2^30 by default
+    pub max_statements: u64,
+    /// the maximum number of elements of a decoded collection, such as the parameters, the solvers or the terms of a linear combination, 2^24 by default
+    pub max_collection_length: usize,
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self {
+            max_constraints: 1 << 28,
+            max_bytes_per_constraint: 1 << 16,
+            max_section_length: 1 << 26,
+            max_statements: 1 << 30,
+            max_collection_length: 1 << 24,
+        }
+    }
+}
+
+impl ReadLimits {
+    /// check the number of constraints and the section lengths declared in `header` against these limits
+    pub fn check(&self, header: &ProgHeader) -> Result<(), String> {
+        if header.constraint_count > self.max_constraints {
+            return Err(format!(
+                "Program declares {} constraints, which exceeds the limit of {}",
+                header.constraint_count, self.max_constraints
+            ));
+        }
+
+        let max_constraints_length = self.max_constraints_length(header);
+
+        let optional = [
+            &header.constraint_index,
+            &header.return_slots,
+            &header.embeds,
+            &header.metadata,
+            &header.types,
+        ];
+
+        for section in header.sections.iter().chain(optional.into_iter().flatten()) {
+            let limit = match section.ty {
+                SectionType::Constraints | SectionType::ConstraintIndex => max_constraints_length,
+                _ => self.max_section_length,
+            };
+
+            if section.length > limit {
+                return Err(format!(
+                    "{:?} section is {} bytes long, which exceeds the limit of {} bytes",
+                    section.ty, section.length, limit
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// check the decompressed length of the constraints section of the program in `r` against these limits, leaving the position of `r` unchanged
+    /// Compressed sections are decoded without being kept in memory, so that a small section cannot expand to an unbounded number of statements.
+    pub fn check_decompressed<R: Read + Seek>(
+        &self,
+        mut r: R,
+        header: &ProgHeader,
+    ) -> Result<(), String> {
+        if !header.is_compressed() {
+            return Ok(());
+        }
+
+        let limit = self.max_constraints_length(header);
+        let section = &header.sections[1];
+
+        let position = r.stream_position().map_err(|e| e.to_string())?;
+        r.seek(SeekFrom::Start(section.offset))
+            .map_err(|e| e.to_string())?;
+
+        let decoded = ConstraintsReader::new(r.by_ref().take(section.length), header, self)
+            .and_then(|mut decoded| std::io::copy(&mut decoded, &mut std::io::sink()))
+            .map_err(|e| format!("Cannot decompress constraints: {}", e));
+
+        r.seek(SeekFrom::Start(position))
+            .map_err(|e| e.to_string())?;
+
+        decoded.map(|_| ())
+    }
+
+    /// check the length of a decoded collection of `what` against these limits
+    pub fn check_length(&self, what: &str, length: usize) -> Result<(), String> {
+        match length > self.max_collection_length {
+            true => Err(format!(
+                "Program has {} {}, which exceeds the limit of {}",
+                length, what, self.max_collection_length
+            )),
+            false => Ok(()),
+        }
+    }
+
+    /// check the `n`-th statement read from a constraints section against these limits
+    /// Both the number of statements and the lengths of the collections of `statement` are bounded.
+    pub fn check_statement<T>(&self, n: u64, statement: &Statement<T>) -> Result<(), String> {
+        if n > self.max_statements {
+            return Err(format!(
+                "Program has more than {} statements, which exceeds the limit",
+                self.max_statements
+            ));
+        }
+
+        self.check_collections(statement)
+    }
+
+    fn check_collections<T>(&self, statement: &Statement<T>) -> Result<(), String> {
+        match statement {
+            Statement::Block(s) => {
+                self.check_length("statements in a block", s.inner.len())?;
+                s.inner.iter().try_for_each(|s| self.check_collections(s))
+            }
+            Statement::Constraint(s) => {
+                self.check_length("terms in a linear combination", s.quad.left.value.len())?;
+                self.check_length("terms in a linear combination", s.quad.right.value.len())?;
+                self.check_length("terms in a linear combination", s.lin.value.len())
+            }
+            Statement::Directive(s) => {
+                self.check_length("directive inputs", s.inputs.len())?;
+                self.check_length("directive outputs", s.outputs.len())?;
+                s.inputs.iter().try_for_each(|q| {
+                    self.check_length("terms in a linear combination", q.left.value.len())?;
+                    self.check_length("terms in a linear combination", q.right.value.len())
+                })
+            }
+            Statement::Log(s) => {
+                self.check_length("logged expressions", s.expressions.len())?;
+                s.expressions.iter().try_for_each(|(_, e)| {
+                    self.check_length("logged expressions", e.len())?;
+                    e.iter().try_for_each(|l| {
+                        self.check_length("terms in a linear combination", l.value.len())
+                    })
+                })
+            }
+        }
+    }
+
+    /// the maximum length of the constraints section and the constraint index of a program described by `header`
+    fn max_constraints_length(&self, header: &ProgHeader) -> u64 {
+        (header.constraint_count.max(1) as u64).saturating_mul(self.max_bytes_per_constraint)
+    }
+}
+
+pub(super) fn check_header(header: &ProgHeader) -> Result<(), String> {
+    // Check the magic number, `ZOK`
+    if &header.magic != ZOKRATES_MAGIC {
+        return Err("Invalid magic number".to_string());
+    }
+
+    // Check the file version
+    if &header.version > FILE_VERSION {
+        return Err(format!(
+            "Invalid file version {}, please update ZoKrates to read this program",
+            header.version[0]
+        ));
+    }
+
+    if !is_compatible_version(header.version) {
+        return Err(format!(
+            "Invalid file version {}, please recompile this program with a newer version of ZoKrates",
+            header.version[0]
+        ));
+    }
+
+    // Check the flags
+    if header.flags & !KNOWN_FLAGS != 0 {
+        return Err("Unknown header flags".to_string());
+    }
+
+    if header.is_incomplete() {
+        return Err(
+            "Program is incomplete, as its chunked serialization was never finalized".to_string(),
+        );
+    }
+
+    if header.is_compressed() && header.is_chunked() {
+        return Err("Program cannot be both compressed and chunked".to_string());
+    }
+
+    if header.is_compressed() && !cfg!(feature = "compression") {
+        return Err(
+            "Program is compressed, which requires the `compression` feature to be read"
+                .to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+/// A reader which keeps track of its position in the underlying stream, so that sections can be located without `Seek`
+struct StreamReader<R> {
+    inner: R,
+    position: u64,
+}
+
+impl<R: Read> StreamReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    /// move forward to `offset` by discarding bytes, failing if `offset` was already passed
+    fn skip_to(&mut self, offset: u64) -> Result<(), String> {
+        if offset < self.position {
+            return Err(format!(
+                "Section at offset {} is out of order (current position is {})",
+                offset, self.position
+            ));
+        }
+
+        let expected = offset - self.position;
+        let skipped = std::io::copy(&mut self.by_ref().take(expected), &mut std::io::sink())
+            .map_err(|e| e.to_string())?;
+
+        if skipped != expected {
+            return Err(String::from("Unexpected end of stream"));
+        }
+
+        Ok(())
+    }
+
+    /// read exactly `length` bytes
+    fn read_bytes(&mut self, length: u64) -> Result<Vec<u8>, String> {
+        let mut buf = vec![];
+        self.by_ref()
+            .take(length)
+            .read_to_end(&mut buf)
+            .map_err(|e| e.to_string())?;
+
+        if buf.len() as u64 != length {
+            return Err(String::from("Unexpected end of stream"));
+        }
+
+        Ok(buf)
+    }
+}
+
+impl<R: Read> Read for StreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<'de>
+    ProgEnum<
+        'de,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Cursor<Vec<u8>>>>,
+            Statement<'de, Bls12_381Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Cursor<Vec<u8>>>>,
+            Statement<'de, Bn128Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Cursor<Vec<u8>>>>,
+            Statement<'de, Bls12_377Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Cursor<Vec<u8>>>>,
+            Statement<'de, Bw6_761Field>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Cursor<Vec<u8>>>>,
+            Statement<'de, PallasField>,
+        >,
+        UnwrappedStreamDeserializer<
+            'de,
+            serde_cbor::de::IoRead<ConstraintsReader<Cursor<Vec<u8>>>>,
+            Statement<'de, VestaField>,
+        >,
+    >
+{
+    fn read_stream<T: Field, R: Read>(
+        r: &mut StreamReader<R>,
+        header: &ProgHeader,
+        limits: &ReadLimits,
+    ) -> Result<
+        ProgIterator<
+            'de,
+            T,
+            UnwrappedStreamDeserializer<
+                'de,
+                serde_cbor::de::IoRead<ConstraintsReader<Cursor<Vec<u8>>>>,
+                Statement<'de, T>,
+            >,
+        >,
+        String,
+    > {
+        // bound the sections before buffering any of them
+        limits.check(header)?;
+
+        let parameters = {
+            let section = &header.sections[0];
+            r.skip_to(section.offset)?;
+            let bytes = r.read_bytes(section.length)?;
+            section.verify_bytes(&bytes)?;
+
+            deserialize_parameters(&bytes[..], &header.version)?
+        };
+        limits.check_length("parameters", parameters.len())?;
+
+        // the constraints section comes before the solvers section, so we buffer its raw bytes, within the length checked against `limits`
+        let constraints = {
+            let section = &header.sections[1];
+            r.skip_to(section.offset)?;
+            let bytes = r.read_bytes(section.length)?;
+            section.verify_bytes(&bytes)?;
+            bytes
+        };
+
+        let solvers: Vec<_> = {
+            let section = &header.sections[2];
+            r.skip_to(section.offset)?;
+            let bytes = r.read_bytes(section.length)?;
+            section.verify_bytes(&bytes)?;
+
+            let mut p = serde_cbor::Deserializer::from_reader(&bytes[..]);
+            Vec::deserialize(&mut p).map_err(|_| String::from("Cannot read solvers"))?
+        };
+        limits.check_length("solvers", solvers.len())?;
+
+        let module_map = {
+            let section = &header.sections[3];
+            r.skip_to(section.offset)?;
+            let bytes = r.read_bytes(section.length)?;
+            section.verify_bytes(&bytes)?;
+
+            let mut p = serde_cbor::Deserializer::from_reader(&bytes[..]);
+            ModuleMap::deserialize(&mut p).map_err(|_| String::from("Cannot read module map"))?
+        };
+
+        let returns = match &header.return_slots {
+            Some(section) => {
+                r.skip_to(section.offset)?;
+                let bytes = r.read_bytes(section.length)?;
+                section.verify_bytes(&bytes)?;
+
+                deserialize_return_slots(&bytes[..])?
+            }
+            None => vec![],
+        };
+
+        let embeds_used = match &header.embeds {
+            Some(section) => {
+                r.skip_to(section.offset)?;
+                let bytes = r.read_bytes(section.length)?;
+                section.verify_bytes(&bytes)?;
+
+                deserialize_embeds_used(&bytes[..])?
+            }
+            None => BTreeSet::new(),
+        };
+
+        let statements_deserializer = {
+            let r = ConstraintsReader::new(Cursor::new(constraints), header, limits)
+                .map_err(|e| e.to_string())?;
+            let p = serde_cbor::Deserializer::from_reader(r);
+            let s = p.into_iter::<Statement<T>>();
+
+            UnwrappedStreamDeserializer::new(s, limits)
+        };
+
+        Ok(ProgIterator {
+            returns,
+            embeds_used,
+            ..ProgIterator::new(
+                parameters,
+                statements_deserializer,
+                header.return_count as usize,
+                module_map,
+                solvers,
+            )
+        })
+    }
+
+    /// deserialize a program from a stream which does not implement `Seek`, such as stdin or a socket
+    /// Sections are expected to appear in the order in which `serialize` writes them: parameters, constraints, solvers, modules, constraint index, return slots and embeds.
+    /// As solvers are written after constraints, the raw bytes of the constraints section are buffered in memory.
+    /// Memory use therefore grows with the length of the constraints section, up to the bound of the default `ReadLimits`.
+    pub fn deserialize_stream<R: Read>(r: R) -> Result<Self, String> {
+        Self::deserialize_stream_with_limits(r, &ReadLimits::default())
+    }
+
+    /// deserialize a program from a stream like `deserialize_stream`, rejecting it if it exceeds `limits`
+    /// The header is checked against `limits` before any section is buffered, so `max_bytes_per_constraint` bounds the memory used for the constraints section.
+    pub fn deserialize_stream_with_limits<R: Read>(
+        r: R,
+        limits: &ReadLimits,
+    ) -> Result<Self, String> {
+        let mut r = StreamReader::new(r);
+
+        let header = ProgHeader::read(&mut r).map_err(|_| String::from("Invalid header"))?;
+
+        check_header(&header)?;
+
+        match header.curve_id {
+            m if m == Bls12_381Field::id() => Ok(ProgEnum::Bls12_381Program(Self::read_stream(
+                &mut r, &header, limits,
+            )?)),
+            m if m == Bn128Field::id() => Ok(ProgEnum::Bn128Program(Self::read_stream(
+                &mut r, &header, limits,
+            )?)),
+            m if m == Bls12_377Field::id() => Ok(ProgEnum::Bls12_377Program(Self::read_stream(
+                &mut r, &header, limits,
+            )?)),
+            m if m == Bw6_761Field::id() => Ok(ProgEnum::Bw6_761Program(Self::read_stream(
+                &mut r, &header, limits,
+            )?)),
+            m if m == PallasField::id() => Ok(ProgEnum::PallasProgram(Self::read_stream(
+                &mut r, &header, limits,
+            )?)),
+            m if m == VestaField::id() => Ok(ProgEnum::VestaProgram(Self::read_stream(
+                &mut r, &header, limits,
+            )?)),
+            _ => Err(String::from("Unknown curve identifier")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Prog;
+    use std::io::{Cursor, Seek, SeekFrom};
+    use zokrates_field::{Bls12_381Field, Bn128Field};
+
+    #[test]
+    fn ser_deser_v2() {
+        let p: Prog<Bn128Field> = Prog::default();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        // rewind back to the beginning of the file
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+
+        // deserialize
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
+
+        let p: Prog<Bls12_381Field> = Prog::default();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        // rewind back to the beginning of the file
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+
+        // deserialize
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+
+        assert_eq!(ProgEnum::Bls12_381Program(p), deserialized_p.collect());
+    }
+
+    fn constrained_prog() -> Prog<'static, Bn128Field> {
+        use crate::ir::{LinComb, Parameter, QuadComb, Variable};
+
+        Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                LinComb::from(Variable::public(0)),
+                None,
+            )],
+            ..Prog::default()
+        }
+    }
+
+    /// serialize `p` in the layout of an older file version, with `downgrade` applied to its header
+    /// Parameters are rewritten with a `private` flag, as in files older than version 7
+    fn serialize_legacy(
+        p: Prog<Bn128Field>,
+        downgrade: impl FnOnce(&mut ProgHeader),
+    ) -> Cursor<Vec<u8>> {
+        #[derive(Serialize)]
+        struct LegacyParameter {
+            span: Option<Span>,
+            id: Variable,
+            private: bool,
+            name: Option<String>,
+        }
+
+        let parameters = serde_cbor::to_vec(
+            &p.arguments
+                .iter()
+                .map(|a| LegacyParameter {
+                    span: a.span,
+                    id: a.id,
+                    private: a.visibility.is_private(),
+                    name: a.name.clone(),
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        // the parameters section comes first, so the following sections are moved by the change in its length
+        let mut header = ProgHeader::read(&bytes[..]).unwrap();
+        let section = header.sections[0].clone();
+
+        for s in header.sections[1..]
+            .iter_mut()
+            .chain(header.constraint_index.as_mut())
+            .chain(header.return_slots.as_mut())
+            .chain(header.embeds.as_mut())
+            .chain(header.metadata.as_mut())
+            .chain(header.types.as_mut())
+        {
+            s.set_offset(s.offset - section.length + parameters.len() as u64);
+        }
+        header.sections[0].set_length(parameters.len() as u64);
+        header.sections[0].set_checksum(crc32fast::hash(&parameters));
+
+        downgrade(&mut header);
+
+        let mut legacy = bytes[..section.offset as usize].to_vec();
+        legacy.extend(parameters);
+        legacy.extend(&bytes[(section.offset + section.length) as usize..]);
+
+        let mut buffer = Cursor::new(legacy);
+        header.write(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn count_constraints() {
+        use crate::ir::{Parameter, Variable};
+
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        let count = p.clone().serialize(&mut buffer).unwrap();
+
+        assert_eq!(p.count_constraints().unwrap(), count);
+
+        // unconstrained variables are detected like in `serialize`
+        let p = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            ..constrained_prog()
+        };
+
+        assert!(p.clone().count_constraints().is_err());
+
+        // which the streaming counter does not detect
+        assert_eq!(p.count_constraints_streaming(), count);
+    }
+
+    #[test]
+    fn append() {
+        use crate::ir::{LinComb, QuadComb, Variable};
+
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        let appended = Statement::constraint(
+            QuadComb::new(Variable::new(0).into(), Variable::public(0).into()),
+            LinComb::from(Variable::new(1)),
+            None,
+        );
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let mut appender = ProgAppender::<Bn128Field, _>::open(&mut buffer).unwrap();
+        assert_eq!(appender.append(vec![appended.clone()]).unwrap(), 1);
+        let header = appender.close().unwrap();
+        assert_eq!(header.constraint_count, 2);
+        assert_eq!(header.variable_count, Some(4));
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+
+        assert_eq!(
+            ProgEnum::Bn128Program(Prog {
+                statements: vec![p.statements[0].clone(), appended],
+                ..p
+            }),
+            deserialized_p.collect()
+        );
+    }
+
+    #[test]
+    fn append_curve_mismatch() {
+        let mut buffer = Cursor::new(vec![]);
+        constrained_prog().serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        assert!(ProgAppender::<Bls12_381Field, _>::open(&mut buffer).is_err());
+    }
+
+    #[test]
+    fn section_types() {
+        let p: Prog<Bn128Field> = Prog {
+            module_map: ModuleMap::new(vec!["main.zok".into(), "foo/bar.zok".into()]),
+            ..constrained_prog()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+
+        assert_eq!(
+            header.sections.iter().map(|s| s.ty).collect::<Vec<_>>(),
+            vec![
+                SectionType::Parameters,
+                SectionType::Constraints,
+                SectionType::Solvers,
+                SectionType::Modules
+            ]
+        );
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
+    }
+
+    #[test]
+    fn parameter_names() {
+        use crate::ir::{Parameter, Variable};
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0)).with_name(Some("a".into()))],
+            ..constrained_prog()
+        };
+
+        // names do not affect equality
+        assert_eq!(p, constrained_prog());
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = match ProgEnum::deserialize(buffer).unwrap().collect() {
+            ProgEnum::Bn128Program(p) => p,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(deserialized_p.arguments[0].name(), "a");
+        assert_eq!(constrained_prog().arguments[0].name(), "_0");
+    }
+
+    #[test]
+    fn invalid_solver() {
+        use crate::ir::{DirectiveStatement, Solver, Variable};
+
+        let mut p = constrained_prog();
+        // `Div` takes two inputs
+        p.statements.insert(
+            0,
+            Statement::Directive(DirectiveStatement {
+                span: None,
+                inputs: vec![Variable::new(0).into()],
+                outputs: vec![Variable::new(1)],
+                solver: Solver::Div,
+            }),
+        );
+
+        let mut buffer = Cursor::new(vec![]);
+        let error = p.serialize(&mut buffer).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid solver"));
+    }
+
+    #[test]
+    fn summary() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let summary = ProgHeader::read(&mut buffer).unwrap().summary();
+
+        assert_eq!(summary.curve, "bn128");
+        assert_eq!(summary.constraint_count, 1);
+        assert_eq!(summary.return_count, 1);
+        assert_eq!(summary.sections.len(), 4);
+        assert_eq!(summary.sections[1].0, SectionType::Constraints);
+        assert!(summary.sections[1].2 > 0);
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+
+        // corrupt the last byte of the constraints section
+        let section = &header.sections[1];
+        let mut bytes = buffer.into_inner();
+        bytes[(section.offset + section.length - 1) as usize] ^= 0xff;
+
+        let error = ProgEnum::deserialize(Cursor::new(bytes)).err().unwrap();
+        assert!(error.contains("Constraints"));
+    }
+
+    #[test]
+    fn read_limits() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        assert!(
+            ProgEnum::deserialize_with_limits(Cursor::new(&bytes), &ReadLimits::default()).is_ok()
+        );
+
+        let error = ProgEnum::deserialize_with_limits(
+            Cursor::new(&bytes),
+            &ReadLimits {
+                max_bytes_per_constraint: 1,
+                ..ReadLimits::default()
+            },
+        )
+        .err()
+        .unwrap();
+        assert!(error.starts_with("Constraints section is"));
+
+        let error = ProgEnum::deserialize_with_limits(
+            Cursor::new(&bytes),
+            &ReadLimits {
+                max_section_length: 0,
+                ..ReadLimits::default()
+            },
+        )
+        .err()
+        .unwrap();
+        assert!(error.starts_with("Parameters section is"));
+
+        let error = ProgEnum::deserialize_with_limits(
+            Cursor::new(&bytes),
+            &ReadLimits {
+                max_constraints: 0,
+                ..ReadLimits::default()
+            },
+        )
+        .err()
+        .unwrap();
+        assert!(error.contains("exceeds the limit of 0"));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn read_limits_decompressed() {
+        use crate::ir::{QuadComb, Variable};
+
+        // identical constraints compress to a fraction of their length
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                    Variable::public(0),
+                    None,
+                );
+                1000
+            ],
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize_compressed(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        assert!(
+            ProgEnum::deserialize_with_limits(Cursor::new(&bytes), &ReadLimits::default()).is_ok()
+        );
+
+        let limits = ReadLimits {
+            max_bytes_per_constraint: 8,
+            ..ReadLimits::default()
+        };
+
+        // the compressed section fits in the limit, but not the decompressed one
+        let mut r = Cursor::new(&bytes);
+        let header = ProgHeader::read(&mut r).unwrap();
+        assert!(limits.check(&header).is_ok());
+
+        let error = ProgEnum::deserialize_with_limits(Cursor::new(&bytes), &limits)
+            .err()
+            .unwrap();
+        assert!(error.contains("decompresses to more bytes than the limit"));
+    }
+
+    #[test]
+    fn read_limits_readers() {
+        let mut buffer = Cursor::new(vec![]);
+        constrained_prog().serialize(&mut buffer).unwrap();
+
+        // declare more constraints than the default limit, without changing the sections
+        buffer.set_position(0);
+        let mut header = ProgHeader::read(&mut buffer).unwrap();
+        header.constraint_count = u32::MAX;
+        buffer.set_position(0);
+        header.write(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        let error = ProgEnum::deserialize_stream(&bytes[..]).err().unwrap();
+        assert!(error.contains("exceeds the limit"));
+
+        let error = ProgIterator::<Bn128Field, _>::from_slice(&bytes)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("exceeds the limit"));
+    }
+
+    #[test]
+    fn read_limits_collections() {
+        use crate::ir::{LinComb, Parameter, QuadComb, Variable};
+
+        let limits = ReadLimits {
+            max_collection_length: 4,
+            ..ReadLimits::default()
+        };
+
+        let p: Prog<Bn128Field> = Prog {
+            // public, as unused private arguments are rejected when serializing
+            arguments: (0..5)
+                .map(|i| Parameter::public(Variable::new(i)))
+                .collect(),
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                Variable::public(0),
+                None,
+            )],
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        let error = ProgEnum::deserialize_stream_with_limits(&bytes[..], &limits)
+            .err()
+            .unwrap();
+        assert!(error.starts_with("Program has 5 parameters"));
+        assert!(ProgIterator::<Bn128Field, _>::from_slice_with_limits(&bytes, &limits).is_err());
+
+        // a linear combination with more terms than the limit
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                LinComb::new(vec![(Variable::public(0), Bn128Field::from(1)); 5]),
+                None,
+            )],
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        let statements = match ProgEnum::deserialize_stream_with_limits(&bytes[..], &limits) {
+            Ok(ProgEnum::Bn128Program(p)) => p.statements.into_fallible(),
+            _ => unreachable!(),
+        };
+        assert!(statements.collect::<Result<Vec<_>, _>>().is_err());
+
+        let statements = ProgIterator::<Bn128Field, _>::from_slice_with_limits(&bytes, &limits)
+            .unwrap()
+            .statements
+            .into_fallible();
+        assert!(statements.collect::<Result<Vec<_>, _>>().is_err());
+    }
+
+    #[test]
+    fn try_read() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+
+        let error = ProgEnum::try_read::<Bls12_381Field>(buffer.clone(), &header)
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            "Expected a program over curve `bls12_381`, found a program over curve `bn128`, which should be read with `Bn128Field`"
+        );
+
+        let deserialized_p = ProgEnum::try_read::<Bn128Field>(buffer.clone(), &header).unwrap();
+        assert_eq!(p, deserialized_p.collect());
+
+        // corrupt the first statement, which `try_read` does not detect as it skips checksums
+        let section = &header.sections[1];
+        let mut bytes = buffer.into_inner();
+        bytes[section.offset as usize] = 0xff;
+
+        let deserialized_p = ProgEnum::try_read::<Bn128Field>(Cursor::new(bytes), &header).unwrap();
+        let mut statements = deserialized_p.statements.into_fallible();
+        assert!(matches!(statements.next(), Some(Err(_))));
+        assert!(statements.next().is_none());
+    }
+
+    #[test]
+    fn read_by_curve_id() {
+        // count the constraints of a program over any curve
+        struct ConstraintCount<R> {
+            r: R,
+            header: ProgHeader,
+        }
+
+        impl<R: Read + Seek> FieldDispatch for ConstraintCount<R> {
+            type Output = Result<usize, String>;
+
+            fn dispatch<T: Field>(self) -> Self::Output {
+                Ok(ProgEnum::try_read::<T>(self.r, &self.header)?
+                    .collect()
+                    .constraint_count())
+            }
+        }
+
+        let mut buffer = Cursor::new(vec![]);
+        constrained_prog().serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+
+        let count = dispatch_by_curve_id(header.curve_id, ConstraintCount { r: buffer, header });
+        assert_eq!(count, Some(Ok(1)));
+    }
+
+    #[test]
+    fn deser_v3() {
+        let p = constrained_prog();
+
+        // rewrite the header in the version 3 layout, which has no checksums
+        let buffer = serialize_legacy(p.clone(), |header| {
+            header.version = [3, 0, 0, 0];
+            for section in header.sections.iter_mut() {
+                section.checksum = None;
+            }
+        });
+
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
+    }
+
+    #[test]
+    fn variable_count() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+
+        // ~one, ~out_0 and _0
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.variable_count, Some(3));
+
+        // rewrite the header in the version 4 layout, which has no variable count
+        let mut buffer = serialize_legacy(constrained_prog(), |header| {
+            header.version = [4, 0, 0, 0];
+            header.variable_count = None;
+        });
+
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.variable_count, None);
+        assert_eq!(header.variable_count::<Bn128Field, _>(buffer), Ok(3));
+    }
+
+    #[test]
+    fn constraint_index() {
+        use crate::ir::{DirectiveStatement, LinComb, Parameter, QuadComb, Solver, Variable};
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: (0..10)
+                .flat_map(|i| {
+                    vec![
+                        Statement::Directive(DirectiveStatement::new(
+                            vec![Variable::new(i + 1)],
+                            Solver::Bits(1),
+                            vec![Variable::new(i).into()],
+                        )),
+                        Statement::constraint(
+                            QuadComb::new(Variable::new(i).into(), Variable::new(i).into()),
+                            LinComb::from(Variable::new(i + 1)),
+                            None,
+                        ),
+                    ]
+                })
+                .chain(std::iter::once(Statement::constraint(
+                    Variable::new(10),
+                    Variable::public(0),
+                    None,
+                )))
+                .collect(),
+            ..Prog::default()
+        };
+
+        let read_from_constraint = |bytes: &[u8], n: usize| {
+            let mut r = Cursor::new(bytes);
+            let header = ProgHeader::read(&mut r).unwrap();
+            ProgEnum::try_read_from_constraint::<Bn128Field>(r, &header, n)
+                .unwrap()
+                .collect()
+        };
+
+        let mut indexed = Cursor::new(vec![]);
+        p.clone().serialize_with_index(&mut indexed, 3).unwrap();
+        let indexed = indexed.into_inner();
+
+        let mut plain = Cursor::new(vec![]);
+        p.clone().serialize(&mut plain).unwrap();
+        let plain = plain.into_inner();
+
+        assert!(ProgHeader::read(&indexed[..])
+            .unwrap()
+            .constraint_index
+            .is_some());
+        assert!(ProgHeader::read(&plain[..])
+            .unwrap()
+            .constraint_index
+            .is_none());
+
+        for n in 0..=12 {
+            let expected = p.clone().seek_to_constraint(n).collect();
+
+            assert_eq!(read_from_constraint(&indexed, n), expected);
+            assert_eq!(read_from_constraint(&plain, n), expected);
+        }
+
+        assert_eq!(
+            read_from_constraint(&indexed, 4).statements[0],
+            p.statements[9]
+        );
+    }
+
+    #[test]
+    fn deser_v6_parameters() {
+        let p = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::public(Variable::new(1)),
+            ],
+            ..constrained_prog()
+        };
+
+        let buffer = serialize_legacy(p.clone(), |header| header.version = [6, 0, 0, 0]);
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
+    }
+
+    #[test]
+    fn committed_parameters() {
+        let p = Prog {
+            arguments: vec![
+                Parameter::committed(Variable::new(0)),
+                Parameter::public(Variable::new(1)),
+            ],
+            ..constrained_prog()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = match ProgEnum::deserialize(buffer).unwrap().collect() {
+            ProgEnum::Bn128Program(p) => p,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(deserialized_p, p);
+        assert_eq!(
+            deserialized_p.arguments[0].visibility,
+            Visibility::Committed
+        );
+        assert_eq!(deserialized_p.arguments[0].to_string(), "committed _0");
+    }
+
+    #[test]
+    fn version() {
+        let mut buffer = Cursor::new(vec![]);
+        Prog::<Bn128Field>::default()
+            .serialize(&mut buffer)
+            .unwrap();
 
-    fn next(&mut self) -> Option<T> {
-        self.s.next().and_then(|v| v.ok())
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let version = ProgHeader::peek_version(&mut buffer).unwrap();
+        assert_eq!(version, CURRENT_FILE_VERSION);
+        assert_eq!(buffer.position(), 8);
+
+        assert!(is_compatible_version(version));
+        assert!(is_compatible_version(*MIN_FILE_VERSION));
+        assert!(!is_compatible_version([2, 0, 0, 0]));
+        assert!(!is_compatible_version([13, 0, 0, 0]));
+
+        assert!(ProgHeader::peek_version(&[0u8; 8][..]).is_err());
     }
-}
 
-impl<'de, R: Read + Seek>
-    ProgEnum<
-        'de,
-        UnwrappedStreamDeserializer<'de, serde_cbor::de::IoRead<R>, Statement<'de, Bls12_381Field>>,
-        UnwrappedStreamDeserializer<'de, serde_cbor::de::IoRead<R>, Statement<'de, Bn128Field>>,
-        UnwrappedStreamDeserializer<'de, serde_cbor::de::IoRead<R>, Statement<'de, Bls12_377Field>>,
-        UnwrappedStreamDeserializer<'de, serde_cbor::de::IoRead<R>, Statement<'de, Bw6_761Field>>,
-        UnwrappedStreamDeserializer<'de, serde_cbor::de::IoRead<R>, Statement<'de, PallasField>>,
-        UnwrappedStreamDeserializer<'de, serde_cbor::de::IoRead<R>, Statement<'de, VestaField>>,
-    >
-{
-    fn read<T: Field>(
-        mut r: R,
-        header: &ProgHeader,
-    ) -> ProgIterator<
-        'de,
-        T,
-        UnwrappedStreamDeserializer<'de, serde_cbor::de::IoRead<R>, Statement<'de, T>>,
-    > {
-        let parameters = {
-            let section = &header.sections[0];
-            r.seek(std::io::SeekFrom::Start(section.offset)).unwrap();
+    #[test]
+    fn strict() {
+        let mut buffer = Cursor::new(vec![]);
+        let e = Prog::<Bn128Field>::default()
+            .serialize_strict(&mut buffer, 1)
+            .err()
+            .unwrap();
+        assert_eq!(e.to_string(), "Expected at least 1 constraints, found 0");
 
-            let mut p = serde_cbor::Deserializer::from_reader(r.by_ref());
-            Vec::deserialize(&mut p)
-                .map_err(|_| String::from("Cannot read parameters"))
-                .unwrap()
+        // the header is left blank
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        assert!(ProgEnum::deserialize(&mut buffer).is_err());
+
+        let p = constrained_prog();
+        let count = p.constraint_count();
+
+        let mut buffer = Cursor::new(vec![]);
+        assert!(p.clone().serialize_strict(&mut buffer, count + 1).is_err());
+
+        let mut buffer = Cursor::new(vec![]);
+        assert_eq!(p.serialize_strict(&mut buffer, count).unwrap(), count);
+    }
+
+    #[test]
+    fn unconstrained_variables() {
+        use crate::ir::{Parameter, Variable};
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            ..Prog::default()
         };
 
-        let solvers = {
-            let section = &header.sections[2];
-            r.seek(std::io::SeekFrom::Start(section.offset)).unwrap();
+        let mut buffer = Cursor::new(vec![]);
+        match p.serialize(&mut buffer).unwrap_err() {
+            SerializeError::UnconstrainedVariables { variables, .. } => {
+                assert_eq!(variables.len(), 1);
+                assert_eq!(variables[0].variable, Variable::new(0));
+            }
+            e => panic!("unexpected error {}", e),
+        }
 
-            let mut p = serde_cbor::Deserializer::from_reader(r.by_ref());
-            Vec::deserialize(&mut p)
-                .map_err(|_| String::from("Cannot read solvers"))
-                .unwrap()
+        // the program is written regardless
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        assert!(ProgEnum::deserialize(buffer).is_ok());
+    }
+
+    #[test]
+    fn no_seek() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        // a `Vec` can be written to but not seeked
+        let mut bytes = vec![];
+        assert_eq!(p.clone().serialize_no_seek(&mut bytes).unwrap(), 1);
+        assert_eq!(bytes, buffer.into_inner());
+
+        let deserialized_p = ProgEnum::deserialize(Cursor::new(bytes)).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
+    }
+
+    #[test]
+    fn unconstrained_policy() {
+        use crate::ir::{Parameter, Variable};
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            ..Prog::default()
         };
 
-        let module_map = {
-            let section = &header.sections[3];
-            r.seek(std::io::SeekFrom::Start(section.offset)).unwrap();
+        let mut reported = vec![];
+        let mut buffer = Cursor::new(vec![]);
+        assert_eq!(
+            p.clone()
+                .serialize_with_unconstrained_policy(
+                    &mut buffer,
+                    UnconstrainedPolicy::Warn,
+                    |variables, _| reported.extend(variables)
+                )
+                .unwrap(),
+            0
+        );
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].variable, Variable::new(0));
 
-            let mut p = serde_cbor::Deserializer::from_reader(r.by_ref());
-            ModuleMap::deserialize(&mut p)
-                .map_err(|_| String::from("Cannot read module map"))
-                .unwrap()
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        assert!(ProgEnum::deserialize(buffer).is_ok());
+
+        let mut called = false;
+        assert!(p
+            .clone()
+            .serialize_with_unconstrained_policy(
+                Cursor::new(vec![]),
+                UnconstrainedPolicy::Ignore,
+                |_, _| called = true
+            )
+            .is_ok());
+        assert!(!called);
+
+        assert!(matches!(
+            p.serialize_with_unconstrained_policy(
+                Cursor::new(vec![]),
+                UnconstrainedPolicy::default(),
+                |_, _| called = true
+            ),
+            Err(SerializeError::UnconstrainedVariables { .. })
+        ));
+        assert!(!called);
+    }
+
+    #[test]
+    fn parameters_only() {
+        let p = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::public(Variable::new(1)),
+            ],
+            ..constrained_prog()
         };
 
-        let statements_deserializer = {
-            let section = &header.sections[1];
-            r.seek(std::io::SeekFrom::Start(section.offset)).unwrap();
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
 
-            let p = serde_cbor::Deserializer::from_reader(r);
-            let s = p.into_iter::<Statement<T>>();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(read_parameters(&mut buffer, &header).unwrap(), p.arguments);
+
+        // a truncated file is an error rather than a panic
+        let mut bytes = buffer.into_inner();
+        bytes.truncate(header.sections[0].offset as usize + 1);
+        assert!(read_parameters(Cursor::new(bytes), &header).is_err());
+
+        let legacy = serialize_legacy(p.clone(), |header| header.version = [6, 0, 0, 0]);
+        let header = ProgHeader::read(legacy.clone()).unwrap();
+        assert_eq!(read_parameters(legacy, &header).unwrap(), p.arguments);
+    }
+
+    #[test]
+    fn ser_deser_stream() {
+        let p: Prog<Bn128Field> = Prog::default();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+
+        // only expose `Read` to the deserializer
+        let bytes = buffer.into_inner();
+        let deserialized_p = ProgEnum::deserialize_stream(&bytes[..]).unwrap();
+
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
+    }
+
+    #[test]
+    fn deser_stream_out_of_order() {
+        let p: Prog<Bn128Field> = Prog::default();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+
+        // swap the offsets of the parameters and constraints sections
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let mut header = ProgHeader::read(&mut buffer).unwrap();
+        let parameters_offset = header.sections[0].offset;
+        header.sections[0].set_offset(header.sections[1].offset);
+        header.sections[1].set_offset(parameters_offset);
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        header.write(&mut buffer).unwrap();
+
+        let bytes = buffer.into_inner();
+        assert!(ProgEnum::deserialize_stream(&bytes[..]).is_err());
+    }
 
-            UnwrappedStreamDeserializer { s }
+    #[test]
+    fn deser_stream_limits() {
+        let mut buffer = Cursor::new(vec![]);
+        constrained_prog().serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        // the constraints section is rejected before it is buffered
+        let limits = ReadLimits {
+            max_bytes_per_constraint: 1,
+            ..ReadLimits::default()
         };
+        let error = ProgEnum::deserialize_stream_with_limits(&bytes[..], &limits)
+            .err()
+            .unwrap();
+        assert!(error.starts_with("Constraints section is"));
+    }
 
-        ProgIterator::new(
-            parameters,
-            statements_deserializer,
-            header.return_count as usize,
-            module_map,
-            solvers,
-        )
+    #[cfg(feature = "compression")]
+    #[test]
+    fn ser_deser_compressed() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize_compressed(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert!(header.is_compressed());
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer.clone()).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p.clone()), deserialized_p.collect());
+
+        let bytes = buffer.into_inner();
+        let deserialized_p = ProgEnum::deserialize_stream(&bytes[..]).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
     }
 
-    pub fn deserialize(mut r: R) -> Result<Self, String> {
-        let header = ProgHeader::read(&mut r).map_err(|_| String::from("Invalid header"))?;
+    #[test]
+    fn return_slots() {
+        use crate::ir::{LinComb, QuadComb, Variable};
+        use crate::typed::ConcreteType;
 
-        // Check the magic number, `ZOK`
-        if &header.magic != ZOKRATES_MAGIC {
-            return Err("Invalid magic number".to_string());
-        }
+        let p = constrained_prog().with_returns(vec![ReturnSlot::new(ConcreteType::FieldElement)]);
 
-        // Check the file version
-        if &header.version != FILE_VERSION {
-            return Err("Invalid file version".to_string());
-        }
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize_with_index(&mut buffer, 1).unwrap();
 
-        match header.curve_id {
-            m if m == Bls12_381Field::id() => {
-                Ok(ProgEnum::Bls12_381Program(Self::read(r, &header)))
-            }
-            m if m == Bn128Field::id() => Ok(ProgEnum::Bn128Program(Self::read(r, &header))),
-            m if m == Bls12_377Field::id() => {
-                Ok(ProgEnum::Bls12_377Program(Self::read(r, &header)))
-            }
-            m if m == Bw6_761Field::id() => Ok(ProgEnum::Bw6_761Program(Self::read(r, &header))),
-            m if m == PallasField::id() => Ok(ProgEnum::PallasProgram(Self::read(r, &header))),
-            m if m == VestaField::id() => Ok(ProgEnum::VestaProgram(Self::read(r, &header))),
-            _ => Err(String::from("Unknown curve identifier")),
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert!(header.return_slots.is_some());
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer.clone()).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p.clone()), deserialized_p.collect());
+
+        let bytes = buffer.clone().into_inner();
+        let deserialized_p = ProgEnum::deserialize_stream(&bytes[..]).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p.clone()), deserialized_p.collect());
+
+        // appending rewrites the return slots after the module map
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let mut appender = ProgAppender::<Bn128Field, _>::open(&mut buffer).unwrap();
+        appender
+            .append(vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::public(0).into()),
+                LinComb::from(Variable::new(1)),
+                None,
+            )])
+            .unwrap();
+        appender.close().unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer).unwrap().collect();
+        match deserialized_p {
+            ProgEnum::Bn128Program(deserialized_p) => assert_eq!(deserialized_p.returns, p.returns),
+            _ => unreachable!(),
         }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::ir::Prog;
-    use std::io::{Cursor, Seek, SeekFrom};
-    use zokrates_field::{Bls12_381Field, Bn128Field};
+        // programs without return slots do not write the section
+        let mut buffer = Cursor::new(vec![]);
+        constrained_prog().serialize(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        assert!(ProgHeader::read(&mut buffer)
+            .unwrap()
+            .return_slots
+            .is_none());
+    }
 
     #[test]
-    fn ser_deser_v2() {
-        let p: Prog<Bn128Field> = Prog::default();
+    fn embeds_used() {
+        let p = ProgIterator {
+            embeds_used: [FlatEmbed::Poseidon, FlatEmbed::Keccak256]
+                .into_iter()
+                .collect(),
+            ..constrained_prog()
+        };
 
         let mut buffer = Cursor::new(vec![]);
         p.clone().serialize(&mut buffer).unwrap();
 
-        // rewind back to the beginning of the file
         buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.read_embeds_used(&mut buffer).unwrap(), p.embeds_used);
 
-        // deserialize
-        let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer.clone()).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p.clone()), deserialized_p.collect());
 
+        let bytes = buffer.into_inner();
+        let deserialized_p = ProgEnum::deserialize_stream(&bytes[..]).unwrap();
         assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
 
-        let p: Prog<Bls12_381Field> = Prog::default();
+        // programs whose embeds are unknown do not write the section
+        let mut buffer = Cursor::new(vec![]);
+        constrained_prog().serialize(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert!(header.embeds.is_none());
+        assert!(header.read_embeds_used(&mut buffer).unwrap().is_empty());
+    }
+
+    #[test]
+    fn section_bounds() {
+        let p = constrained_prog();
 
         let mut buffer = Cursor::new(vec![]);
-        p.clone().serialize(&mut buffer).unwrap();
+        p.serialize(&mut buffer).unwrap();
 
-        // rewind back to the beginning of the file
         buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert!(header.check_bounds(&mut buffer).is_ok());
+
+        let write = |header: &ProgHeader| {
+            let mut buffer = buffer.clone();
+            buffer.seek(SeekFrom::Start(0)).unwrap();
+            header.write(&mut buffer).unwrap();
+            buffer.seek(SeekFrom::Start(0)).unwrap();
+            buffer
+        };
 
-        // deserialize
+        // a section past the end of the file
+        let mut past_end = header.clone();
+        past_end.sections[2].set_offset(buffer.get_ref().len() as u64);
+        let error = ProgEnum::deserialize(write(&past_end)).err().unwrap();
+        assert_eq!(
+            error,
+            "Section Solvers out of bounds, the file may be corrupted"
+        );
+        assert!(ProgEnum::try_read::<Bn128Field>(write(&past_end), &past_end).is_err());
+
+        // a section whose end overflows
+        let mut overflow = header.clone();
+        overflow.sections[2].set_length(u64::MAX);
+        assert!(ProgEnum::deserialize(write(&overflow))
+            .err()
+            .unwrap()
+            .contains("out of bounds"));
+
+        // the solvers section starting within the constraints section
+        let mut overlap = header.clone();
+        overlap.sections[2].set_offset(header.sections[1].offset);
+        assert_eq!(
+            ProgEnum::deserialize(write(&overlap)).err().unwrap(),
+            "Sections Constraints and Solvers overlap, the file may be corrupted"
+        );
+    }
+
+    #[test]
+    fn source_hash() {
+        let mut p = constrained_prog();
+        p.module_map = ModuleMap::new(vec!["main.zok".into(), "STDLIB/utils.zok".into()]);
+
+        let read = |contents: &'static str| {
+            move |path: &std::path::Path| -> std::io::Result<Vec<u8>> {
+                match path.to_str().unwrap() {
+                    "main.zok" => Ok(contents.as_bytes().to_vec()),
+                    _ => Ok(b"def utils() {}".to_vec()),
+                }
+            }
+        };
+
+        let hash = p.module_map.source_hash(read("def main() {}")).unwrap();
+        assert_ne!(
+            hash,
+            p.module_map.source_hash(read("def main() { }")).unwrap()
+        );
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone()
+            .serialize_with_source_hash(&mut buffer, hash)
+            .unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.source_hash, hash);
+        assert_eq!(header.summary().source_hash, Some(hash));
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
         let deserialized_p = ProgEnum::deserialize(buffer).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p.clone()), deserialized_p.collect());
 
-        assert_eq!(ProgEnum::Bls12_381Program(p), deserialized_p.collect());
+        // the hash is zero-filled by default
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.source_hash, [0; 32]);
+        assert_eq!(header.summary().source_hash, None);
+    }
+
+    #[test]
+    fn section_bytes() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+
+        let section = &header.sections[1];
+        let bytes = read_section_bytes(&mut buffer, section).unwrap();
+        assert_eq!(
+            bytes,
+            buffer.get_ref()[section.offset as usize..(section.offset + section.length) as usize]
+        );
+        assert!(section.verify_bytes(&bytes).is_ok());
+
+        // the position is unchanged, so the whole program can still be read
+        assert_eq!(buffer.position(), 0);
+        let deserialized_p = ProgEnum::deserialize(&mut buffer).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p), deserialized_p.collect());
+
+        // sections past the end of the stream are an error, which also leaves the position unchanged
+        let mut truncated = Cursor::new(buffer.get_ref()[..section.offset as usize + 1].to_vec());
+        truncated.set_position(1);
+        assert!(read_section_bytes(&mut truncated, section).is_err());
+        assert_eq!(truncated.position(), 1);
+
+        // a corrupted length is not allocated up front
+        let mut corrupted = section.clone();
+        corrupted.set_length(u64::MAX);
+        assert!(read_section_bytes(&mut buffer, &corrupted).is_err());
+    }
+
+    #[test]
+    fn metadata() {
+        let p = constrained_prog();
+        let metadata: BTreeMap<String, String> = [
+            ("compiler".to_string(), "zokrates 0.8.8".to_string()),
+            ("optimization".to_string(), "O2".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone()
+            .serialize_with_metadata(&mut buffer, &metadata)
+            .unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.read_metadata(&mut buffer).unwrap(), metadata);
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer.clone()).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p.clone()), deserialized_p.collect());
+
+        // the metadata is kept when appending to the program
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let mut appender = ProgAppender::<Bn128Field, _>::open(&mut buffer).unwrap();
+        appender.append(p.statements.clone()).unwrap();
+        let header = appender.close().unwrap();
+        assert_eq!(header.read_metadata(&mut buffer).unwrap(), metadata);
+
+        // programs without metadata do not write the section
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert!(header.metadata.is_none());
+        assert!(header.read_metadata(&mut buffer).unwrap().is_empty());
+    }
+
+    #[test]
+    fn types() {
+        use crate::typed::abi::AbiInput;
+        use crate::typed::types::{ConcreteTupleType, ConcreteType, UBitwidth};
+
+        let p = constrained_prog();
+        let types = Abi {
+            inputs: vec![AbiInput {
+                name: "a".into(),
+                public: false,
+                ty: ConcreteType::Uint(UBitwidth::B32),
+            }],
+            output: ConcreteType::Tuple(ConcreteTupleType::new(vec![ConcreteType::Boolean])),
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone()
+            .serialize_with_options(
+                &mut buffer,
+                SerializeOptions::default()
+                    .types(&types)
+                    .source_hash([1; 32]),
+            )
+            .unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.source_hash, [1; 32]);
+        assert_eq!(header.read_types(&mut buffer).unwrap(), Some(types.clone()));
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let deserialized_p = ProgEnum::deserialize(buffer.clone()).unwrap();
+        assert_eq!(ProgEnum::Bn128Program(p.clone()), deserialized_p.collect());
+
+        // the types are kept when appending to the program
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let mut appender = ProgAppender::<Bn128Field, _>::open(&mut buffer).unwrap();
+        appender.append(p.statements.clone()).unwrap();
+        let header = appender.close().unwrap();
+        assert_eq!(header.read_types(&mut buffer).unwrap(), Some(types));
+
+        // programs serialized without types do not write the section
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert!(header.types.is_none());
+        assert_eq!(header.read_types(&mut buffer).unwrap(), None);
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize_with_types(&mut buffer, &types).unwrap();
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(header.source_hash, [0; 32]);
+        assert_eq!(header.read_types(&mut buffer).unwrap(), Some(types));
+    }
+
+    #[test]
+    fn progress() {
+        let mut p = constrained_prog();
+        p.statements = p
+            .statements
+            .iter()
+            .cycle()
+            .take(2 * PROGRESS_BATCH as usize + 1)
+            .cloned()
+            .collect();
+
+        let mut events = vec![];
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize_with_progress(&mut buffer, |e| events.push(e))
+            .unwrap();
+
+        assert_eq!(
+            events.iter().map(|e| e.constraints).collect::<Vec<_>>(),
+            vec![10_000, 20_000, 20_001]
+        );
+        assert!(events.windows(2).all(|e| e[0].bytes < e[1].bytes));
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let header = ProgHeader::read(&mut buffer).unwrap();
+        assert_eq!(events[2].bytes, header.sections[1].length);
+    }
+
+    #[test]
+    fn lazy_statements() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        /// A writer which shares the number of bytes written through it
+        struct SharedCountWriter {
+            inner: Cursor<Vec<u8>>,
+            written: Rc<Cell<u64>>,
+        }
+
+        impl Write for SharedCountWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let n = self.inner.write(buf)?;
+                self.written.set(self.written.get() + n as u64);
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        impl Seek for SharedCountWriter {
+            fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        let p = constrained_prog();
+        let statement = p.statements[0].clone();
+        let written = Rc::new(Cell::new(0));
+
+        // a generator of 100 statements, checking that each statement is written before the next one is pulled
+        let statements = {
+            let written = written.clone();
+            let mut pulled = 0;
+            let mut last = 0;
+            std::iter::from_fn(move || {
+                if pulled > 0 {
+                    assert!(written.get() > last);
+                }
+                last = written.get();
+                pulled += 1;
+                (pulled <= 100).then(|| statement.clone())
+            })
+        };
+
+        let lazy = ProgIterator::new(
+            p.arguments.clone(),
+            statements,
+            p.return_count,
+            p.module_map.clone(),
+            vec![],
+        );
+
+        let mut w = SharedCountWriter {
+            inner: Cursor::new(vec![]),
+            written,
+        };
+        assert_eq!(lazy.serialize(&mut w).unwrap(), 100);
+
+        let deserialized = ProgEnum::deserialize(Cursor::new(w.inner.into_inner())).unwrap();
+        assert_eq!(
+            deserialized.collect(),
+            ProgEnum::Bn128Program(Prog {
+                statements: vec![p.statements[0].clone(); 100],
+                ..p
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_flags() {
+        let p = constrained_prog();
+
+        let mut buffer = Cursor::new(vec![]);
+        p.serialize(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        let mut header = ProgHeader::read(&mut buffer).unwrap();
+        assert!(!header.is_compressed());
+        header.flags = 0x80;
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        header.write(&mut buffer).unwrap();
+
+        buffer.seek(SeekFrom::Start(0)).unwrap();
+        assert!(ProgEnum::deserialize(buffer).is_err());
     }
 }
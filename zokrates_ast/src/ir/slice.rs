@@ -0,0 +1,107 @@
+use super::serialize::{check_header, UnwrappedStreamDeserializer};
+use super::{ProgEnum, ProgHeader, ProgIterator, ReadLimits, Statement};
+use serde_cbor::de::SliceRead;
+use std::io::Cursor;
+use zokrates_field::Field;
+
+type DynamicError = Box<dyn std::error::Error>;
+
+impl<'ast, T: Field>
+    ProgIterator<'ast, T, UnwrappedStreamDeserializer<'ast, SliceRead<'ast>, Statement<'ast, T>>>
+{
+    /// read a program over `T` from `bytes`, checking its header and the integrity of its sections like `ProgEnum::deserialize`
+    /// Statements are deserialized lazily from `bytes` directly, borrowing from them instead of copying them through a reader.
+    /// Compressed and chunked constraints sections are not supported, as they cannot be read in place
+    pub fn from_slice(bytes: &'ast [u8]) -> Result<Self, DynamicError> {
+        Self::from_slice_with_limits(bytes, &ReadLimits::default())
+    }
+
+    /// read a program over `T` from `bytes` like `from_slice`, rejecting it if it exceeds `limits`
+    pub fn from_slice_with_limits(
+        bytes: &'ast [u8],
+        limits: &ReadLimits,
+    ) -> Result<Self, DynamicError> {
+        let mut r = Cursor::new(bytes);
+
+        let header = ProgHeader::read(&mut r).map_err(|_| "Invalid header")?;
+
+        check_header(&header)?;
+
+        if header.is_compressed() {
+            return Err("Compressed programs cannot be read in place".into());
+        }
+
+        if header.is_chunked() {
+            return Err("Chunked programs cannot be read in place".into());
+        }
+
+        header.verify(&mut r)?;
+
+        let prog = ProgEnum::try_read_with_limits::<T>(&mut r, &header, limits)?;
+
+        let section = &header.sections[1];
+        let constraints = bytes
+            .get(section.offset as usize..(section.offset + section.length) as usize)
+            .ok_or("Constraints section out of bounds")?;
+
+        let statements = UnwrappedStreamDeserializer::new(
+            serde_cbor::Deserializer::from_slice(constraints).into_iter::<Statement<T>>(),
+            limits,
+        );
+
+        Ok(ProgIterator {
+            module_map: prog.module_map,
+            arguments: prog.arguments,
+            return_count: prog.return_count,
+            returns: prog.returns,
+            embeds_used: prog.embeds_used,
+            statements,
+            solvers: prog.solvers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::FormatString;
+    use crate::ir::{LinComb, Parameter, Prog, QuadComb, Variable};
+    use crate::typed::ConcreteType;
+    use zokrates_field::{Bls12_381Field, Bn128Field};
+
+    #[test]
+    fn from_slice() {
+        // a format string long enough for its bytes to span several reads of a buffered reader
+        let format_string = format!("{}{{}}", "_".repeat(1 << 14));
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                Statement::log(
+                    FormatString::from(format_string.as_str()),
+                    vec![(ConcreteType::FieldElement, vec![Variable::new(0).into()])],
+                ),
+                Statement::constraint(
+                    QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                    LinComb::from(Variable::public(0)),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        };
+
+        let mut buffer = Cursor::new(vec![]);
+        p.clone().serialize(&mut buffer).unwrap();
+        let bytes = buffer.into_inner();
+
+        assert_eq!(
+            ProgIterator::<Bn128Field, _>::from_slice(&bytes)
+                .unwrap()
+                .collect(),
+            p
+        );
+        assert!(ProgIterator::<Bls12_381Field, _>::from_slice(&bytes).is_err());
+        assert!(ProgIterator::<Bn128Field, _>::from_slice(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -1,9 +1,8 @@
-use crate::common::RefCall;
+use crate::common::{RefCall, SolverError, WithSpan};
 use crate::ir::folder::Folder;
 use crate::ir::Solver;
 use crate::zir::ZirFunction;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use zokrates_field::Field;
 
@@ -24,6 +23,74 @@ fn hash<T: Field>(f: &ZirFunction<T>) -> Hash {
 pub struct SolverIndexer<'ast, T> {
     pub solvers: Vec<Solver<'ast, T>>,
     pub index_map: HashMap<Hash, usize>,
+    /// the first invalid solver found while indexing, if any
+    pub error: Option<SolverError>,
+}
+
+impl<'ast, T: Field> SolverIndexer<'ast, T> {
+    /// create an indexer which reuses the indices of already indexed `solvers`
+    pub fn with_solvers(solvers: Vec<Solver<'ast, T>>) -> Self {
+        let mut index_map = HashMap::new();
+        for (index, solver) in solvers.iter().enumerate() {
+            if let Solver::Zir(f) = solver {
+                index_map.entry(hash(f)).or_insert(index);
+            }
+        }
+
+        Self {
+            solvers,
+            index_map,
+            error: None,
+        }
+    }
+}
+
+impl<'ast, T: Field> SolverIndexer<'ast, T> {
+    /// shift the solver references of `statements` by `offset`, so that they refer to solvers appended after `offset` other solvers
+    /// This is what concatenating the solvers of two programs requires of the statements of the second one, see `ProgIterator::chain`.
+    /// Directives which hold their solver rather than a reference to it are left untouched.
+    pub fn remap<I: IntoIterator<Item = Statement<'ast, T>>>(
+        statements: I,
+        offset: usize,
+    ) -> impl Iterator<Item = Statement<'ast, T>> {
+        let mut remapper = SolverRemapper { offset };
+        statements
+            .into_iter()
+            .flat_map(move |s| remapper.fold_statement(s))
+    }
+}
+
+/// shift `solver` by `offset` if it is a reference, see `SolverIndexer::remap`
+pub(super) fn remap_solver<T>(solver: Solver<T>, offset: usize) -> Solver<T> {
+    match solver {
+        Solver::Ref(call) => Solver::Ref(RefCall {
+            index: call.index + offset,
+            ..call
+        }),
+        solver => solver,
+    }
+}
+
+struct SolverRemapper {
+    offset: usize,
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for SolverRemapper {
+    fn fold_directive_statement(
+        &mut self,
+        d: DirectiveStatement<'ast, T>,
+    ) -> Vec<Statement<'ast, T>> {
+        vec![Statement::Directive(DirectiveStatement {
+            solver: remap_solver(d.solver, self.offset),
+            ..d
+        })]
+    }
+}
+
+impl<'ast, T> SolverIndexer<'ast, T> {
+    fn report(&mut self, e: SolverError) {
+        self.error.get_or_insert(e);
+    }
 }
 
 impl<'ast, T: Field> Folder<'ast, T> for SolverIndexer<'ast, T> {
@@ -32,26 +99,187 @@ impl<'ast, T: Field> Folder<'ast, T> for SolverIndexer<'ast, T> {
         d: DirectiveStatement<'ast, T>,
     ) -> Vec<Statement<'ast, T>> {
         let signature = d.solver.get_signature();
+        let span = d.get_span();
+
         let res = match d.solver {
             Solver::Zir(f) => {
                 let h = hash(&f);
-                let index = match self.index_map.entry(h) {
-                    Entry::Occupied(v) => *v.get(),
-                    Entry::Vacant(entry) => {
+                let index = match self.index_map.get(&h) {
+                    // reuse the index of an identical solver, making sure this is not a hash collision
+                    Some(index) if matches!(&self.solvers[*index], Solver::Zir(g) if *g == f) => {
+                        *index
+                    }
+                    existing => {
                         let index = self.solvers.len();
-                        entry.insert(index);
-                        self.solvers.push(Solver::Zir(f));
+                        if existing.is_none() {
+                            self.index_map.insert(h, index);
+                        }
+                        let solver = Solver::Zir(f);
+                        if let Err(e) = solver.validate() {
+                            self.report(e.index(index).span(span));
+                        }
+                        self.solvers.push(solver);
                         index
                     }
                 };
-                DirectiveStatement::new(
-                    d.outputs,
-                    Solver::Ref(RefCall { index, signature }),
-                    d.inputs,
-                )
+                // not using `DirectiveStatement::new`, as it would panic on an arity mismatch
+                DirectiveStatement {
+                    solver: Solver::Ref(RefCall { index, signature }),
+                    ..d
+                }
             }
             _ => d,
         };
+
+        // zir solvers were validated above when indexed, this checks the solvers held by other directives
+        if let Err(e) = res.solver.validate() {
+            self.report(e.span(span));
+        }
+
+        if let Err(e) = res
+            .solver
+            .validate_arity(res.inputs.len(), res.outputs.len())
+        {
+            let e = match &res.solver {
+                Solver::Ref(call) => e.index(call.index),
+                _ => e,
+            };
+            self.report(e.span(span));
+        }
+
         vec![Statement::Directive(res)]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{QuadComb, Variable};
+    use crate::zir::{
+        types::{Signature, Type},
+        FieldElementExpression, Identifier, IdentifierExpression, Parameter, ZirStatement,
+    };
+    use std::ops::{Add, Mul};
+    use zokrates_field::Bn128Field;
+
+    fn solver(square: bool) -> Solver<'static, Bn128Field> {
+        let id = IdentifierExpression::new(Identifier::internal(0usize));
+        let e = FieldElementExpression::Identifier(id.clone());
+
+        // (field i0) -> i0 * i0 or (field i0) -> i0 + i0
+        let e = match square {
+            true => FieldElementExpression::mul(e.clone(), e),
+            false => FieldElementExpression::add(e.clone(), e),
+        };
+
+        Solver::Zir(ZirFunction {
+            arguments: vec![Parameter::private(crate::zir::Variable::field_element(
+                id.id,
+            ))],
+            statements: vec![ZirStatement::ret(vec![e.into()])],
+            signature: Signature::new()
+                .inputs(vec![Type::FieldElement])
+                .outputs(vec![Type::FieldElement]),
+        })
+    }
+
+    #[test]
+    fn deduplicate() {
+        let mut indexer = SolverIndexer::default();
+
+        let statements: Vec<_> = (0..100)
+            .flat_map(|i| {
+                indexer.fold_statement(Statement::directive(
+                    vec![Variable::new(i + 1)],
+                    solver(i % 2 == 0),
+                    vec![QuadComb::from(Variable::new(0))],
+                ))
+            })
+            .collect();
+
+        assert!(indexer.error.is_none());
+        assert_eq!(indexer.solvers.len(), 2);
+        assert!(statements.iter().all(|s| matches!(
+            s,
+            Statement::Directive(d) if matches!(d.solver, Solver::Ref(RefCall { index, .. }) if index < 2)
+        )));
+    }
+
+    #[test]
+    fn remap() {
+        let directive = |solver| {
+            Statement::directive(
+                vec![Variable::new(1)],
+                solver,
+                vec![QuadComb::from(Variable::new(0))],
+            )
+        };
+
+        let statements = vec![
+            directive(Solver::Ref(RefCall {
+                index: 1,
+                signature: (1, 1),
+            })),
+            Statement::block(vec![directive(Solver::Ref(RefCall {
+                index: 0,
+                signature: (1, 1),
+            }))]),
+            directive(Solver::Bits(1)),
+        ];
+
+        let remapped: Vec<Statement<Bn128Field>> = SolverIndexer::remap(statements, 3).collect();
+
+        assert_eq!(
+            remapped,
+            vec![
+                directive(Solver::Ref(RefCall {
+                    index: 4,
+                    signature: (1, 1),
+                })),
+                Statement::block(vec![directive(Solver::Ref(RefCall {
+                    index: 3,
+                    signature: (1, 1),
+                }))]),
+                directive(Solver::Bits(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_polynomial() {
+        use crate::common::Polynomial;
+
+        let mut indexer = SolverIndexer::default();
+
+        // a polynomial of two variables with a single exponent, called with two inputs
+        indexer.fold_statement(Statement::<Bn128Field>::directive(
+            vec![Variable::new(2)],
+            Solver::Polynomial(Polynomial::new(2, vec![(Bn128Field::from(1), vec![1])])),
+            vec![
+                QuadComb::from(Variable::new(0)),
+                QuadComb::from(Variable::new(1)),
+            ],
+        ));
+
+        assert!(indexer.error.is_some());
+    }
+
+    #[test]
+    fn hash_collision() {
+        let mut indexer = SolverIndexer::default();
+
+        // pretend that the hash of the first solver is the hash of the second one
+        if let Solver::Zir(f) = solver(false) {
+            indexer.index_map.insert(hash(&f), 0);
+        }
+        indexer.solvers.push(solver(true));
+
+        indexer.fold_statement(Statement::directive(
+            vec![Variable::new(1)],
+            solver(false),
+            vec![QuadComb::from(Variable::new(0))],
+        ));
+
+        assert_eq!(indexer.solvers, vec![solver(true), solver(false)]);
+    }
+}
@@ -0,0 +1,111 @@
+use super::visitor::{visit_directive_statement, Visitor};
+use super::{DirectiveStatement, Prog, Solver};
+use crate::common::Span;
+use zokrates_field::Field;
+
+/// The directives of a program which refer to one of its solvers, see `Prog::solver_usage`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolverUsage {
+    /// the number of directives referring to the solver
+    pub references: usize,
+    /// the spans of these directives, for the ones which have a span
+    pub spans: Vec<Span>,
+}
+
+impl SolverUsage {
+    /// whether no directive refers to the solver, so that it can be removed
+    pub fn is_unused(&self) -> bool {
+        self.references == 0
+    }
+}
+
+struct SolverUsageCollector {
+    usage: Vec<SolverUsage>,
+}
+
+impl<T: Field> Visitor<T> for SolverUsageCollector {
+    fn visit_directive_statement(&mut self, d: &DirectiveStatement<T>) {
+        if let Solver::Ref(call) = &d.solver {
+            // references to missing solvers are reported when serializing the program
+            if let Some(usage) = self.usage.get_mut(call.index) {
+                usage.references += 1;
+                usage.spans.extend(d.span);
+            }
+        }
+
+        visit_directive_statement(self, d)
+    }
+}
+
+impl<'ast, T: Field> Prog<'ast, T> {
+    /// the directives referring to each solver of this program, in the order of the solvers, including the directives of nested blocks
+    pub fn solver_usage(&self) -> Vec<SolverUsage> {
+        let mut collector = SolverUsageCollector {
+            usage: vec![SolverUsage::default(); self.solvers.len()],
+        };
+
+        collector.visit_module(self);
+        collector.usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{RefCall, SourceSpan, WithSpan};
+    use crate::ir::{Parameter, Statement, Variable};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn usage() {
+        let span = Span::Source(SourceSpan::mock());
+
+        let call = |index: usize| {
+            Statement::directive(
+                vec![Variable::new(1)],
+                Solver::Ref(RefCall {
+                    index,
+                    signature: (1, 1),
+                }),
+                vec![Variable::new(0).into()],
+            )
+        };
+
+        // def main(private _0) {
+        //     # _1 = Ref@0(_0)
+        //     # _1 = Ref@2(_0)
+        //     {
+        //         # _1 = Ref@0(_0)
+        //     }
+        // }
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            statements: vec![
+                call(0).span(Some(span)),
+                call(2),
+                Statement::block(vec![call(0)]),
+            ],
+            solvers: vec![Solver::Div, Solver::Xor, Solver::Bits(1)],
+            ..Prog::default()
+        };
+
+        let usage = p.solver_usage();
+
+        assert_eq!(
+            usage,
+            vec![
+                SolverUsage {
+                    references: 2,
+                    spans: vec![span],
+                },
+                SolverUsage::default(),
+                SolverUsage {
+                    references: 1,
+                    spans: vec![],
+                },
+            ]
+        );
+        assert!(usage[1].is_unused());
+        assert!(!usage[0].is_unused());
+    }
+}
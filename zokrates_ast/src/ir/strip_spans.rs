@@ -0,0 +1,241 @@
+use super::folder::{
+    fold_argument, fold_constraint_statement, fold_directive_statement, fold_linear_combination,
+    fold_quadratic_combination, fold_statement_cases, Folder,
+};
+use super::{
+    ConstraintStatement, DirectiveStatement, LinComb, Parameter, ProgIterator, QuadComb,
+    ReturnSlot, RuntimeError, Solver, Statement,
+};
+use crate::common::{ModuleMap, SourceMetadata, WithSpan};
+use crate::zir::{
+    self, AssemblyConstraint, AssertionStatement, BooleanExpression, FieldElementExpression,
+    UBitwidth, UExpressionInner, ZirAssemblyStatement, ZirStatement,
+};
+use zokrates_field::Field;
+
+/// Removes source information from a program, so that compiling the same source in different locations yields the same bytes
+/// Spans are removed from statements, expressions, parameters, return slots and solvers, the module map is cleared and the file of assertion metadata is emptied.
+#[derive(Default)]
+pub struct StripSpans;
+
+impl<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>> ProgIterator<'ast, T, I> {
+    pub fn strip_spans(
+        self,
+    ) -> ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>> {
+        ProgIterator {
+            module_map: ModuleMap::default(),
+            arguments: self
+                .arguments
+                .into_iter()
+                .map(|a| <StripSpans as Folder<T>>::fold_argument(&mut StripSpans, a))
+                .collect(),
+            return_count: self.return_count,
+            returns: self
+                .returns
+                .into_iter()
+                .map(|slot| ReturnSlot { span: None, ..slot })
+                .collect(),
+            embeds_used: self.embeds_used,
+            statements: self
+                .statements
+                .into_iter()
+                .flat_map(|s| Folder::fold_statement(&mut StripSpans, s)),
+            solvers: self
+                .solvers
+                .into_iter()
+                .map(|s| StripSpans.fold_solver(s))
+                .collect(),
+        }
+    }
+}
+
+fn strip_metadata(metadata: SourceMetadata) -> SourceMetadata {
+    SourceMetadata {
+        file: String::new(),
+        ..metadata
+    }
+}
+
+impl StripSpans {
+    pub(super) fn fold_solver<'ast, T: Field>(&mut self, s: Solver<'ast, T>) -> Solver<'ast, T> {
+        match s {
+            Solver::Zir(f) => Solver::Zir(zir::Folder::fold_function(&mut ZirStripSpans, f)),
+            s => s,
+        }
+    }
+}
+
+/// The counterpart of `StripSpans` for the zir functions of solvers
+struct ZirStripSpans;
+
+impl<'ast, T: Field> Folder<'ast, T> for StripSpans {
+    fn fold_argument(&mut self, p: Parameter) -> Parameter {
+        fold_argument(self, p).span(None)
+    }
+
+    fn fold_statement(&mut self, s: Statement<'ast, T>) -> Vec<Statement<'ast, T>> {
+        fold_statement_cases(self, s)
+            .into_iter()
+            .map(|s| s.span(None))
+            .collect()
+    }
+
+    fn fold_constraint_statement(&mut self, s: ConstraintStatement<T>) -> Vec<Statement<'ast, T>> {
+        let error = s.error.map(|e| match e {
+            RuntimeError::SourceAssertion(m) => RuntimeError::SourceAssertion(strip_metadata(m)),
+            RuntimeError::SourceAssertionMessage(m, message) => {
+                RuntimeError::SourceAssertionMessage(strip_metadata(m), message)
+            }
+            RuntimeError::SourceAssemblyConstraint(m) => {
+                RuntimeError::SourceAssemblyConstraint(strip_metadata(m))
+            }
+            e => e,
+        });
+
+        fold_constraint_statement(self, ConstraintStatement { error, ..s })
+    }
+
+    fn fold_directive_statement(
+        &mut self,
+        d: DirectiveStatement<'ast, T>,
+    ) -> Vec<Statement<'ast, T>> {
+        let solver = self.fold_solver(d.solver);
+        fold_directive_statement(self, DirectiveStatement { solver, ..d })
+    }
+
+    fn fold_linear_combination(&mut self, e: LinComb<T>) -> LinComb<T> {
+        fold_linear_combination(self, e).span(None)
+    }
+
+    fn fold_quadratic_combination(&mut self, e: QuadComb<T>) -> QuadComb<T> {
+        fold_quadratic_combination(self, e).span(None)
+    }
+}
+
+impl<'ast, T: Field> zir::Folder<'ast, T> for ZirStripSpans {
+    fn fold_parameter(&mut self, p: zir::Parameter<'ast>) -> zir::Parameter<'ast> {
+        zir::Parameter {
+            id: zir::Folder::<T>::fold_variable(self, p.id),
+            ..p
+        }
+        .span(None)
+    }
+
+    fn fold_statement(&mut self, s: ZirStatement<'ast, T>) -> Vec<ZirStatement<'ast, T>> {
+        zir::Folder::fold_statement_cases(self, s)
+            .into_iter()
+            .map(|s| s.span(None))
+            .collect()
+    }
+
+    fn fold_assembly_statement(
+        &mut self,
+        s: ZirAssemblyStatement<'ast, T>,
+    ) -> Vec<ZirAssemblyStatement<'ast, T>> {
+        zir::Folder::fold_assembly_statement_cases(self, s)
+            .into_iter()
+            .map(|s| s.span(None))
+            .collect()
+    }
+
+    fn fold_assembly_constraint(
+        &mut self,
+        s: AssemblyConstraint<'ast, T>,
+    ) -> Vec<ZirAssemblyStatement<'ast, T>> {
+        zir::folder::fold_assembly_constraint(
+            self,
+            AssemblyConstraint {
+                metadata: strip_metadata(s.metadata),
+                ..s
+            },
+        )
+    }
+
+    fn fold_assertion_statement(
+        &mut self,
+        s: AssertionStatement<'ast, T>,
+    ) -> Vec<ZirStatement<'ast, T>> {
+        let error = match s.error {
+            zir::RuntimeError::SourceAssertion(m) => {
+                zir::RuntimeError::SourceAssertion(strip_metadata(m))
+            }
+            e => e,
+        };
+
+        zir::folder::fold_assertion_statement(self, AssertionStatement { error, ..s })
+    }
+
+    fn fold_field_expression(
+        &mut self,
+        e: FieldElementExpression<'ast, T>,
+    ) -> FieldElementExpression<'ast, T> {
+        zir::Folder::fold_field_expression_cases(self, e).span(None)
+    }
+
+    fn fold_boolean_expression(
+        &mut self,
+        e: BooleanExpression<'ast, T>,
+    ) -> BooleanExpression<'ast, T> {
+        zir::Folder::fold_boolean_expression_cases(self, e).span(None)
+    }
+
+    fn fold_uint_expression_inner(
+        &mut self,
+        bitwidth: UBitwidth,
+        e: UExpressionInner<'ast, T>,
+    ) -> UExpressionInner<'ast, T> {
+        zir::Folder::fold_uint_expression_cases(self, bitwidth, e).span(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{SourceSpan, Span};
+    use crate::ir::{Prog, Variable};
+    use std::io::Cursor;
+    use zokrates_field::Bn128Field;
+
+    fn prog(file: &str, span: Option<Span>) -> Prog<'static, Bn128Field> {
+        Prog {
+            module_map: ModuleMap::new(vec![file.into()]),
+            arguments: vec![Parameter::private(Variable::new(0)).span(span)],
+            return_count: 1,
+            statements: vec![Statement::constraint(
+                QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+                Variable::public(0),
+                Some(RuntimeError::SourceAssertion(SourceMetadata::new(
+                    file.into(),
+                    Default::default(),
+                ))),
+            )
+            .span(span)],
+            ..Prog::default()
+        }
+    }
+
+    fn serialize(p: Prog<Bn128Field>) -> Vec<u8> {
+        let mut buffer = Cursor::new(vec![]);
+        p.strip_spans().collect().serialize(&mut buffer).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn identical_bytes() {
+        let a = prog("/home/alice/main.zok", Some(SourceSpan::mock().into()));
+        let b = prog("/home/bob/main.zok", None);
+
+        assert_eq!(serialize(a), serialize(b));
+    }
+
+    #[test]
+    fn strip() {
+        let p = prog("main.zok", Some(SourceSpan::mock().into()))
+            .strip_spans()
+            .collect();
+
+        assert_eq!(p.module_map, ModuleMap::default());
+        assert_eq!(p.arguments[0].get_span(), None);
+        assert_eq!(p.statements[0].get_span(), None);
+    }
+}
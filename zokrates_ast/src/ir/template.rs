@@ -0,0 +1,70 @@
+use super::Parameter;
+use crate::common::Visibility;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// a JSON skeleton of the inputs of a program with parameters `parameters`, to be filled in with concrete values
+///
+/// The template maps `public`, `private` and `committed` to the parameters of that visibility, in declaration order.
+/// Each parameter is keyed by its source-level name, or by its variable id if it has none or if an earlier parameter has the same name,
+/// and given the placeholder value `"0"`. Visibilities without parameters are omitted.
+/// Only the parameters are needed, so they can be read without the rest of the program, see `read_parameters`.
+pub fn generate_input_template(parameters: &[Parameter]) -> Value {
+    let mut names = HashSet::new();
+    let mut groups = [
+        ("public", Map::new()),
+        ("private", Map::new()),
+        ("committed", Map::new()),
+    ];
+
+    for p in parameters {
+        let name = match p.name() {
+            name if names.insert(name.clone()) => name,
+            _ => p.id.to_string(),
+        };
+
+        let group = match p.visibility {
+            Visibility::Public => 0,
+            Visibility::Private => 1,
+            Visibility::Committed => 2,
+        };
+
+        groups[group]
+            .1
+            .insert(name, Value::String(String::from("0")));
+    }
+
+    Value::Object(
+        groups
+            .into_iter()
+            .filter(|(_, group)| !group.is_empty())
+            .map(|(visibility, group)| (String::from(visibility), Value::Object(group)))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Variable;
+    use serde_json::json;
+
+    #[test]
+    fn template() {
+        let parameters = vec![
+            Parameter::private(Variable::new(0)).with_name(Some(String::from("a"))),
+            Parameter::public(Variable::new(1)).with_name(Some(String::from("b"))),
+            Parameter::private(Variable::new(2)),
+            Parameter::private(Variable::new(3)).with_name(Some(String::from("a"))),
+        ];
+
+        assert_eq!(
+            generate_input_template(&parameters),
+            json!({
+                "public": { "b": "0" },
+                "private": { "a": "0", "_2": "0", "_3": "0" }
+            })
+        );
+        assert_eq!(generate_input_template(&[]), json!({}));
+    }
+}
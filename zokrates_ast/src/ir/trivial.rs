@@ -0,0 +1,98 @@
+use super::folder::{fold_constraint_statement, Folder};
+use super::{ConstraintStatement, Statement};
+use zokrates_field::Field;
+
+/// Removes the constraints which hold for every assignment of their variables, such as `x == x` or `0 == 0`
+///
+/// A constraint `A * B == C` is removed if and only if, once duplicate terms are merged and zero terms are dropped,
+/// `A` or `B` is a constant `k` (which includes zero) and `C` is exactly `k` times the other side.
+/// Such a constraint is an identity, given that `~one` is 1: it does not restrict any variable, so removing it keeps the solutions of the program.
+/// Any constraint whose sides differ once reduced is kept, in particular definitions like `~one * x == y` which bind `y` to `x`,
+/// and the interpreter can never use a removed constraint to assign a variable, as the variables of its right side also appear on its left side.
+#[derive(Default)]
+pub struct RemoveTrivialConstraints {
+    removed: usize,
+}
+
+impl RemoveTrivialConstraints {
+    /// the number of constraints removed so far
+    pub fn removed(&self) -> usize {
+        self.removed
+    }
+}
+
+/// whether `s` holds for every assignment of its variables, see `RemoveTrivialConstraints`
+fn is_trivial<T: Field>(s: &ConstraintStatement<T>) -> bool {
+    match s.quad.clone().reduce().try_linear() {
+        Ok(quad) => quad.reduce() == s.lin.clone().reduce(),
+        Err(_) => false,
+    }
+}
+
+impl<'ast, T: Field> Folder<'ast, T> for RemoveTrivialConstraints {
+    fn fold_constraint_statement(&mut self, s: ConstraintStatement<T>) -> Vec<Statement<'ast, T>> {
+        if is_trivial(&s) {
+            self.removed += 1;
+            return vec![];
+        }
+
+        fold_constraint_statement(self, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{LinComb, Parameter, Prog, QuadComb, Variable};
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn remove_trivial() {
+        let x = || LinComb::from(Variable::new(0));
+        let y = || LinComb::from(Variable::new(1));
+
+        let trivial: Vec<Statement<Bn128Field>> = vec![
+            // x == x
+            Statement::constraint(x(), x(), None),
+            // 0 == 0
+            Statement::constraint(LinComb::zero(), LinComb::zero(), None),
+            // x * (y - y) == 0
+            Statement::constraint(QuadComb::new(x(), y() - y()), LinComb::zero(), None),
+            // (2 * x) * 3 == 6 * x
+            Statement::constraint(
+                QuadComb::new(
+                    LinComb::summand(2, Variable::new(0)),
+                    LinComb::summand(3, Variable::one()),
+                ),
+                LinComb::summand(6, Variable::new(0)),
+                None,
+            ),
+        ];
+
+        let binding: Vec<Statement<Bn128Field>> = vec![
+            // y is defined as x
+            Statement::constraint(x(), y(), None),
+            // x is boolean
+            Statement::constraint(QuadComb::new(x(), x()), x(), None),
+            // x == 0 is not an identity, even though its right side is zero
+            Statement::constraint(x(), LinComb::zero(), None),
+            // x * y == x + y - y only holds for some values of y
+            Statement::constraint(QuadComb::new(x(), y()), x() + y() - y(), None),
+        ];
+
+        let p = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            statements: trivial.into_iter().chain(binding.clone()).collect(),
+            ..Prog::default()
+        };
+
+        let mut remover = RemoveTrivialConstraints::default();
+        let p = remover.fold_program(p);
+
+        assert_eq!(remover.removed(), 4);
+        assert_eq!(p.statements, binding);
+    }
+}
@@ -0,0 +1,92 @@
+use super::visitor::Visitor;
+use super::{Prog, Statement};
+use crate::common::flat::Variable;
+use std::collections::HashSet;
+use zokrates_field::Field;
+
+#[derive(Default)]
+struct VariableCollector {
+    variables: HashSet<Variable>,
+}
+
+impl<T: Field> Visitor<T> for VariableCollector {
+    fn visit_variable(&mut self, v: &Variable) {
+        self.variables.insert(*v);
+    }
+}
+
+/// the variables referenced by `s`, including the ones of nested statements
+pub fn collect_variables<T: Field>(s: &Statement<T>) -> HashSet<Variable> {
+    let mut collector = VariableCollector::default();
+    collector.visit_statement(s);
+    collector.variables
+}
+
+/// the variables referenced by the arguments, the statements and the returns of `p`
+pub fn collect_variables_prog<T: Field>(p: &Prog<T>) -> HashSet<Variable> {
+    let mut collector = VariableCollector::default();
+    collector.visit_module(p);
+    collector.variables.extend(p.returns());
+    collector.variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::FormatString;
+    use crate::ir::{LinComb, Parameter, QuadComb, Solver};
+    use crate::typed::ConcreteType;
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn collect() {
+        let directive = Statement::directive(
+            vec![Variable::new(1)],
+            Solver::Bits(1),
+            vec![Variable::new(0).into()],
+        );
+        let log = Statement::log(
+            FormatString::from("{}"),
+            vec![(ConcreteType::FieldElement, vec![Variable::new(2).into()])],
+        );
+
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                directive.clone(),
+                Statement::block(vec![Statement::constraint(
+                    QuadComb::new(Variable::new(1).into(), LinComb::one()),
+                    Variable::new(3),
+                    None,
+                )]),
+                log.clone(),
+            ],
+            ..Prog::default()
+        };
+
+        assert_eq!(
+            collect_variables(&directive),
+            vec![Variable::new(0), Variable::new(1)]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            collect_variables(&log),
+            vec![Variable::new(2)].into_iter().collect()
+        );
+        assert_eq!(
+            collect_variables_prog(&p),
+            vec![
+                Variable::one(),
+                Variable::new(0),
+                Variable::new(1),
+                Variable::new(2),
+                Variable::new(3),
+                Variable::public(0)
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::ProgHeader;
 use crate::common::flat::Variable;
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
@@ -5,6 +6,14 @@ use std::io;
 use std::io::{Read, Write};
 use zokrates_field::Field;
 
+/// The magic number of a serialized witness, `ZKW`
+const WITNESS_MAGIC: &[u8; 4] = &[0x5a, 0x4b, 0x57, 0];
+const WITNESS_VERSION: &[u8; 4] = &[1, 0, 0, 0];
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
 pub struct Witness<T>(pub BTreeMap<Variable, T>);
 
@@ -41,22 +50,109 @@ impl<T: Field> Witness<T> {
         Witness(BTreeMap::new())
     }
 
+    /// write this witness, after a header with its own magic number, its version and the id of the curve it is defined over
+    /// The assignments follow as a sequence of CBOR `(variable, value)` items, like the statements of a program
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
-        let length = self.0.len();
-        writer.write_all(&length.to_le_bytes())?;
+        writer.write_all(WITNESS_MAGIC)?;
+        writer.write_all(WITNESS_VERSION)?;
+        writer.write_all(&T::id())?;
 
-        for (variable, value) in &self.0 {
-            variable.write(&mut writer)?;
-            value.write(&mut writer)?;
+        for assignment in &self.0 {
+            serde_cbor::to_writer(&mut writer, &assignment).map_err(io::Error::other)?;
         }
+
         Ok(())
     }
 
+    /// read a witness over `T`, failing if it was written for another curve
+    /// Witnesses written before the header was introduced are read without checking their curve
     pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != WITNESS_MAGIC {
+            return Self::read_legacy(magic, reader);
+        }
+
+        let mut version = [0; 4];
+        reader.read_exact(&mut version)?;
+
+        if &version != WITNESS_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported witness version `{:?}`",
+                version
+            )));
+        }
+
+        let mut curve_id = [0; 4];
+        reader.read_exact(&mut curve_id)?;
+
+        if curve_id != T::id() {
+            return Err(invalid_data(format!(
+                "Expected a witness over curve `{}`, found curve id `{:?}`",
+                T::name(),
+                curve_id
+            )));
+        }
+
+        serde_cbor::Deserializer::from_reader(reader)
+            .into_iter::<(Variable, T)>()
+            .collect::<Result<_, _>>()
+            .map(Witness)
+            .map_err(|e| invalid_data(e.to_string()))
+    }
+
+    /// read a witness for the program described by `header`
+    /// The witness must be over the curve of the program, and only assign outputs of the program
+    /// If the header records the variable count of the program, the witness must also only assign its intermediate variables
+    pub fn read_for_program<R: Read>(reader: R, header: &ProgHeader) -> io::Result<Self> {
+        if header.curve_id != T::id() {
+            return Err(invalid_data(format!(
+                "Expected a program over curve `{}`, found `{}`",
+                T::name(),
+                header.curve_name().unwrap_or("unknown")
+            )));
+        }
+
+        let witness = Self::read(reader)?;
+
+        let return_count = header.return_count as usize;
+        // outputs are numbered from `~out_0`, so the last output is the smallest variable
+        if let Some(v) = witness.0.keys().next().filter(|v| {
+            v.is_output() && (return_count == 0 || **v < Variable::public(return_count - 1))
+        }) {
+            return Err(invalid_data(format!(
+                "Witness assigns `{}`, but the program only has {} outputs",
+                v, return_count
+            )));
+        }
+
+        if let Some(variable_count) = header.variable_count {
+            // the variable count includes `~one` and the outputs, and intermediate variables are numbered from `_0`
+            let intermediates = (variable_count as usize).saturating_sub(1 + return_count);
+            if let Some(v) = witness
+                .0
+                .keys()
+                .next_back()
+                .filter(|v| v.id > 0 && v.id() >= intermediates)
+            {
+                return Err(invalid_data(format!(
+                    "Witness assigns `{}`, but the program only has {} variables",
+                    v, variable_count
+                )));
+            }
+        }
+
+        Ok(witness)
+    }
+
+    /// read a witness in the format without header, given its first bytes
+    fn read_legacy<R: Read>(prefix: [u8; 4], mut reader: R) -> io::Result<Self> {
         let mut witness = Self::empty();
 
         let mut buf = [0; std::mem::size_of::<usize>()];
-        reader.read_exact(&mut buf)?;
+        buf[..prefix.len()].copy_from_slice(&prefix);
+        reader.read_exact(&mut buf[prefix.len()..])?;
 
         let length: usize = usize::from_le_bytes(buf);
 
@@ -99,7 +195,7 @@ impl<T: Field> fmt::Display for Witness<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use zokrates_field::Bn128Field;
+    use zokrates_field::{Bls12_381Field, Bn128Field};
 
     mod io {
         use super::*;
@@ -127,6 +223,98 @@ mod tests {
             assert_eq!(w, r);
         }
 
+        #[test]
+        fn deserialize_legacy() {
+            let w = Witness(
+                vec![
+                    (Variable::new(42), Bn128Field::from(42)),
+                    (Variable::public(8), Bn128Field::from(8)),
+                ]
+                .into_iter()
+                .collect(),
+            );
+
+            let mut buff = Cursor::new(vec![]);
+            buff.write_all(&w.0.len().to_le_bytes()).unwrap();
+            for (variable, value) in &w.0 {
+                variable.write(&mut buff).unwrap();
+                value.write(&mut buff).unwrap();
+            }
+            buff.set_position(0);
+
+            assert_eq!(Witness::read(buff).unwrap(), w);
+        }
+
+        #[test]
+        fn curve_mismatch() {
+            let w = Witness(
+                vec![(Variable::one(), Bn128Field::from(1))]
+                    .into_iter()
+                    .collect(),
+            );
+
+            let mut buff = Cursor::new(vec![]);
+            w.write(&mut buff).unwrap();
+            buff.set_position(0);
+
+            assert!(Witness::<Bls12_381Field>::read(buff).is_err());
+        }
+
+        #[test]
+        fn read_for_program() {
+            let w = Witness(
+                vec![
+                    (Variable::one(), Bn128Field::from(1)),
+                    (Variable::public(1), Bn128Field::from(1)),
+                ]
+                .into_iter()
+                .collect(),
+            );
+
+            let mut buff = Cursor::new(vec![]);
+            w.write(&mut buff).unwrap();
+
+            let header = |return_count| {
+                let mut p = Cursor::new(vec![]);
+                crate::ir::Prog::<Bn128Field> {
+                    return_count,
+                    ..Default::default()
+                }
+                .serialize(&mut p)
+                .unwrap();
+                p.set_position(0);
+                ProgHeader::read(p).unwrap()
+            };
+
+            buff.set_position(0);
+            assert_eq!(Witness::read_for_program(&mut buff, &header(2)).unwrap(), w);
+
+            buff.set_position(0);
+            assert!(Witness::<Bn128Field>::read_for_program(&mut buff, &header(1)).is_err());
+
+            buff.set_position(0);
+            assert!(Witness::<Bls12_381Field>::read_for_program(&mut buff, &header(2)).is_err());
+
+            // `~one`, `~out_0`, `~out_1` and `_0`
+            let mut program = header(2);
+            program.variable_count = Some(4);
+
+            let mut w = w;
+            w.insert(Variable::new(0), Bn128Field::from(1));
+            let mut buff = Cursor::new(vec![]);
+            w.write(&mut buff).unwrap();
+
+            buff.set_position(0);
+            assert_eq!(Witness::read_for_program(&mut buff, &program).unwrap(), w);
+
+            w.insert(Variable::new(1), Bn128Field::from(1));
+            let mut buff = Cursor::new(vec![]);
+            w.write(&mut buff).unwrap();
+
+            buff.set_position(0);
+            assert!(Witness::<Bn128Field>::read_for_program(&mut buff, &program).is_err());
+        }
+
         #[test]
         fn serialize_json() {
             let w = Witness(
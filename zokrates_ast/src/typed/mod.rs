@@ -119,7 +119,7 @@ impl<'ast, T: Field> TypedProgram<'ast, T> {
                         .unwrap(),
                     )
                     .map(|ty| AbiInput {
-                        public: !p.private,
+                        public: p.visibility.is_public(),
                         name: p.id.id.to_string(),
                         ty,
                     })
@@ -48,7 +48,7 @@ mod tests {
     #[test]
     fn canonicalize() {
         let func = ZirFunction::<Bn128Field> {
-            arguments: vec![Parameter::new(Variable::field_element("a"), true)],
+            arguments: vec![Parameter::private(Variable::field_element("a"))],
             statements: vec![
                 ZirStatement::definition(
                     ZirAssignee::field_element("b"),
@@ -69,10 +69,9 @@ mod tests {
         let result = canonicalizer.fold_function(func);
 
         let expected = ZirFunction::<Bn128Field> {
-            arguments: vec![Parameter::new(
-                Variable::field_element(Identifier::internal(0usize)),
-                true,
-            )],
+            arguments: vec![Parameter::private(Variable::field_element(
+                Identifier::internal(0usize),
+            ))],
             statements: vec![
                 ZirStatement::definition(
                     ZirAssignee::field_element(Identifier::internal(1usize)),
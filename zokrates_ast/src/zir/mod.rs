@@ -23,6 +23,7 @@ use crate::typed::ConcreteType;
 pub use crate::zir::uint::{ShouldReduce, UExpression, UExpressionInner, UMetadata};
 
 use crate::zir::types::Signature;
+use std::collections::BTreeSet;
 use std::fmt;
 
 use derivative::Derivative;
@@ -46,6 +47,32 @@ impl<'ast, T: fmt::Display> fmt::Display for ZirProgram<'ast, T> {
         write!(f, "{}", self.main)
     }
 }
+
+impl<'ast, T> ZirProgram<'ast, T> {
+    /// the embeds called by this program, which are inlined when it is flattened
+    pub fn embeds_used(&self) -> BTreeSet<FlatEmbed> {
+        fn visit<T>(statements: &[ZirStatement<T>], embeds: &mut BTreeSet<FlatEmbed>) {
+            for s in statements {
+                match s {
+                    ZirStatement::MultipleDefinition(s) => match &s.rhs {
+                        ZirExpressionList::EmbedCall(embed, ..) => {
+                            embeds.insert(*embed);
+                        }
+                    },
+                    ZirStatement::IfElse(s) => {
+                        visit(&s.consequence, embeds);
+                        visit(&s.alternative, embeds);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut embeds = BTreeSet::new();
+        visit(&self.main.statements, &mut embeds);
+        embeds
+    }
+}
 /// A typed function
 #[derive(Derivative)]
 #[derivative(PartialEq, Hash, Eq)]
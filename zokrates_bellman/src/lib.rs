@@ -100,7 +100,7 @@ impl<'a, T: BellmanFieldExtensions + Field, I: IntoIterator<Item = Statement<'a,
         assert!(symbols.insert(Variable::one(), CS::one()).is_none());
 
         symbols.extend(self.program.arguments.iter().enumerate().map(|(index, p)| {
-            let wire = match p.private {
+            let wire = match p.visibility.is_private() {
                 true => cs.alloc(
                     || format!("PRIVATE_INPUT_{}", index),
                     || {
@@ -273,6 +273,8 @@ mod tests {
                 module_map: Default::default(),
                 arguments: vec![Parameter::private(Variable::new(0))],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     Variable::new(0),
                     Variable::public(0),
@@ -305,6 +307,8 @@ mod tests {
                 module_map: Default::default(),
                 arguments: vec![Parameter::public(Variable::new(0))],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     Variable::new(0),
                     Variable::public(0),
@@ -337,6 +341,8 @@ mod tests {
                 module_map: Default::default(),
                 arguments: vec![],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     Variable::one(),
                     Variable::public(0),
@@ -373,6 +379,8 @@ mod tests {
                     Parameter::public(Variable::new(51)),
                 ],
                 return_count: 2,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![
                     Statement::constraint(
                         LinComb::from(Variable::new(42)) + LinComb::from(Variable::new(51)),
@@ -411,6 +419,8 @@ mod tests {
                 module_map: Default::default(),
                 arguments: vec![Parameter::public(Variable::new(42))],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     LinComb::from(Variable::new(42)) + LinComb::one(),
                     Variable::public(0),
@@ -446,6 +456,8 @@ mod tests {
                     Parameter::public(Variable::new(51)),
                 ],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     LinComb::from(Variable::new(42)) + LinComb::from(Variable::new(51)),
                     Variable::public(0),
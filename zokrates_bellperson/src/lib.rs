@@ -84,7 +84,7 @@ impl<'ast, T: BellpersonFieldExtensions + Field> Circuit<T::BellpersonField>
         assert!(symbols.insert(Variable::one(), CS::one()).is_none());
 
         symbols.extend(self.program.arguments.iter().enumerate().map(|(index, p)| {
-            let wire = match p.private {
+            let wire = match p.visibility.is_private() {
                 true => {
                     AllocatedNum::alloc(cs.namespace(|| format!("PRIVATE_INPUT_{}", index)), || {
                         Ok(witness
@@ -346,6 +346,8 @@ mod tests {
             let program: Prog<PallasField> = Prog {
                 arguments: vec![Parameter::public(Variable::new(0))],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     Variable::new(0),
                     Variable::public(0),
@@ -368,6 +370,8 @@ mod tests {
             let program = Prog {
                 arguments: vec![Parameter::public(Variable::new(42))],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     LinComb::from(Variable::new(42)) + LinComb::one(),
                     Variable::public(0),
@@ -393,6 +397,8 @@ mod tests {
                     Parameter::public(Variable::new(51)),
                 ],
                 return_count: 2,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![
                     Statement::constraint(
                         LinComb::from(Variable::new(42)) + LinComb::from(Variable::new(51)),
@@ -432,6 +438,8 @@ mod tests {
                     Parameter::private(Variable::new(1)),
                 ],
                 return_count: 1,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![Statement::constraint(
                     LinComb::from(Variable::new(0)) + LinComb::from(Variable::new(1)),
                     Variable::public(0),
@@ -470,6 +478,8 @@ mod tests {
                     Parameter::private(Variable::new(3)),
                 ],
                 return_count: 2,
+                returns: vec![],
+                embeds_used: Default::default(),
                 statements: vec![
                     Statement::constraint(
                         LinComb::from(Variable::new(0)) + LinComb::from(Variable::new(2)),
@@ -1,7 +1,9 @@
 mod r1cs;
+mod r1cs_json;
 mod witness;
 
 pub use r1cs::write_r1cs;
+pub use r1cs_json::write_r1cs_json;
 pub use witness::write_witness;
 
 #[cfg(test)]
@@ -30,6 +32,8 @@ mod tests {
                 Parameter::public(Variable::new(1)),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements: vec![
                 Statement::constraint(
                     QuadComb::new(
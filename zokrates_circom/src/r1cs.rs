@@ -58,7 +58,7 @@ pub fn r1cs_program<T: Field>(prog: Prog<T>) -> (Vec<Variable>, usize, Vec<Const
         provide_variable_idx(&mut variables, &Variable::public(i));
     }
 
-    for x in prog.arguments.iter().filter(|p| !p.private) {
+    for x in prog.arguments.iter().filter(|p| p.visibility.is_public()) {
         provide_variable_idx(&mut variables, &x.id);
     }
 
@@ -131,8 +131,16 @@ pub fn write_r1cs<T: Field, W: Write>(writer: &mut W, p: Prog<T>) -> Result<()>
     let modulo_byte_count = T::max_value().to_biguint().add(1u32).to_bytes_le().len() as u32;
 
     let n_pub_out = p.return_count as u32;
-    let n_pub_in = p.arguments.iter().filter(|a| !a.private).count() as u32;
-    let n_prv_in = p.arguments.iter().filter(|a| a.private).count() as u32;
+    let n_pub_in = p
+        .arguments
+        .iter()
+        .filter(|a| a.visibility.is_public())
+        .count() as u32;
+    let n_prv_in = p
+        .arguments
+        .iter()
+        .filter(|a| a.visibility.is_private())
+        .count() as u32;
 
     let (vars, _, constraints) = r1cs_program(p);
 
@@ -292,6 +300,8 @@ mod tests {
             module_map: Default::default(),
             arguments: vec![],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements: vec![Statement::constraint(
                 LinComb::one(),
                 Variable::public(0),
@@ -353,6 +363,8 @@ mod tests {
                 Parameter::public(Variable::new(1)),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             statements: vec![
                 Statement::constraint(
                     QuadComb::new(
@@ -0,0 +1,134 @@
+use crate::r1cs::r1cs_program;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::io::{Result, Write};
+use std::ops::Add;
+use zokrates_ast::ir::Prog;
+use zokrates_field::Field;
+
+/// Write `p` as the JSON representation of an R1CS used by snarkjs (`snarkjs r1cs export json`)
+///
+/// Each constraint is a list of three maps `A`, `B` and `C` from wire index to coefficient, such that `<A,w> * <B,w> = <C,w>`.
+/// Coefficients are written as decimal strings. Wires are laid out as in `write_r1cs`: the constant one, the public outputs,
+/// the public inputs, then the other variables.
+pub fn write_r1cs_json<T: Field, W: Write>(writer: W, p: Prog<T>) -> Result<()> {
+    let n_pub_out = p.return_count;
+    let n_pub_in = p
+        .arguments
+        .iter()
+        .filter(|a| a.visibility.is_public())
+        .count();
+    let n_prv_in = p
+        .arguments
+        .iter()
+        .filter(|a| a.visibility.is_private())
+        .count();
+
+    let (vars, _, constraints) = r1cs_program(p);
+
+    let prime = T::max_value().to_biguint().add(1u32);
+
+    let constraints: Vec<Value> = constraints
+        .into_iter()
+        .map(|(a, b, c)| json!([to_json(a), to_json(b), to_json(c)]))
+        .collect();
+
+    let r1cs = json!({
+        "n8": prime.to_bytes_le().len(),
+        "prime": prime.to_str_radix(10),
+        "nVars": vars.len(),
+        "nOutputs": n_pub_out,
+        "nPubInputs": n_pub_in,
+        "nPrvInputs": n_prv_in,
+        "nLabels": vars.len(),
+        "nConstraints": constraints.len(),
+        "constraints": constraints,
+        // for now we do not write any signal map, see `write_r1cs`
+        "map": (0..vars.len()).collect::<Vec<_>>(),
+    });
+
+    serde_json::to_writer_pretty(writer, &r1cs)?;
+
+    Ok(())
+}
+
+/// Sum the coefficients of each wire of `l` into a map from wire index to decimal coefficient
+fn to_json<T: Field>(l: Vec<(usize, T)>) -> Value {
+    let mut summands = BTreeMap::new();
+    for (wire, coefficient) in l {
+        let sum = summands.entry(wire).or_insert_with(T::zero);
+        *sum = sum.clone() + coefficient;
+    }
+
+    Value::Object(
+        summands
+            .into_iter()
+            .filter(|(_, coefficient)| *coefficient != T::zero())
+            .map(|(wire, coefficient)| {
+                (wire.to_string(), Value::String(coefficient.to_dec_string()))
+            })
+            .collect::<Map<_, _>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use zokrates_ast::{
+        flat::{Parameter, Variable},
+        ir::{LinComb, QuadComb, Statement},
+    };
+    use zokrates_field::Bn128Field;
+
+    #[test]
+    fn with_inputs() {
+        let prog: Prog<Bn128Field> = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::public(Variable::new(1)),
+            ],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(
+                    QuadComb::new(
+                        LinComb::from(Variable::new(0)),
+                        LinComb::from(Variable::new(0)),
+                    ),
+                    LinComb::summand(2, Variable::new(0)),
+                    None,
+                ),
+                Statement::constraint(
+                    LinComb::from(Variable::new(0)) + LinComb::from(Variable::new(1)),
+                    Variable::public(0),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        };
+
+        let mut buf = Vec::new();
+        write_r1cs_json(&mut buf, prog).unwrap();
+        let r1cs: Value = serde_json::from_slice(&buf).unwrap();
+
+        // wires are: one, ~out_0, _1, _0
+        assert_eq!(
+            r1cs,
+            json!({
+                "n8": 32,
+                "prime": "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+                "nVars": 4,
+                "nOutputs": 1,
+                "nPubInputs": 1,
+                "nPrvInputs": 1,
+                "nLabels": 4,
+                "nConstraints": 2,
+                "constraints": [
+                    [{ "3": "1" }, { "3": "1" }, { "3": "2" }],
+                    [{ "0": "1" }, { "2": "1", "3": "1" }, { "1": "1" }]
+                ],
+                "map": [0, 1, 2, 3]
+            })
+        );
+    }
+}
@@ -6,6 +6,7 @@ use std::fs::File;
 use std::io::{BufReader, BufWriter, Read};
 use std::path::{Path, PathBuf};
 use typed_arena::Arena;
+use zokrates_ast::ir::SerializeOptions;
 use zokrates_circom::write_r1cs;
 use zokrates_common::constants::BN128;
 use zokrates_common::{helpers::CurveParameter, CompileConfig};
@@ -83,6 +84,19 @@ pub fn subcommand() -> App<'static, 'static> {
                 .help("Include logs")
                 .required(false),
         )
+        .arg(
+            Arg::with_name("strip-spans")
+                .long("strip-spans")
+                .help("Remove source information from the compiled program, so that it does not depend on where it was compiled")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("source-hash")
+                .long("source-hash")
+                .help("Record a hash of the source files in the compiled program, so that they can be checked with `inspect --verify-source`")
+                .required(false)
+                .conflicts_with("strip-spans"),
+        )
 }
 
 pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
@@ -171,9 +185,29 @@ fn cli_compile<T: Field>(sub_matches: &ArgMatches) -> Result<(), String> {
         .module_map
         .remap_prefix(Path::new(stdlib_path), Path::new("STDLIB"));
 
+    if sub_matches.is_present("strip-spans") {
+        program_flattened = program_flattened.strip_spans().collect();
+    }
+
+    let source_hash = match sub_matches.is_present("source-hash") {
+        true => program_flattened
+            .module_map
+            .source_hash(|module| {
+                std::fs::read(resolve_module(module, path.parent().unwrap(), stdlib_path))
+            })
+            .map_err(|why| format!("Could not hash the source files: {}", why))?,
+        false => [0; 32],
+    };
+
     write_r1cs(&mut r1cs_writer, program_flattened.clone()).unwrap();
 
-    match program_flattened.serialize(&mut bin_writer) {
+    // record the declared types of the inputs and outputs, which the program flattens to field elements
+    match program_flattened.serialize_with_options(
+        &mut bin_writer,
+        SerializeOptions::default()
+            .source_hash(source_hash)
+            .types(&abi),
+    ) {
         Ok(constraint_count) => {
             // serialize ABI spec and write to JSON file
             log::debug!("Serialize ABI");
@@ -197,3 +231,11 @@ fn cli_compile<T: Field>(sub_matches: &ArgMatches) -> Result<(), String> {
         }
     }
 }
+
+/// the path of a module of a compiled program, whose paths are relative to the directory of the entry point or to `STDLIB`
+pub fn resolve_module(module: &Path, root: &Path, stdlib_path: &str) -> PathBuf {
+    match module.strip_prefix("STDLIB") {
+        Ok(module) => Path::new(stdlib_path).join(module),
+        Err(_) => root.join(module),
+    }
+}
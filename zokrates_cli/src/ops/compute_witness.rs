@@ -81,7 +81,7 @@ pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
 
     let mut reader = BufReader::new(file);
 
-    match ProgEnum::deserialize(&mut reader)? {
+    match ProgEnum::deserialize_with_limits(&mut reader, &ir::ReadLimits::default())? {
         ProgEnum::Bn128Program(p) => cli_compute(p, sub_matches),
         ProgEnum::Bls12_377Program(p) => cli_compute(p, sub_matches),
         ProgEnum::Bls12_381Program(p) => cli_compute(p, sub_matches),
@@ -175,6 +175,7 @@ fn cli_compute<'a, T: Field, I: Iterator<Item = ir::Statement<'a, T>>>(
 
     let interpreter = zokrates_interpreter::Interpreter::default();
     let public_inputs = ir_prog.public_inputs();
+    let module_map = &ir_prog.module_map;
 
     let witness = interpreter
         .execute_with_log_stream(
@@ -184,7 +185,10 @@ fn cli_compute<'a, T: Field, I: Iterator<Item = ir::Statement<'a, T>>>(
             &ir_prog.solvers,
             &mut std::io::stdout(),
         )
-        .map_err(|e| format!("Execution failed: {}", e))?;
+        .map_err(|e| match e.span() {
+            Some(span) => format!("Execution failed: {} ({})", e, span.display(module_map)),
+            None => format!("Execution failed: {}", e),
+        })?;
 
     use zokrates_abi::Decode;
 
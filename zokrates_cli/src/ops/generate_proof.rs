@@ -98,7 +98,7 @@ pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
         .map_err(|why| format!("Could not open {}: {}", program_path.display(), why))?;
 
     let mut reader = BufReader::new(program_file);
-    let prog = ProgEnum::deserialize(&mut reader)?;
+    let prog = ProgEnum::deserialize_with_limits(&mut reader, &ir::ReadLimits::default())?;
 
     let curve_parameter = CurveParameter::try_from(prog.curve())?;
 
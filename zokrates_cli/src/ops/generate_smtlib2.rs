@@ -39,7 +39,7 @@ pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
 
     let mut reader = BufReader::new(file);
 
-    match ProgEnum::deserialize(&mut reader)? {
+    match ProgEnum::deserialize_with_limits(&mut reader, &ir::ReadLimits::default())? {
         ProgEnum::Bn128Program(p) => cli_smtlib2(p, sub_matches),
         ProgEnum::Bls12_377Program(p) => cli_smtlib2(p, sub_matches),
         ProgEnum::Bls12_381Program(p) => cli_smtlib2(p, sub_matches),
@@ -1,10 +1,12 @@
-use crate::cli_constants::FLATTENED_CODE_DEFAULT_PATH;
+use crate::cli_constants::{self, FLATTENED_CODE_DEFAULT_PATH};
+use crate::ops::compile::resolve_module;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
+use std::io::{BufReader, BufWriter, Read, Seek, Write};
 use std::path::{Path, PathBuf};
-use zokrates_ast::ir::{self, ProgEnum};
-use zokrates_field::Field;
+use zokrates_ast::common::ModuleMap;
+use zokrates_ast::ir::{self, ProgEnum, ProgHeader};
+use zokrates_field::{dispatch_by_curve_id, Field, FieldDispatch};
 
 pub fn subcommand() -> App<'static, 'static> {
     SubCommand::with_name("inspect")
@@ -25,6 +27,80 @@ pub fn subcommand() -> App<'static, 'static> {
                 .help("Writes human readable output (ztf) to a file")
                 .required(false),
         )
+        .arg(
+            Arg::with_name("summary")
+                .long("summary")
+                .help(
+                    "Only prints information available in the header, without loading the program",
+                )
+                .required(false)
+                .conflicts_with("ztf"),
+        )
+        .arg(
+            Arg::with_name("solvers")
+                .long("solvers")
+                .help("Prints a listing of the solvers of the program, without loading its constraints")
+                .required(false)
+                .conflicts_with_all(&["ztf", "summary"]),
+        )
+        .arg(
+            Arg::with_name("input-template")
+                .long("input-template")
+                .help("Prints a JSON template of the inputs of the program, without loading its constraints")
+                .required(false)
+                .conflicts_with_all(&["ztf", "summary", "solvers"]),
+        )
+        .arg(
+            Arg::with_name("verify-source")
+                .long("verify-source")
+                .help("Checks that the source files in the given directory match the hash recorded when compiling with `--source-hash`")
+                .value_name("PATH")
+                .takes_value(true)
+                .required(false)
+                .conflicts_with_all(&["ztf", "summary", "solvers", "input-template"]),
+        )
+        .arg(
+            Arg::with_name("stdlib-path")
+                .long("stdlib-path")
+                .help("Path to the standard library, used to find the standard library files with `--verify-source`")
+                .value_name("PATH")
+                .takes_value(true)
+                .required(false)
+                .env("ZOKRATES_STDLIB")
+                .default_value(cli_constants::DEFAULT_STDLIB_PATH.as_str()),
+        )
+}
+
+/// Disassembles the solvers of a program over any curve, reading its solvers section only
+struct SolversListing<R> {
+    r: R,
+    header: ProgHeader,
+}
+
+impl<R: Read + Seek> FieldDispatch for SolversListing<R> {
+    type Output = Result<Vec<String>, String>;
+
+    fn dispatch<T: Field>(self) -> Self::Output {
+        Ok(ProgEnum::try_read::<T>(self.r, &self.header)?
+            .solvers
+            .iter()
+            .map(|s| s.disassemble())
+            .collect())
+    }
+}
+
+/// Reads the module map of a program over any curve, without loading its constraints
+struct ModuleMapReader<R> {
+    r: R,
+    header: ProgHeader,
+}
+
+impl<R: Read + Seek> FieldDispatch for ModuleMapReader<R> {
+    type Output = Result<ModuleMap, String>;
+
+    fn dispatch<T: Field>(self) -> Self::Output {
+        Ok(ProgEnum::try_read::<T>(self.r, &self.header)?.module_map)
+    }
 }
 
 pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
@@ -35,7 +111,76 @@ pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
 
     let mut reader = BufReader::new(file);
 
-    match ProgEnum::deserialize(&mut reader)? {
+    if sub_matches.is_present("summary") {
+        let header = ProgHeader::read(&mut reader)
+            .map_err(|why| format!("Could not read header of `{}`: {}", path.display(), why))?;
+        print!("{}", header.summary());
+        return Ok(());
+    }
+
+    if sub_matches.is_present("solvers") {
+        let header = ProgHeader::read(&mut reader)
+            .map_err(|why| format!("Could not read header of `{}`: {}", path.display(), why))?;
+        let solvers = dispatch_by_curve_id(header.curve_id, SolversListing { r: reader, header })
+            .ok_or_else(|| String::from("Unknown curve identifier"))??;
+
+        println!("{:<17} {}", "solver_count:", solvers.len());
+        for (index, solver) in solvers.iter().enumerate() {
+            println!("#{} {}", index, solver);
+        }
+        return Ok(());
+    }
+
+    if sub_matches.is_present("input-template") {
+        let header = ProgHeader::read(&mut reader)
+            .map_err(|why| format!("Could not read header of `{}`: {}", path.display(), why))?;
+        let parameters = ir::read_parameters(&mut reader, &header)
+            .map_err(|why| format!("Could not read parameters of `{}`: {}", path.display(), why))?;
+
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&ir::generate_input_template(&parameters)).unwrap()
+        );
+        return Ok(());
+    }
+
+    if let Some(source_path) = sub_matches.value_of("verify-source") {
+        let header = ProgHeader::read(&mut reader)
+            .map_err(|why| format!("Could not read header of `{}`: {}", path.display(), why))?;
+
+        if header.source_hash == [0; 32] {
+            return Err(format!(
+                "`{}` does not record a hash of its source files, compile it with `--source-hash`",
+                path.display()
+            ));
+        }
+
+        let source_hash = header.source_hash;
+        let module_map =
+            dispatch_by_curve_id(header.curve_id, ModuleMapReader { r: reader, header })
+                .ok_or_else(|| String::from("Unknown curve identifier"))??;
+
+        let stdlib_path = sub_matches.value_of("stdlib-path").unwrap();
+        let hash = module_map
+            .source_hash(|module| {
+                std::fs::read(resolve_module(module, Path::new(source_path), stdlib_path))
+            })
+            .map_err(|why| format!("Could not hash the source files: {}", why))?;
+
+        return match hash == source_hash {
+            true => {
+                println!("Source files match `{}`", path.display());
+                Ok(())
+            }
+            false => Err(format!(
+                "Source files in `{}` do not match `{}`",
+                source_path,
+                path.display()
+            )),
+        };
+    }
+
+    match ProgEnum::deserialize_with_limits(&mut reader, &ir::ReadLimits::default())? {
         ProgEnum::Bn128Program(p) => cli_inspect(p, sub_matches),
         ProgEnum::Bls12_377Program(p) => cli_inspect(p, sub_matches),
         ProgEnum::Bls12_381Program(p) => cli_inspect(p, sub_matches),
@@ -39,7 +39,7 @@ pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
 
     let mut reader = BufReader::new(file);
 
-    match ProgEnum::deserialize(&mut reader)? {
+    match ProgEnum::deserialize_with_limits(&mut reader, &ir::ReadLimits::default())? {
         ProgEnum::PallasProgram(p) => cli_nova_setup(p, sub_matches),
         ProgEnum::VestaProgram(p) => cli_nova_setup(p, sub_matches),
         _ => Err("Nova is only supported for the following curves: [\"pallas\", \"vesta\"]".into()),
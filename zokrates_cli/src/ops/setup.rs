@@ -98,7 +98,7 @@ pub fn exec(sub_matches: &ArgMatches) -> Result<(), String> {
         File::open(path).map_err(|why| format!("Couldn't open {}: {}", path.display(), why))?;
 
     let mut reader = BufReader::new(file);
-    let prog = ProgEnum::deserialize(&mut reader)?;
+    let prog = ProgEnum::deserialize_with_limits(&mut reader, &ir::ReadLimits::default())?;
 
     let parameters = Parameters::try_from((
         sub_matches.value_of("backend").unwrap(),
@@ -1098,12 +1098,65 @@ impl<'ast, T: Field> Flattener<'ast, T> {
                     ),
                 )]
             }
+            FlatEmbed::LimbDecompose => {
+                let radix_bits = generics[0] as usize;
+                let limbs = generics[1] as usize;
+                let bitwidth = T::get_required_bits();
+
+                // decompose the input to the bitwidth of the field
+                let bits = self.get_bits_unchecked(
+                    &params.pop().unwrap(),
+                    bitwidth,
+                    bitwidth,
+                    statements_flattened,
+                    RuntimeError::Sum,
+                );
+
+                // check that this decomposition does not overflow the field, so that the limbs are unique
+                self.enforce_constant_le_check_bits(
+                    statements_flattened,
+                    &bits,
+                    &T::max_value().to_bits_be(),
+                    RuntimeError::Le,
+                );
+
+                // the most significant limb gets the bits which are left over by the other limbs
+                let msb_bits = bitwidth - radix_bits * (limbs - 1);
+
+                std::iter::once(&bits[..msb_bits])
+                    .chain(bits[msb_bits..].chunks(radix_bits))
+                    .map(|limb| {
+                        FlatUExpression::with_field(flat_expression_from_bits(limb.to_vec()))
+                    })
+                    .collect()
+            }
             funct => match funct {
                 FlatEmbed::Unpack => self.flatten_embed_call_aux(
                     statements_flattened,
                     params,
                     unpack_to_bitwidth(generics[0] as usize),
                 ),
+                FlatEmbed::Keccak256 => self.flatten_embed_call_aux(
+                    statements_flattened,
+                    params,
+                    keccak256(generics[0] as usize),
+                ),
+                FlatEmbed::Poseidon => match poseidon(generics[0] as usize) {
+                    Ok(funct) => self.flatten_embed_call_aux(statements_flattened, params, funct),
+                    // the curve is checked on import and the arity during propagation, which codegen relies on
+                    Err(e) => unreachable!("{}", e),
+                },
+                FlatEmbed::ConditionalInverse => {
+                    self.flatten_embed_call_aux(statements_flattened, params, conditional_inverse())
+                }
+                FlatEmbed::ScalarMul => self.flatten_embed_call_aux(
+                    statements_flattened,
+                    params,
+                    scalar_mul(generics[0] as usize),
+                ),
+                FlatEmbed::EuclideanDiv => {
+                    self.flatten_embed_call_aux(statements_flattened, params, euclidean_div())
+                }
                 #[cfg(feature = "bellman")]
                 FlatEmbed::Sha256Round => {
                     self.flatten_embed_call_aux(statements_flattened, params, sha256_round())
@@ -2954,7 +3007,14 @@ impl<'ast, T: Field> Flattener<'ast, T> {
 
         statements_flattened.set_span(backup_span);
 
-        Parameter::new(variable, parameter.private).span(span)
+        let name = match &parameter.id.id {
+            Identifier::Source(id) => Some(id.to_string()),
+            Identifier::Internal(..) => None,
+        };
+
+        Parameter::new(variable, parameter.visibility)
+            .with_name(name)
+            .span(span)
     }
 
     fn issue_new_variable(&mut self) -> Variable {
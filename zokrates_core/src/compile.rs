@@ -15,7 +15,7 @@ use std::path::{Path, PathBuf};
 use typed_arena::Arena;
 use zokrates_analysis::{self, analyse};
 use zokrates_ast::ir::{self, from_flat::from_flat};
-use zokrates_ast::typed::abi::Abi;
+use zokrates_ast::typed::{abi::Abi, ConcreteType};
 use zokrates_ast::untyped::{Module, OwnedModuleId, Program};
 use zokrates_ast::zir::ZirProgram;
 use zokrates_codegen::from_program_and_config;
@@ -181,13 +181,19 @@ pub fn compile<'ast, T: Field, E: Into<imports::Error>>(
     let (typed_ast, abi): (zokrates_ast::zir::ZirProgram<'_, T>, _) =
         check_with_arena(source, location, resolver, &config, arena)?;
 
+    // the embeds are inlined by the flattener, so record them beforehand
+    let embeds_used = typed_ast.embeds_used();
+
     // flatten input program
     log::debug!("Flatten");
     let program_flattened = from_program_and_config(typed_ast);
 
     // convert to ir
     log::debug!("Convert to IR");
-    let ir_prog = from_flat(program_flattened);
+    let ir_prog = ir::ProgIterator {
+        embeds_used,
+        ..from_flat(program_flattened).with_returns(return_slots(&abi))
+    };
 
     // optimize
     log::debug!("Optimise IR");
@@ -202,6 +208,19 @@ pub fn compile<'ast, T: Field, E: Into<imports::Error>>(
     })
 }
 
+/// the return slots of a program with this abi: one per element if it returns a tuple, a single one otherwise
+fn return_slots(abi: &Abi) -> Vec<ir::ReturnSlot> {
+    match &abi.output {
+        ConcreteType::Tuple(t) => t
+            .elements
+            .iter()
+            .cloned()
+            .map(ir::ReturnSlot::new)
+            .collect(),
+        ty => vec![ir::ReturnSlot::new(ty.clone())],
+    }
+}
+
 pub fn check<T: Field, E: Into<imports::Error>>(
     source: String,
     location: FilePath,
@@ -345,6 +364,39 @@ mod test {
         assert!(res.is_ok());
     }
 
+    #[test]
+    fn embeds_used() {
+        use zokrates_ast::common::FlatEmbed;
+
+        let source = r#"
+            from "EMBED" import unpack, conditional_inverse;
+            def main(field x) -> bool[8] {
+                (field, bool) r = conditional_inverse(x);
+                return unpack::<8>(r.0);
+            }
+        "#
+        .to_string();
+
+        let arena = Arena::new();
+
+        let prog = compile::<Bn128Field, io::Error>(
+            source,
+            "./path/to/file".into(),
+            None::<&dyn Resolver<io::Error>>,
+            CompileConfig::default(),
+            &arena,
+        )
+        .unwrap()
+        .prog();
+
+        assert_eq!(
+            prog.embeds_used,
+            [FlatEmbed::Unpack, FlatEmbed::ConditionalInverse]
+                .into_iter()
+                .collect()
+        );
+    }
+
     mod abi {
         use super::*;
         use zokrates_ast::typed::abi::*;
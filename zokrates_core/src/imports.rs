@@ -13,6 +13,7 @@ use std::path::{Path, PathBuf};
 use zokrates_ast::untyped::*;
 
 use typed_arena::Arena;
+use zokrates_ast::common::embed::check_poseidon_field;
 use zokrates_ast::common::{FlatEmbed, SourceSpan};
 use zokrates_ast::untyped::types::UnresolvedType;
 use zokrates_common::Resolver;
@@ -143,6 +144,55 @@ impl Importer {
                         }
                     }
                 }
+                "keccak256" => SymbolDeclaration {
+                    id: symbol.get_alias(),
+                    symbol: Symbol::Flat(FlatEmbed::Keccak256),
+                },
+                "poseidon" => match check_poseidon_field::<T>() {
+                    Err(e) => {
+                        return Err(CompileErrorInner::ImportError(
+                            Error::new(e).with_span(Some(span)),
+                        )
+                        .in_file(location)
+                        .into());
+                    }
+                    Ok(()) => SymbolDeclaration {
+                        id: symbol.get_alias(),
+                        symbol: Symbol::Flat(FlatEmbed::Poseidon),
+                    },
+                },
+                "limb_decompose" => SymbolDeclaration {
+                    id: symbol.get_alias(),
+                    symbol: Symbol::Flat(FlatEmbed::LimbDecompose),
+                },
+                "conditional_inverse" => SymbolDeclaration {
+                    id: symbol.get_alias(),
+                    symbol: Symbol::Flat(FlatEmbed::ConditionalInverse),
+                },
+                "euclidean_div" => SymbolDeclaration {
+                    id: symbol.get_alias(),
+                    symbol: Symbol::Flat(FlatEmbed::EuclideanDiv),
+                },
+                "scalar_mul" => {
+                    use zokrates_field::Bn128Field;
+                    if T::id() != Bn128Field::id() {
+                        return Err(CompileErrorInner::ImportError(
+                            Error::new(format!(
+                                "`scalar_mul` is expected to be compiled over `{}` curve, but found `{}`",
+                                Bn128Field::name(),
+                                T::name()
+                            ))
+                            .with_span(Some(span)),
+                        )
+                        .in_file(location)
+                        .into());
+                    } else {
+                        SymbolDeclaration {
+                            id: symbol.get_alias(),
+                            symbol: Symbol::Flat(FlatEmbed::ScalarMul),
+                        }
+                    }
+                }
                 "unpack" => SymbolDeclaration {
                     id: symbol.get_alias(),
                     symbol: Symbol::Flat(FlatEmbed::Unpack),
@@ -87,6 +87,8 @@ mod tests {
                 ),
             ],
             return_count: 0,
+            returns: vec![],
+            embeds_used: Default::default(),
             arguments: vec![],
             solvers: vec![],
         };
@@ -127,6 +129,8 @@ mod tests {
                 constraint.clone(),
             ],
             return_count: 0,
+            returns: vec![],
+            embeds_used: Default::default(),
             arguments: vec![],
             solvers: vec![],
         };
@@ -145,6 +149,8 @@ mod tests {
                 ),
             ],
             return_count: 0,
+            returns: vec![],
+            embeds_used: Default::default(),
             arguments: vec![],
             solvers: vec![],
         };
@@ -16,20 +16,23 @@ use self::duplicate::DuplicateOptimizer;
 use self::redefinition::RedefinitionOptimizer;
 use self::tautology::TautologyOptimizer;
 
-use zokrates_ast::ir::{ProgIterator, Statement};
+use zokrates_ast::ir::{ProgIterator, RemoveTrivialConstraints, Statement};
 use zokrates_field::Field;
 
 pub fn optimize<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>>(
     p: ProgIterator<'ast, T, I>,
 ) -> ProgIterator<'ast, T, impl IntoIterator<Item = Statement<'ast, T>>> {
     // remove redefinitions
-    log::debug!("Optimizer: Remove redefinitions and tautologies and directives and duplicates");
+    log::debug!(
+        "Optimizer: Remove redefinitions and tautologies and trivial constraints and directives and duplicates"
+    );
 
     // define all optimizer steps
     let mut redefinition_optimizer = RedefinitionOptimizer::init(&p);
     let mut tautologies_optimizer = TautologyOptimizer;
     let mut directive_optimizer = DirectiveOptimizer::default();
     let mut canonicalizer = Canonicalizer;
+    let mut trivial_constraints_remover = RemoveTrivialConstraints::default();
     let mut duplicate_optimizer = DuplicateOptimizer::default();
 
     use zokrates_ast::ir::folder::Folder;
@@ -51,9 +54,12 @@ pub fn optimize<'ast, T: Field, I: IntoIterator<Item = Statement<'ast, T>>>(
             .flat_map(move |s| redefinition_optimizer.fold_statement(s))
             .flat_map(move |s| tautologies_optimizer.fold_statement(s))
             .flat_map(move |s| canonicalizer.fold_statement(s))
+            .flat_map(move |s| trivial_constraints_remover.fold_statement(s))
             .flat_map(move |s| directive_optimizer.fold_statement(s))
             .flat_map(move |s| duplicate_optimizer.fold_statement(s)),
         return_count: p.return_count,
+        returns: p.returns,
+        embeds_used: p.embeds_used,
         module_map: p.module_map,
         solvers: p.solvers,
     };
@@ -270,20 +270,24 @@ mod tests {
 
         let p: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![
                 Statement::definition(y, x.id),
                 Statement::definition(out, y),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
         let optimized: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![Statement::definition(out, x.id)],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
@@ -301,9 +305,11 @@ mod tests {
 
         let p: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![Statement::definition(one, x.id)],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
@@ -334,7 +340,7 @@ mod tests {
 
         let p: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![
                 Statement::definition(y, x.id),
                 Statement::definition(z, y),
@@ -342,17 +348,21 @@ mod tests {
                 Statement::definition(out, z),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
         let optimized: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![
                 Statement::constraint(x.id, x.id, None),
                 Statement::definition(out, x.id),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
@@ -385,7 +395,7 @@ mod tests {
 
         let p: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![
                 Statement::definition(y, x.id),
                 Statement::definition(t, Bn128Field::from(1)),
@@ -395,17 +405,21 @@ mod tests {
                 Statement::definition(out_1, w),
             ],
             return_count: 2,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
         let optimized: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![
                 Statement::definition(out_0, x.id),
                 Statement::definition(out_1, Bn128Field::from(1)),
             ],
             return_count: 2,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
@@ -438,7 +452,7 @@ mod tests {
 
         let p: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x, y],
+            arguments: vec![x.clone(), y.clone()],
             statements: vec![
                 Statement::definition(a, LinComb::from(x.id) + LinComb::from(y.id)),
                 Statement::definition(
@@ -457,12 +471,14 @@ mod tests {
                 Statement::definition(r, LinComb::from(a) + LinComb::from(b) + LinComb::from(c)),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
         let expected: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x, y],
+            arguments: vec![x.clone(), y.clone()],
             statements: vec![
                 Statement::constraint(
                     LinComb::summand(6, x.id) + LinComb::summand(6, y.id),
@@ -480,6 +496,8 @@ mod tests {
                 ),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
@@ -510,12 +528,14 @@ mod tests {
 
         let p: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x, y],
+            arguments: vec![x.clone(), y.clone()],
             statements: vec![
                 Statement::definition(z, QuadComb::new(LinComb::from(x.id), LinComb::from(y.id))),
                 Statement::definition(z, LinComb::from(x.id)),
             ],
             return_count: 0,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
@@ -540,12 +560,14 @@ mod tests {
 
         let p: Prog<Bn128Field> = Prog {
             module_map: Default::default(),
-            arguments: vec![x],
+            arguments: vec![x.clone()],
             statements: vec![
                 Statement::constraint(x.id, Bn128Field::from(1), None),
                 Statement::constraint(x.id, Bn128Field::from(2), None),
             ],
             return_count: 1,
+            returns: vec![],
+            embeds_used: Default::default(),
             solvers: vec![],
         };
 
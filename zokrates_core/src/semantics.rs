@@ -9,7 +9,9 @@ use std::collections::{btree_map::Entry, BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fmt;
 use std::path::PathBuf;
 use zokrates_ast::common::expressions::ValueExpression;
-use zokrates_ast::common::{FormatString, ModuleMap, SourceMetadata, SourceSpan, WithSpan};
+use zokrates_ast::common::{
+    FormatString, ModuleMap, SourceMetadata, SourceSpan, Visibility, WithSpan,
+};
 use zokrates_ast::typed::types::{GGenericsAssignment, GTupleType, GenericsAssignment};
 use zokrates_ast::typed::SourceIdentifier;
 use zokrates_ast::typed::*;
@@ -1186,10 +1188,13 @@ impl<'ast, T: Field> Checker<'ast, T> {
                         }
                     };
 
-                    arguments_checked.push(
-                        DeclarationParameter::new(decl_v, arg.is_private.unwrap_or(false))
-                            .with_span(span),
-                    );
+                    let visibility = match arg.is_private.unwrap_or(false) {
+                        true => Visibility::Private,
+                        false => Visibility::Public,
+                    };
+
+                    arguments_checked
+                        .push(DeclarationParameter::new(decl_v, visibility).with_span(span));
                 }
 
                 let mut found_return = false;
@@ -1908,7 +1913,12 @@ impl<'ast, T: Field> Checker<'ast, T> {
                 ))
             }
             Statement::Log(l, expressions) => {
-                let l = FormatString::from(l);
+                let l = FormatString::parse(l).map_err(|e| {
+                    vec![ErrorInner {
+                        span: Some(span),
+                        message: e.to_string(),
+                    }]
+                })?;
 
                 let expressions = expressions
                     .into_iter()
@@ -1927,14 +1937,10 @@ impl<'ast, T: Field> Checker<'ast, T> {
                     }
                 }
 
-                if expressions.len() != l.len() {
+                if let Err(e) = l.validate_arity(expressions.len()) {
                     errors.push(ErrorInner {
                         span: Some(span),
-                        message: format!(
-                            "Wrong argument count in log call: expected {}, got {}",
-                            l.len(),
-                            expressions.len()
-                        ),
+                        message: e.to_string(),
                     });
                 }
 
@@ -41,6 +41,89 @@ mod tests {
             assert!(bits[253]);
         }
 
+        #[cfg(feature = "rand")]
+        #[test]
+        fn random() {
+            use num_traits::ToPrimitive;
+            use rand_0_8::{rngs::StdRng, SeedableRng};
+
+            let mut rng = StdRng::seed_from_u64(42);
+            let modulus = FieldPrime::max_value().to_biguint() + 1u32;
+
+            // sort 4000 samples into 8 ranges of equal width, each expected to get 500 of them
+            let mut buckets = [0; 8];
+            for _ in 0..4000 {
+                let v = FieldPrime::random(&mut rng).to_biguint();
+                buckets[(v * 8u32 / &modulus).to_usize().unwrap()] += 1;
+            }
+
+            // the standard deviation of each count is about 21
+            assert!(
+                buckets.iter().all(|count| (400..600).contains(count)),
+                "{:?}",
+                buckets
+            );
+        }
+
+        #[test]
+        fn batch_inverse() {
+            let values: Vec<FieldPrime> = vec![
+                FieldPrime::from(2),
+                FieldPrime::from(0),
+                FieldPrime::from(-1),
+                FieldPrime::max_value(),
+                FieldPrime::from(0),
+                FieldPrime::from("123456789123456789123456789"),
+            ];
+
+            let mut inverses = values.clone();
+            FieldPrime::batch_inverse(&mut inverses);
+
+            assert_eq!(
+                inverses,
+                values
+                    .iter()
+                    .map(|v| v.inverse_mul().unwrap_or_else(FieldPrime::zero))
+                    .collect::<Vec<_>>()
+            );
+
+            let mut empty: Vec<FieldPrime> = vec![];
+            FieldPrime::batch_inverse(&mut empty);
+            assert!(empty.is_empty());
+        }
+
+        #[test]
+        fn bytes() {
+            assert_eq!(FieldPrime::byte_width(), 32);
+
+            let one = FieldPrime::one();
+            let mut be = vec![0; 32];
+            be[31] = 1;
+            assert_eq!(one.to_bytes_be(), be);
+            assert_eq!(FieldPrime::from_bytes_be(&be).unwrap(), one);
+
+            let mut le = vec![0; 32];
+            le[0] = 1;
+            assert_eq!(one.to_bytes_le(), le);
+            assert_eq!(FieldPrime::from_bytes_le(&le).unwrap(), one);
+
+            let max = FieldPrime::max_value();
+            assert_eq!(FieldPrime::from_bytes_be(&max.to_bytes_be()).unwrap(), max);
+            assert_eq!(FieldPrime::from_bytes_le(&max.to_bytes_le()).unwrap(), max);
+        }
+
+        #[test]
+        fn bytes_out_of_range() {
+            // the modulus itself is rejected
+            let modulus = (FieldPrime::max_value().to_biguint() + 1u32).to_bytes_be();
+            assert!(FieldPrime::from_bytes_be(&modulus).is_err());
+            assert!(FieldPrime::from_bytes_be(&[0xff; 32]).is_err());
+
+            // inputs of the wrong width are rejected
+            assert!(FieldPrime::from_bytes_be(&[1]).is_err());
+            assert!(FieldPrime::from_bytes_le(&[0; 33]).is_err());
+        }
+
         #[test]
         fn addition() {
             assert_eq!(
@@ -194,6 +277,14 @@ mod tests {
             assert_eq!(FieldPrime::get_required_bits(), 254);
         }
 
+        #[test]
+        fn modulus_bits() {
+            assert_eq!(FieldPrime::modulus_bits(), 254);
+            assert_eq!(crate::Bls12_381Field::modulus_bits(), 255);
+            assert_eq!(crate::Bls12_377Field::modulus_bits(), 253);
+            assert_eq!(crate::Bw6_761Field::modulus_bits(), 377);
+        }
+
         #[test]
         fn bits() {
             assert_eq!(FieldPrime::from(0).bits(), 1);
@@ -128,6 +128,31 @@ pub trait Field:
     fn to_dec_string(&self) -> String;
     /// Returns the multiplicative inverse, i.e.: self * self.inverse_mul() = Self::one()
     fn inverse_mul(&self) -> Option<Self>;
+    /// Replaces each element of `values` with its multiplicative inverse, leaving zeros as zeros
+    /// Uses Montgomery's trick, so that the whole slice is inverted with a single inversion and three multiplications per element
+    fn batch_inverse(values: &mut [Self]) {
+        // the product of the non-zero elements preceding each element
+        let mut prefixes = Vec::with_capacity(values.len());
+        let mut product = Self::one();
+        for v in values.iter() {
+            prefixes.push(product);
+            if !v.is_zero() {
+                product = product * v;
+            }
+        }
+
+        // the inverse of the product of the non-zero elements up to the current element
+        let mut inverse = product
+            .inverse_mul()
+            .expect("a product of non-zero elements is non-zero");
+        for (v, prefix) in values.iter_mut().zip(prefixes).rev() {
+            if !v.is_zero() {
+                let next = inverse * &*v;
+                *v = inverse * prefix;
+                inverse = next;
+            }
+        }
+    }
     /// Returns the smallest value that can be represented by this field type.
     fn min_value() -> Self;
     /// Returns the largest value that can be represented by this field type.
@@ -139,6 +164,60 @@ pub trait Field:
     fn to_bits_be(&self) -> Vec<bool>;
     /// Returns the number of bits required to represent any element of this field type.
     fn get_required_bits() -> usize;
+    /// Returns the exact bit length of the prime modulus of this field type.
+    fn modulus_bits() -> u32 {
+        Self::get_required_bits() as u32
+    }
+    /// Returns the number of bytes used by the fixed width byte representations of this field type
+    fn byte_width() -> usize {
+        (Self::get_required_bits() + 7) / 8
+    }
+    /// Returns this `Field`'s contents as big-endian bytes, left-padded to `Self::byte_width()`
+    fn to_bytes_be(&self) -> Vec<u8> {
+        let bytes = self.to_biguint().to_bytes_be();
+        let mut res = vec![0; Self::byte_width() - bytes.len()];
+        res.extend(bytes);
+        res
+    }
+    /// Returns this `Field`'s contents as little-endian bytes, right-padded to `Self::byte_width()`
+    fn to_bytes_le(&self) -> Vec<u8> {
+        let mut res = self.to_biguint().to_bytes_le();
+        res.resize(Self::byte_width(), 0);
+        res
+    }
+    /// Tries to read an element of this `Field` from exactly `Self::byte_width()` big-endian bytes
+    /// Values greater or equal to the modulus are rejected rather than reduced
+    fn from_bytes_be(bytes: &[u8]) -> Result<Self, FieldParseError> {
+        if bytes.len() != Self::byte_width() {
+            return Err(FieldParseError);
+        }
+        Self::try_from_str(&BigUint::from_bytes_be(bytes).to_str_radix(16), 16)
+    }
+    /// Tries to read an element of this `Field` from exactly `Self::byte_width()` little-endian bytes
+    /// Values greater or equal to the modulus are rejected rather than reduced
+    fn from_bytes_le(bytes: &[u8]) -> Result<Self, FieldParseError> {
+        if bytes.len() != Self::byte_width() {
+            return Err(FieldParseError);
+        }
+        Self::try_from_str(&BigUint::from_bytes_le(bytes).to_str_radix(16), 16)
+    }
+    /// Returns an element of this `Field` drawn uniformly at random from `rng`
+    /// Candidates of `Self::get_required_bits()` random bits are drawn until one is smaller than the modulus, which avoids the bias of reducing a random number modulo the modulus
+    #[cfg(feature = "rand")]
+    fn random<R: rand_0_8::RngCore>(rng: &mut R) -> Self {
+        let mut bytes = vec![0; Self::byte_width()];
+        // the bits of the most significant byte above the bit length of the modulus, which are always cleared
+        let excess = Self::byte_width() * 8 - Self::get_required_bits();
+
+        loop {
+            rng.fill_bytes(&mut bytes);
+            bytes[0] &= 0xff >> excess;
+
+            if let Ok(v) = Self::from_bytes_be(&bytes) {
+                return v;
+            }
+        }
+    }
     /// Tries to parse a string into this representation
     fn try_from_dec_str(s: &str) -> Result<Self, FieldParseError>;
     fn try_from_str(s: &str, radix: u32) -> Result<Self, FieldParseError>;
@@ -707,3 +786,48 @@ pub use bw6_761::FieldPrime as Bw6_761Field;
 pub use dummy_curve::FieldPrime as DummyCurveField;
 pub use pallas::FieldPrime as PallasField;
 pub use vesta::FieldPrime as VestaField;
+
+/// A computation which is generic over the field type, to be run with `dispatch_by_curve_id`
+pub trait FieldDispatch {
+    type Output;
+
+    fn dispatch<T: Field>(self) -> Self::Output;
+}
+
+/// Runs `f` with the field whose id is `id`, returning `None` if no field has this id
+pub fn dispatch_by_curve_id<F: FieldDispatch>(id: [u8; 4], f: F) -> Option<F::Output> {
+    match id {
+        m if m == Bls12_381Field::id() => Some(f.dispatch::<Bls12_381Field>()),
+        m if m == Bn128Field::id() => Some(f.dispatch::<Bn128Field>()),
+        m if m == Bls12_377Field::id() => Some(f.dispatch::<Bls12_377Field>()),
+        m if m == Bw6_761Field::id() => Some(f.dispatch::<Bw6_761Field>()),
+        m if m == PallasField::id() => Some(f.dispatch::<PallasField>()),
+        m if m == VestaField::id() => Some(f.dispatch::<VestaField>()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Name;
+
+    impl FieldDispatch for Name {
+        type Output = &'static str;
+
+        fn dispatch<T: Field>(self) -> Self::Output {
+            T::name()
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        assert_eq!(dispatch_by_curve_id(Bn128Field::id(), Name), Some("bn128"));
+        assert_eq!(
+            dispatch_by_curve_id(VestaField::id(), Name),
+            Some(VestaField::name())
+        );
+        assert_eq!(dispatch_by_curve_id([0; 4], Name), None);
+    }
+}
@@ -3,6 +3,9 @@ use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::fmt;
 use zokrates_abi::{Decode, Value};
+use zokrates_analysis::ZirPropagationError;
+use zokrates_ast::common::embed::baby_jubjub_add;
+use zokrates_ast::common::{FormatString, Span};
 use zokrates_ast::ir::{
     LinComb, Parameter, QuadComb, RuntimeError, Solver, Statement, Variable, Witness,
 };
@@ -11,19 +14,44 @@ use zokrates_field::Field;
 
 pub type ExecutionResult<T> = Result<Witness<T>, Error>;
 
+/// The default number of steps solvers can take during an execution
+pub const DEFAULT_MAX_SOLVER_STEPS: u64 = 1 << 32;
+
+/// Bounds on the work solvers can do during an execution, so that malicious or buggy solvers cannot hang it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolverLimits {
+    /// the number of steps shared by all solver calls of an execution
+    /// Each call to a builtin solver is a step, and each statement and expression of a zir solver is a step.
+    pub max_steps: u64,
+}
+
+impl Default for SolverLimits {
+    fn default() -> Self {
+        Self {
+            max_steps: DEFAULT_MAX_SOLVER_STEPS,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct Interpreter {
     /// Whether we should try to give out-of-range bit decompositions when the input is not a single summand.
     /// Used to do targeted testing of `<` flattening, making sure the bit decomposition we base the result on is unique.
     should_try_out_of_range: bool,
+    limits: SolverLimits,
 }
 
 impl Interpreter {
     pub fn try_out_of_range() -> Interpreter {
         Interpreter {
             should_try_out_of_range: true,
+            ..Interpreter::default()
         }
     }
+
+    pub fn with_solver_limits(self, limits: SolverLimits) -> Interpreter {
+        Interpreter { limits, ..self }
+    }
 }
 
 impl Interpreter {
@@ -50,6 +78,35 @@ impl Interpreter {
         arguments: &[Parameter],
         solvers: &[Solver<'ast, T>],
         log_stream: &mut W,
+    ) -> ExecutionResult<T> {
+        self.execute_with_logger(inputs, statements, arguments, solvers, |event| {
+            writeln!(log_stream, "{}", event).map_err(|_| Error::LogStream)?;
+            log_stream.flush().map_err(|_| Error::LogStream)
+        })
+    }
+
+    /// execute the program, passing each log to `on_log` as a structured event instead of printing it
+    pub fn execute_with_log_callback<'ast, T: Field, S: Borrow<Statement<'ast, T>>>(
+        &self,
+        inputs: &[T],
+        statements: impl Iterator<Item = S>,
+        arguments: &[Parameter],
+        solvers: &[Solver<'ast, T>],
+        mut on_log: impl FnMut(LogEvent<T>),
+    ) -> ExecutionResult<T> {
+        self.execute_with_logger(inputs, statements, arguments, solvers, |event| {
+            on_log(event);
+            Ok(())
+        })
+    }
+
+    fn execute_with_logger<'ast, T: Field, S: Borrow<Statement<'ast, T>>>(
+        &self,
+        inputs: &[T],
+        statements: impl Iterator<Item = S>,
+        arguments: &[Parameter],
+        solvers: &[Solver<'ast, T>],
+        mut logger: impl FnMut(LogEvent<T>) -> Result<(), Error>,
     ) -> ExecutionResult<T> {
         if arguments.len() != inputs.len() {
             return Err(Error::WrongInputCount {
@@ -65,6 +122,8 @@ impl Interpreter {
             witness.insert(arg.id, *value);
         }
 
+        let mut remaining_steps = self.limits.max_steps;
+
         for statement in statements {
             match statement.borrow() {
                 Statement::Block(..) => unreachable!(),
@@ -78,7 +137,10 @@ impl Interpreter {
                         let rhs_value = evaluate_lin(&witness, &s.lin).unwrap();
                         if lhs_value != rhs_value {
                             return Err(Error::UnsatisfiedConstraint {
-                                error: s.error.clone(),
+                                error: s.error.clone().map(|e| {
+                                    e.resolve(|v| witness.0.get(v).map(|v| v.to_dec_string()))
+                                }),
+                                span: s.span,
                             });
                         }
                     }
@@ -97,39 +159,50 @@ impl Interpreter {
                                 inputs.pop().unwrap(),
                             ))
                         }
-                        _ => Self::execute_solver(&d.solver, &inputs, solvers),
-                    }
-                    .map_err(Error::Solver)?;
+                        _ => Self::execute_solver_with_limit(
+                            &d.solver,
+                            &inputs,
+                            solvers,
+                            &mut remaining_steps,
+                        )
+                        .map_err(|e| match e {
+                            Error::SolverStepLimit { .. } => Error::SolverStepLimit {
+                                max_steps: self.limits.max_steps,
+                            },
+                            e => e,
+                        }),
+                    }?;
 
                     for (i, o) in d.outputs.iter().enumerate() {
                         witness.insert(*o, res[i]);
                     }
                 }
                 Statement::Log(s) => {
-                    let mut parts = s.format_string.parts.iter();
-
-                    write!(log_stream, "{}", parts.next().unwrap())
-                        .map_err(|_| Error::LogStream)?;
-
-                    for ((t, e), part) in s.expressions.iter().zip(parts) {
-                        let values: Vec<_> = e
-                            .iter()
-                            .map(|e| evaluate_lin(&witness, e).unwrap())
-                            .collect();
-
-                        write!(
-                            log_stream,
-                            "{}",
-                            Value::decode(values, t.clone()).into_serde_json()
-                        )
-                        .map_err(|_| Error::LogStream)?;
+                    let values = s
+                        .expressions
+                        .iter()
+                        .map(|(t, e)| {
+                            let values: Vec<_> = e
+                                .iter()
+                                .map(|e| evaluate_lin(&witness, e).unwrap())
+                                .collect();
 
-                        write!(log_stream, "{}", part).map_err(|_| Error::LogStream)?;
-                    }
+                            Value::decode(values, t.clone())
+                        })
+                        .collect::<Vec<_>>();
 
-                    writeln!(log_stream).map_err(|_| Error::LogStream)?;
+                    s.format_string.validate_arity(values.len()).map_err(|e| {
+                        Error::InvalidLog {
+                            error: e.to_string(),
+                            span: s.span,
+                        }
+                    })?;
 
-                    log_stream.flush().map_err(|_| Error::LogStream)?;
+                    logger(LogEvent {
+                        span: s.span,
+                        format: s.format_string.clone(),
+                        values,
+                    })?;
                 }
             }
         }
@@ -169,13 +242,35 @@ impl Interpreter {
         inputs: &[T],
         solvers: &[Solver<'ast, T>],
     ) -> Result<Vec<T>, String> {
+        let mut unlimited = u64::MAX;
+
+        Self::execute_solver_with_limit(solver, inputs, solvers, &mut unlimited).map_err(
+            |e| match e {
+                Error::Solver(e) => e,
+                e => e.to_string(),
+            },
+        )
+    }
+
+    /// execute `solver` like `execute_solver`, taking its steps from `remaining_steps`
+    /// Fails with `Error::SolverStepLimit` if the solver takes more than `remaining_steps` steps, see `SolverLimits`
+    pub fn execute_solver_with_limit<'ast, T: Field>(
+        solver: &Solver<'ast, T>,
+        inputs: &[T],
+        solvers: &[Solver<'ast, T>],
+        remaining_steps: &mut u64,
+    ) -> Result<Vec<T>, Error> {
         let solver = match solver {
-            Solver::Ref(call) => solvers
-                .get(call.index)
-                .ok_or_else(|| format!("Could not get solver at index {}", call.index))?,
+            Solver::Ref(call) => solvers.get(call.index).ok_or_else(|| {
+                Error::Solver(format!("Could not get solver at index {}", call.index))
+            })?,
             s => s,
         };
 
+        let limit = Error::SolverStepLimit {
+            max_steps: *remaining_steps,
+        };
+
         let (expected_input_count, expected_output_count) = solver.get_signature();
         assert_eq!(inputs.len(), expected_input_count);
 
@@ -221,13 +316,19 @@ impl Interpreter {
                             )),
                         },
                     })
-                    .collect::<Result<HashMap<_, _>, _>>()?;
+                    .collect::<Result<HashMap<_, _>, _>>()
+                    .map_err(Error::Solver)?;
 
-                let mut propagator = zokrates_analysis::ZirPropagator::with_constants(constants);
+                let mut propagator = zokrates_analysis::ZirPropagator::with_constants(constants)
+                    .with_step_limit(*remaining_steps);
 
-                let folded_function = propagator
-                    .fold_function(func.clone())
-                    .map_err(|e| e.to_string())?;
+                let folded_function = match propagator.fold_function(func.clone()) {
+                    Ok(f) => f,
+                    Err(ZirPropagationError::StepLimitExceeded(_)) => return Err(limit),
+                    Err(e) => return Err(Error::Solver(e.to_string())),
+                };
+
+                *remaining_steps -= propagator.steps();
 
                 assert_eq!(folded_function.statements.len(), 1);
                 if let zokrates_ast::zir::ZirStatement::Return(v) =
@@ -246,6 +347,7 @@ impl Interpreter {
                     unreachable!()
                 }
             }
+            _ if *remaining_steps == 0 => return Err(limit),
             Solver::ConditionEq => match inputs[0].is_zero() {
                 true => vec![T::zero(), T::one()],
                 false => vec![
@@ -253,6 +355,14 @@ impl Interpreter {
                     T::one().checked_div(&inputs[0]).unwrap_or_else(T::one),
                 ],
             },
+            Solver::ConditionalInverse => match inputs[0].is_zero() {
+                true => vec![T::zero(), T::one()],
+                false => vec![T::one().checked_div(&inputs[0]).unwrap(), T::zero()],
+            },
+            Solver::BabyJubjubAdd => {
+                let (x, y) = baby_jubjub_add((inputs[0], inputs[1]), (inputs[2], inputs[3]));
+                vec![x, y]
+            }
             Solver::Bits(bit_width) => {
                 // get all the bits
                 let bits = inputs[0].to_bits_be();
@@ -295,6 +405,13 @@ impl Interpreter {
             }
 
             Solver::Div => vec![inputs[0].checked_div(&inputs[1]).unwrap_or_else(T::one)],
+            Solver::Polynomial(p) => {
+                // a malformed polynomial would silently ignore some of its inputs
+                solver
+                    .validate()
+                    .map_err(|e| Error::Solver(e.to_string()))?;
+                vec![p.evaluate(inputs)]
+            }
             Solver::EuclideanDiv => {
                 use num::CheckedDiv;
 
@@ -346,21 +463,70 @@ impl Interpreter {
             _ => unreachable!("unexpected solver"),
         };
 
+        if !matches!(solver, Solver::Zir(..)) {
+            *remaining_steps -= 1;
+        }
+
         assert_eq!(res.len(), expected_output_count);
 
         Ok(res)
     }
 }
 
+/// A log statement reached during an execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEvent<T> {
+    /// the span of the source code which produced the log, if known
+    pub span: Option<Span>,
+    pub format: FormatString,
+    /// the values of the arguments of the format string, in order
+    pub values: Vec<Value<T>>,
+}
+
+impl<T: Field> fmt::Display for LogEvent<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut parts = self.format.parts.iter();
+
+        write!(f, "{}", parts.next().unwrap())?;
+
+        for (index, part) in self.format.argument_indices().zip(parts) {
+            // events built by hand may lack some arguments, which are shown as their placeholder
+            match self.values.get(index) {
+                Some(value) => write!(f, "{}", value.clone().into_serde_json())?,
+                None => write!(f, "{{{}}}", index)?,
+            }
+            write!(f, "{}", part)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub struct EvaluationError;
 
 #[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Error {
-    UnsatisfiedConstraint { error: Option<RuntimeError> },
+    UnsatisfiedConstraint {
+        error: Option<RuntimeError>,
+        /// the span of the source code which produced the unsatisfied constraint, if known
+        span: Option<Span>,
+    },
     Solver(String),
-    WrongInputCount { expected: usize, received: usize },
+    /// the solvers of an execution took more than `max_steps` steps, see `SolverLimits`
+    SolverStepLimit {
+        max_steps: u64,
+    },
+    WrongInputCount {
+        expected: usize,
+        received: usize,
+    },
     LogStream,
+    /// a log statement whose format string does not match its arguments
+    InvalidLog {
+        error: String,
+        span: Option<Span>,
+    },
 }
 
 fn evaluate_lin<T: Field>(w: &Witness<T>, l: &LinComb<T>) -> Result<T, EvaluationError> {
@@ -377,10 +543,20 @@ pub fn evaluate_quad<T: Field>(w: &Witness<T>, q: &QuadComb<T>) -> Result<T, Eva
     Ok(left * right)
 }
 
+impl Error {
+    /// the span of the source code which caused this error, if known
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Error::UnsatisfiedConstraint { span, .. } | Error::InvalidLog { span, .. } => *span,
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::UnsatisfiedConstraint { ref error } => {
+            Error::UnsatisfiedConstraint { ref error, .. } => {
                 write!(
                     f,
                     "{}",
@@ -399,6 +575,11 @@ impl fmt::Display for Error {
                 }
             }
             Error::Solver(ref e) => write!(f, "Solver error: {}", e),
+            Error::SolverStepLimit { max_steps } => write!(
+                f,
+                "Solver error: step limit of {} exceeded, the program may contain a malicious or buggy solver",
+                max_steps
+            ),
             Error::WrongInputCount { expected, received } => write!(
                 f,
                 "Program takes {} input{} but was passed {} value{}",
@@ -408,6 +589,7 @@ impl fmt::Display for Error {
                 if received == 1 { "" } else { "s" }
             ),
             Error::LogStream => write!(f, "Error writing a log to the log stream"),
+            Error::InvalidLog { ref error, .. } => write!(f, "Invalid log statement: {}", error),
         }
     }
 }
@@ -466,6 +648,106 @@ mod tests {
         }
     }
 
+    mod conditional_inverse {
+        use super::*;
+
+        fn execute(x: Bn128Field) -> Vec<Bn128Field> {
+            Interpreter::execute_solver(&Solver::ConditionalInverse, &[x], &[]).unwrap()
+        }
+
+        #[test]
+        fn zero() {
+            assert_eq!(
+                execute(Bn128Field::from(0)),
+                vec![Bn128Field::from(0), Bn128Field::from(1)]
+            );
+        }
+
+        #[test]
+        fn one() {
+            assert_eq!(
+                execute(Bn128Field::from(1)),
+                vec![Bn128Field::from(1), Bn128Field::from(0)]
+            );
+        }
+
+        #[test]
+        fn non_zero() {
+            let x = Bn128Field::try_from_dec_str(
+                "8472903847523984752398475029384750293847502938475029384750293847",
+            )
+            .unwrap();
+            let r = execute(x);
+
+            assert_eq!(r[0] * x, Bn128Field::from(1));
+            assert_eq!(r[1], Bn128Field::from(0));
+        }
+    }
+
+    #[test]
+    fn polynomial() {
+        use zokrates_ast::common::Polynomial;
+
+        // 2 * i0^2 + 3 * i0 * i1 - i1 + 7
+        let solver = Solver::Polynomial(Polynomial::new(
+            2,
+            vec![
+                (Bn128Field::from(2), vec![2, 0]),
+                (Bn128Field::from(3), vec![1, 1]),
+                (Bn128Field::from(-1), vec![0, 1]),
+                (Bn128Field::from(7), vec![0, 0]),
+            ],
+        ));
+
+        let evaluate = |x: i32, y: i32| {
+            Interpreter::execute_solver(&solver, &[Bn128Field::from(x), Bn128Field::from(y)], &[])
+                .unwrap()
+        };
+
+        assert_eq!(evaluate(0, 0), vec![Bn128Field::from(7)]);
+        assert_eq!(evaluate(3, 5), vec![Bn128Field::from(18 + 45 - 5 + 7)]);
+        assert_eq!(evaluate(-1, 2), vec![Bn128Field::from(2 - 6 - 2 + 7)]);
+    }
+
+    #[test]
+    fn invalid_polynomial() {
+        use zokrates_ast::common::Polynomial;
+
+        // a polynomial of two variables whose term only has an exponent for the first one
+        let solver = Solver::Polynomial(Polynomial::new(2, vec![(Bn128Field::from(1), vec![1])]));
+
+        assert!(Interpreter::execute_solver(
+            &solver,
+            &[Bn128Field::from(2), Bn128Field::from(3)],
+            &[]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn baby_jubjub_double() {
+        let g = [
+            "16540640123574156134436876038791482806971768689494387082833631921987005038935",
+            "20819045374670962167435360035096875258406992893633759881276124905556507972311",
+        ]
+        .map(|c| Bn128Field::try_from_dec_str(c).unwrap());
+
+        assert_eq!(
+            Interpreter::execute_solver(&Solver::BabyJubjubAdd, &[g[0], g[1], g[0], g[1]], &[])
+                .unwrap(),
+            vec![
+                Bn128Field::try_from_dec_str(
+                    "17324563846726889236817837922625232543153115346355010501047597319863650987830"
+                )
+                .unwrap(),
+                Bn128Field::try_from_dec_str(
+                    "20022170825455209233733649024450576091402881793145646502279487074566492066831"
+                )
+                .unwrap()
+            ]
+        );
+    }
+
     #[test]
     fn bits_of_one() {
         let inputs = vec![Bn128Field::from(1)];
@@ -524,7 +806,7 @@ mod tests {
 
         // (field i0) -> i0 * i0
         let solver = Solver::Zir(ZirFunction {
-            arguments: vec![Parameter::new(Variable::field_element(id.id.clone()), true)],
+            arguments: vec![Parameter::private(Variable::field_element(id.id.clone()))],
             statements: vec![ZirStatement::ret(vec![FieldElementExpression::mul(
                 FieldElementExpression::Identifier(id.clone()),
                 FieldElementExpression::Identifier(id.clone()),
@@ -552,4 +834,304 @@ mod tests {
         let expected = vec![Bn128Field::from(4)];
         assert_eq!(res, expected);
     }
+
+    #[test]
+    fn solver_step_limit() {
+        use zir::{
+            types::{Signature, Type},
+            FieldElementExpression, Identifier, IdentifierExpression, ZirFunction, ZirStatement,
+        };
+        use zokrates_ast::ir::DirectiveStatement;
+
+        let id = |i: usize| IdentifierExpression::new(Identifier::internal(i));
+
+        // (field i0) -> i1000, where i(n + 1) = i(n) * i(n)
+        let solver = Solver::Zir(ZirFunction {
+            arguments: vec![zir::Parameter::private(zir::Variable::field_element(
+                id(0).id,
+            ))],
+            statements: (0..1000)
+                .map(|i| {
+                    ZirStatement::definition(
+                        zir::Variable::field_element(id(i + 1).id),
+                        FieldElementExpression::mul(
+                            FieldElementExpression::Identifier(id(i)),
+                            FieldElementExpression::Identifier(id(i)),
+                        )
+                        .into(),
+                    )
+                })
+                .chain(std::iter::once(ZirStatement::ret(vec![
+                    FieldElementExpression::Identifier(id(1000)).into(),
+                ])))
+                .collect(),
+            signature: Signature::new()
+                .inputs(vec![Type::FieldElement])
+                .outputs(vec![Type::FieldElement]),
+        });
+
+        let statements: Vec<Statement<Bn128Field>> =
+            vec![Statement::Directive(DirectiveStatement::new(
+                vec![Variable::new(1)],
+                solver,
+                vec![Variable::new(0).into()],
+            ))];
+        let arguments = [Parameter::private(Variable::new(0))];
+        let inputs = [Bn128Field::from(1)];
+
+        let error = Interpreter::default()
+            .with_solver_limits(SolverLimits { max_steps: 100 })
+            .execute(&inputs, statements.iter(), &arguments, &[])
+            .unwrap_err();
+        assert_eq!(error, Error::SolverStepLimit { max_steps: 100 });
+
+        let witness = Interpreter::default()
+            .execute(&inputs, statements.iter(), &arguments, &[])
+            .unwrap();
+        assert_eq!(witness.0[&Variable::new(1)], Bn128Field::from(1));
+    }
+
+    #[test]
+    fn chained_solvers() {
+        use zokrates_ast::common::RefCall;
+        use zokrates_ast::ir::Prog;
+
+        // def main(private _0) -> (~out_0)
+        // # _1, _2 = Ref@0(_0)
+        // _1 == ~out_0
+        let first: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(0))],
+            return_count: 1,
+            statements: vec![
+                Statement::directive(
+                    vec![Variable::new(1), Variable::new(2)],
+                    Solver::Ref(RefCall {
+                        index: 0,
+                        signature: (1, 2),
+                    }),
+                    vec![Variable::new(0).into()],
+                ),
+                Statement::constraint(Variable::new(1), Variable::public(0), None),
+            ],
+            solvers: vec![Solver::ConditionEq],
+            ..Prog::default()
+        };
+
+        // def main(private _3)
+        // # _4 = Ref@0(_3, 2)
+        // 2 * _4 == _3
+        let second: Prog<Bn128Field> = Prog {
+            arguments: vec![Parameter::private(Variable::new(3))],
+            statements: vec![
+                Statement::directive(
+                    vec![Variable::new(4)],
+                    Solver::Ref(RefCall {
+                        index: 0,
+                        signature: (2, 1),
+                    }),
+                    vec![
+                        Variable::new(3).into(),
+                        LinComb::from(Bn128Field::from(2)).into(),
+                    ],
+                ),
+                Statement::constraint(
+                    QuadComb::new(LinComb::from(Bn128Field::from(2)), Variable::new(4).into()),
+                    Variable::new(3),
+                    None,
+                ),
+            ],
+            solvers: vec![Solver::Div],
+            ..Prog::default()
+        };
+
+        // the directive of the second program refers to the solver appended after the solver of the first one
+        let linked = first.chain(second).unwrap().collect();
+        assert_eq!(linked.solvers, vec![Solver::ConditionEq, Solver::Div]);
+
+        let witness = Interpreter::default()
+            .execute(
+                &[Bn128Field::from(3), Bn128Field::from(8)],
+                linked.statements.iter(),
+                &linked.arguments,
+                &linked.solvers,
+            )
+            .unwrap();
+
+        assert_eq!(witness.0[&Variable::public(0)], Bn128Field::from(1));
+        // the intermediate variable of the second program is renumbered after the variables of the first one
+        assert_eq!(witness.0[&Variable::new(8)], Bn128Field::from(4));
+    }
+
+    #[test]
+    fn hoisted_program() {
+        use zokrates_ast::ir::{HoistCommonSubexpressions, Prog};
+
+        let sum = || LinComb::from(Variable::new(0)) + LinComb::from(Variable::new(1));
+
+        // def main(private _0, private _1) -> (~out_0)
+        // (_0 + _1) * (_0 + _1) == _2
+        // (_0 + _1) * _2 == ~out_0
+        let p: Prog<Bn128Field> = Prog {
+            arguments: vec![
+                Parameter::private(Variable::new(0)),
+                Parameter::private(Variable::new(1)),
+            ],
+            return_count: 1,
+            statements: vec![
+                Statement::constraint(QuadComb::new(sum(), sum()), Variable::new(2), None),
+                Statement::constraint(
+                    QuadComb::new(sum(), Variable::new(2).into()),
+                    Variable::public(0),
+                    None,
+                ),
+            ],
+            ..Prog::default()
+        };
+
+        // `_2` occurs twice but is assigned by the first constraint, so only `_0 + _1` is hoisted
+        let (hoisted, count) = HoistCommonSubexpressions::hoist(p, 1);
+        assert_eq!(count, 1);
+
+        let witness = Interpreter::default()
+            .execute(
+                &[Bn128Field::from(1), Bn128Field::from(2)],
+                hoisted.statements.iter(),
+                &hoisted.arguments,
+                &hoisted.solvers,
+            )
+            .unwrap();
+
+        assert_eq!(witness.0[&Variable::public(0)], Bn128Field::from(27));
+    }
+
+    #[test]
+    fn unsatisfied_constraint_span() {
+        use zokrates_ast::common::{SourceSpan, WithSpan};
+
+        let span = Span::Source(SourceSpan::mock());
+
+        // _0 * _0 == 2
+        let statements: Vec<Statement<Bn128Field>> = vec![Statement::constraint(
+            QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+            LinComb::from(Bn128Field::from(2)),
+            Some(RuntimeError::Equal),
+        )
+        .span(Some(span))];
+
+        let error = Interpreter::default()
+            .execute(
+                &[Bn128Field::from(1)],
+                statements.iter(),
+                &[Parameter::private(Variable::new(0))],
+                &[],
+            )
+            .unwrap_err();
+
+        assert_eq!(error.span(), Some(span));
+    }
+
+    #[test]
+    fn assertion_message() {
+        use zokrates_ast::common::{AssertionMessage, FormatString, SourceMetadata};
+
+        // _0 * _0 == 2
+        let statements: Vec<Statement<Bn128Field>> = vec![Statement::constraint(
+            QuadComb::new(Variable::new(0).into(), Variable::new(0).into()),
+            LinComb::from(Bn128Field::from(2)),
+            Some(RuntimeError::SourceAssertionMessage(
+                SourceMetadata::new(String::from("main.zok"), Default::default()),
+                AssertionMessage::new(
+                    FormatString::from("{} is not a square root of 2"),
+                    vec![Variable::new(0)],
+                ),
+            )),
+        )];
+
+        let error = Interpreter::default()
+            .execute(
+                &[Bn128Field::from(3)],
+                statements.iter(),
+                &[Parameter::private(Variable::new(0))],
+                &[],
+            )
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Assertion failed at main.zok:0:0: \"3 is not a square root of 2\""
+        );
+    }
+
+    #[test]
+    fn log_events() {
+        use zokrates_ast::common::{SourceSpan, WithSpan};
+        use zokrates_ast::typed::ConcreteType;
+
+        let span = Span::from(SourceSpan::mock());
+        let statements: Vec<Statement<Bn128Field>> = vec![Statement::log(
+            FormatString::from("x is {}, y is {}"),
+            vec![
+                (ConcreteType::FieldElement, vec![Variable::new(0).into()]),
+                (ConcreteType::Boolean, vec![LinComb::one()]),
+            ],
+        )
+        .span(Some(span))];
+        let arguments = [Parameter::private(Variable::new(0))];
+        let inputs = [Bn128Field::from(42)];
+
+        let mut events = vec![];
+        Interpreter::default()
+            .execute_with_log_callback(&inputs, statements.iter(), &arguments, &[], |e| {
+                events.push(e)
+            })
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![LogEvent {
+                span: Some(span),
+                format: FormatString::from("x is {}, y is {}"),
+                values: vec![Value::Field(Bn128Field::from(42)), Value::Boolean(true)],
+            }]
+        );
+
+        let mut log_stream = vec![];
+        Interpreter::default()
+            .execute_with_log_stream(&inputs, statements.iter(), &arguments, &[], &mut log_stream)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(log_stream).unwrap(),
+            "x is \"42\", y is true\n"
+        );
+    }
+
+    #[test]
+    fn invalid_log() {
+        use zokrates_ast::typed::ConcreteType;
+
+        // a single argument for two placeholders
+        let statements: Vec<Statement<Bn128Field>> = vec![Statement::log(
+            FormatString::from("x is {}, y is {}"),
+            vec![(ConcreteType::FieldElement, vec![Variable::new(0).into()])],
+        )];
+
+        let error = Interpreter::default()
+            .execute(
+                &[Bn128Field::from(42)],
+                statements.iter(),
+                &[Parameter::private(Variable::new(0))],
+                &[],
+            )
+            .unwrap_err();
+        assert!(matches!(error, Error::InvalidLog { .. }));
+
+        // missing values are displayed as their placeholder
+        let event = LogEvent {
+            span: None,
+            format: FormatString::from("x is {}, y is {}"),
+            values: vec![Value::Field(Bn128Field::from(42))],
+        };
+        assert_eq!(event.to_string(), "x is \"42\", y is {1}");
+    }
 }
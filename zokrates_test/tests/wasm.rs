@@ -20,6 +20,8 @@ fn generate_proof() {
         module_map: Default::default(),
         arguments: vec![Parameter::public(Variable::new(0))],
         return_count: 1,
+        returns: vec![],
+        embeds_used: Default::default(),
         statements: vec![Statement::constraint(
             Variable::new(0),
             Variable::new(0),